@@ -30,7 +30,8 @@ pub mod core {
 
 // Re-export most commonly used core types at the root level
 pub use tempered_core::{
-    Email, Password, TwoFaAttemptId, TwoFaCode, TwoFaError, User, UserError, ValidatedUser,
+    Email, EmailDomainPolicy, Identifier, Password, TwoFaAttemptId, TwoFaCode, TwoFaError, User,
+    UserError, UserId, Username, ValidatedUser,
 };
 
 // ============================================================================
@@ -40,15 +41,19 @@ pub use tempered_core::{
 /// Repository trait definitions
 pub mod repositories {
     pub use tempered_core::{
-        BannedTokenStore, BannedTokenStoreError, TwoFaCodeStore, TwoFaCodeStoreError, UserStore,
-        UserStoreError,
+        ApiKeyStore, ApiKeyStoreError, BackupCodeStore, BackupCodeStoreError, BannedTokenStore,
+        BannedTokenStoreError, ProbeableStore, RateLimitStore, RateLimitStoreError, StoreProbe,
+        TwoFaCodeStore, TwoFaCodeStoreError, UserStore, UserStoreError,
     };
 }
 
 // Re-export repository traits at root level
 pub use core::{
-    BannedTokenStore, BannedTokenStoreError, EmailClient, TwoFaCodeStore, TwoFaCodeStoreError,
-    UserStore, UserStoreError,
+    ApiKeyStore, ApiKeyStoreError, AttemptIdGenerator, AuditEvent, AuditSink, BackupCodeStore,
+    BackupCodeStoreError, BannedTokenStore, BannedTokenStoreError, EmailClient, EmailKind,
+    NoopRegistrationHook, PasswordStrength, PasswordStrengthEstimator, ProbeableStore,
+    RateLimitStore, RateLimitStoreError, RegistrationHook, StoreProbe, TwoFaCodeStore,
+    TwoFaCodeStoreError, UserStore, UserStoreError, UuidAttemptIdGenerator,
 };
 
 // ============================================================================
@@ -62,10 +67,18 @@ pub mod use_cases {
 
 // Re-export use cases at root level
 pub use tempered_application::{
-    ChangePasswordUseCase, DeleteAccountUseCase, ElevateUseCase, LoginUseCase, LogoutUseCase,
-    SignupUseCase, Verify2FaUseCase,
+    ChangePasswordUseCase, DeleteAccountUseCase, ElevateUseCase, ForceLogoutUseCase, LoginUseCase,
+    LogoutUseCase, SignupUseCase, Verify2FaUseCase, VerifyPasswordUseCase, VerifyTokenUseCase,
 };
 
+// Re-export the token ports for plugging in an external signer or extra claims
+pub use tempered_application::{
+    ClaimsEnricher, LocalJwtIssuer, NoopClaimsEnricher, TokenIssuer, TokenType,
+};
+
+// Re-export the password strength policy for plugging in an estimator like zxcvbn
+pub use tempered_application::{DEFAULT_MIN_PASSWORD_SCORE, PasswordStrengthPolicy, WeakPassword};
+
 // ============================================================================
 // Adapters (Infrastructure)
 // ============================================================================
@@ -100,10 +113,15 @@ pub mod adapters {
 
 // Re-export commonly used adapters at root level
 pub use tempered_adapters::{
-    email::{MockEmailClient, PostmarkEmailClient},
+    email::{
+        MockEmailClient, PostmarkEmailClient, SpawningEmailClient, TemplatingEmailClient,
+        TracingEmailClient,
+    },
     persistence::{
-        HashMapTwoFaCodeStore, HashMapUserStore, HashSetBannedTokenStore, PostgresUserStore,
-        RedisBannedTokenStore, RedisTwoFaCodeStore,
+        CompositeBannedTokenStore, HashMapApiKeyStore, HashMapBackupCodeStore,
+        HashMapRateLimitStore, HashMapTwoFaCodeStore, HashMapUserStore, HashSetBannedTokenStore,
+        PasswordHashingPool, PostgresApiKeyStore, PostgresRateLimitStore, PostgresUserStore,
+        RedisBannedTokenStore, RedisRateLimitStore, RedisTwoFaCodeStore, TwoFaCodeHasher,
     },
 };
 
@@ -113,9 +131,14 @@ pub use tempered_adapters::{
 
 /// Main auth service
 pub use tempered_auth_service::{
-    AuthService, configure_postgresql, configure_redis, get_redis_client,
+    AuthService, Capabilities, MIGRATOR, RouteSpec, SecurityHeadersLayer, SetupError,
+    configure_postgresql, configure_redis, get_redis_client, run_migrations,
 };
 
+/// In-memory test server, enabled by the `test-utils` feature
+#[cfg(feature = "test-utils")]
+pub use tempered_auth_service::TestServer;
+
 // ============================================================================
 // Re-export common external dependencies
 // ============================================================================
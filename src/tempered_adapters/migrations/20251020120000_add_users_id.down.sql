@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS users_id_idx;
+
+ALTER TABLE users DROP COLUMN IF EXISTS id;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- Stable user id alongside the email primary key. Moving the primary key and the
+-- password_history foreign key over to it is left to a later migration.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS id UUID NOT NULL DEFAULT gen_random_uuid();
+
+CREATE UNIQUE INDEX IF NOT EXISTS users_id_idx ON users (id);
@@ -0,0 +1,25 @@
+-- Add down migration script here
+ALTER TABLE password_history ADD COLUMN IF NOT EXISTS email TEXT;
+
+UPDATE password_history
+SET email = users.email
+FROM users
+WHERE users.id = password_history.user_id;
+
+ALTER TABLE password_history ALTER COLUMN email SET NOT NULL;
+
+-- Also drops the user_id foreign key and password_history_user_id_changed_at_idx
+ALTER TABLE password_history DROP COLUMN IF EXISTS user_id;
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_pkey;
+ALTER TABLE users ADD CONSTRAINT users_pkey PRIMARY KEY (email);
+
+CREATE UNIQUE INDEX IF NOT EXISTS users_id_idx ON users (id);
+
+ALTER TABLE password_history
+   ADD CONSTRAINT password_history_email_fkey
+   FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS password_history_email_changed_at_idx
+   ON password_history (email, changed_at DESC);
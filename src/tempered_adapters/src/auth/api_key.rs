@@ -0,0 +1,71 @@
+use axum::http::{HeaderMap, HeaderName};
+use tempered_core::ApiKey;
+
+use super::bearer::{AuthorizationHeaderError, bearer_token};
+
+pub static API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+
+/// Extract an API key from the `X-Api-Key` header, or from `Authorization: Bearer <key>`
+///
+/// Returns `Ok(None)` if neither header is set. A malformed `Authorization` header
+/// is only an error when there is no `X-Api-Key` header.
+pub fn extract_api_key(headers: &HeaderMap) -> Result<Option<ApiKey>, AuthorizationHeaderError> {
+    let key = headers
+        .get(&API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty());
+
+    let key = match key {
+        Some(key) => Some(key),
+        None => bearer_token(headers)?,
+    };
+    Ok(key.map(|key| ApiKey::from(key.to_owned())))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderValue, header::AUTHORIZATION};
+    use secrecy::ExposeSecret;
+
+    use super::*;
+
+    #[test]
+    fn test_extract_api_key_from_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(&API_KEY_HEADER, HeaderValue::from_static(" tmp_key "));
+
+        let key = extract_api_key(&headers).unwrap().unwrap();
+        assert_eq!(key.expose_secret(), "tmp_key");
+    }
+
+    #[test]
+    fn test_extract_api_key_from_bearer() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer tmp_key"));
+
+        let key = extract_api_key(&headers).unwrap().unwrap();
+        assert_eq!(key.expose_secret(), "tmp_key");
+    }
+
+    #[test]
+    fn test_extract_api_key_malformed_authorization() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static("Basic dXNlcjpwYXNz"),
+        );
+        assert!(matches!(
+            extract_api_key(&headers),
+            Err(AuthorizationHeaderError::UnsupportedScheme(_))
+        ));
+
+        headers.insert(&API_KEY_HEADER, HeaderValue::from_static("tmp_key"));
+        assert!(extract_api_key(&headers).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_extract_api_key_missing() {
+        assert!(extract_api_key(&HeaderMap::new()).unwrap().is_none());
+    }
+}
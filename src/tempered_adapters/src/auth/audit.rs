@@ -0,0 +1,37 @@
+use secrecy::ExposeSecret;
+use tempered_core::{AuditEvent, AuditSink};
+
+/// Audit sink that writes events to the `audit` tracing target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingAuditSink;
+
+#[async_trait::async_trait]
+impl AuditSink for TracingAuditSink {
+    async fn record(&self, event: AuditEvent) {
+        match event {
+            AuditEvent::Impersonation { subject, actor } => tracing::info!(
+                target: "audit",
+                subject = subject.as_ref().expose_secret(),
+                actor,
+                "Token issued by impersonation"
+            ),
+            AuditEvent::ForcedLogout { subject, actor } => tracing::info!(
+                target: "audit",
+                subject = subject.as_ref().expose_secret(),
+                actor,
+                "All tokens revoked by forced logout"
+            ),
+            AuditEvent::EmailDeliveryFailed {
+                recipient,
+                subject,
+                error,
+            } => tracing::warn!(
+                target: "audit",
+                recipient = recipient.as_ref().expose_secret(),
+                subject,
+                error,
+                "Background email delivery failed"
+            ),
+        }
+    }
+}
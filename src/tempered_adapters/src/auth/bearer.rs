@@ -0,0 +1,108 @@
+use axum::http::{HeaderMap, header::AUTHORIZATION};
+use thiserror::Error;
+
+const BEARER_SCHEME: &str = "bearer";
+
+/// Why an `Authorization` header that is present couldn't be used
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AuthorizationHeaderError {
+    #[error("Authorization header is empty or not valid UTF-8")]
+    InvalidValue,
+    #[error("Unsupported authorization scheme '{0}', expected Bearer")]
+    UnsupportedScheme(String),
+    #[error("Authorization header has no bearer token")]
+    MissingToken,
+}
+
+/// Extract the token from an `Authorization: Bearer <token>` header
+///
+/// The scheme is matched case-insensitively and surrounding whitespace is ignored.
+/// Returns `Ok(None)` if there is no `Authorization` header and an error if the
+/// header is not valid UTF-8, uses another scheme or carries an empty token.
+pub fn bearer_token(headers: &HeaderMap) -> Result<Option<&str>, AuthorizationHeaderError> {
+    let Some(value) = headers.get(AUTHORIZATION) else {
+        return Ok(None);
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| AuthorizationHeaderError::InvalidValue)?
+        .trim();
+    if value.is_empty() {
+        return Err(AuthorizationHeaderError::InvalidValue);
+    }
+
+    let (scheme, token) = value.split_once(char::is_whitespace).unwrap_or((value, ""));
+    if !scheme.eq_ignore_ascii_case(BEARER_SCHEME) {
+        return Err(AuthorizationHeaderError::UnsupportedScheme(
+            scheme.to_owned(),
+        ));
+    }
+
+    match token.trim() {
+        "" => Err(AuthorizationHeaderError::MissingToken),
+        token => Ok(Some(token)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn headers_with_authorization(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let headers = headers_with_authorization("Bearer abc.def.ghi");
+        assert_eq!(bearer_token(&headers), Ok(Some("abc.def.ghi")));
+    }
+
+    #[test]
+    fn test_bearer_token_is_case_insensitive_and_trims_whitespace() {
+        let headers = headers_with_authorization("  bEaReR    abc.def.ghi  ");
+        assert_eq!(bearer_token(&headers), Ok(Some("abc.def.ghi")));
+    }
+
+    #[test]
+    fn test_bearer_token_missing_prefix() {
+        let headers = headers_with_authorization("abc.def.ghi");
+        assert_eq!(
+            bearer_token(&headers),
+            Err(AuthorizationHeaderError::UnsupportedScheme(
+                "abc.def.ghi".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_bearer_token_other_scheme() {
+        let headers = headers_with_authorization("Basic dXNlcjpwYXNz");
+        assert_eq!(
+            bearer_token(&headers),
+            Err(AuthorizationHeaderError::UnsupportedScheme(
+                "Basic".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_bearer_token_empty_token() {
+        for value in ["Bearer   ", "Bearer"] {
+            let headers = headers_with_authorization(value);
+            assert_eq!(
+                bearer_token(&headers),
+                Err(AuthorizationHeaderError::MissingToken)
+            );
+        }
+    }
+
+    #[test]
+    fn test_bearer_token_missing_header() {
+        assert_eq!(bearer_token(&HeaderMap::new()), Ok(None));
+    }
+}
@@ -0,0 +1,40 @@
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use rand::{Rng, distr::Alphanumeric};
+
+const CSRF_TOKEN_LENGTH: usize = 32;
+
+/// Mint a fresh random CSRF token
+pub fn generate_csrf_token() -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(CSRF_TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// Create the cookie half of a double-submit CSRF token
+///
+/// The same token is handed to the client in the response body, which echoes it
+/// back in a header so the two can be compared.
+pub fn create_csrf_cookie(token: String, cookie_name: &str) -> Cookie<'_> {
+    Cookie::build((cookie_name, token))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_tokens_are_unique() {
+        let token = generate_csrf_token();
+
+        assert_eq!(token.len(), CSRF_TOKEN_LENGTH);
+        assert!(token.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(token, generate_csrf_token());
+    }
+}
@@ -4,15 +4,23 @@ use axum_extra::extract::{
     CookieJar,
     cookie::{Cookie, SameSite},
 };
-use chrono::Utc;
 use color_eyre::eyre::eyre;
-use jsonwebtoken::{DecodingKey, EncodingKey, Validation, decode, encode};
-use secrecy::{ExposeSecret, Secret};
-use serde::{Deserialize, Serialize, ser::SerializeStruct};
-use tempered_core::{BannedTokenStore, Email};
+use jsonwebtoken::errors::ErrorKind;
+use secrecy::ExposeSecret;
+pub use tempered_application::{
+    ACTOR_CLAIM, Claims, ClaimsEnricher, ElevationScope, LocalJwtIssuer, NoopClaimsEnricher,
+    TokenIssuer, TokenType,
+};
+use tempered_application::{
+    TokenIssuerError, TwoFaTokenSigner, VerifyTokenError, VerifyTokenUseCase,
+};
+use tempered_core::{
+    AuditEvent, AuditSink, BannedTokenStore, Email, User, UserId, UserStore, UserStoreError,
+};
 use thiserror::Error;
 
-use crate::config::settings::{AuthServiceSetting, Config};
+use crate::config::settings::{AuthServiceSetting, Config, JWTConfig};
+use crate::persistence::TwoFaCodeHasher;
 
 pub static JWT_COOKIE_NAME: LazyLock<&'static str> = LazyLock::new(|| {
     let cookie_name = AuthServiceSetting::load().auth.jwt.cookie_name.clone();
@@ -37,10 +45,32 @@ pub enum TokenAuthError {
     TokenError(jsonwebtoken::errors::Error),
     #[error("Token is banned")]
     TokenIsBanned,
+    #[error("Token is not valid for this operation")]
+    InsufficientScope,
     #[error("Unexpected error")]
     UnexpectedError(#[source] color_eyre::Report),
 }
 
+impl From<VerifyTokenError> for TokenAuthError {
+    fn from(error: VerifyTokenError) -> Self {
+        match error {
+            VerifyTokenError::TokenError(e) => TokenAuthError::TokenError(e),
+            VerifyTokenError::TokenIsBanned => TokenAuthError::TokenIsBanned,
+            VerifyTokenError::IssuerError(e) => e.into(),
+            VerifyTokenError::BannedTokenStoreError(e) => TokenAuthError::UnexpectedError(eyre!(e)),
+        }
+    }
+}
+
+impl From<TokenIssuerError> for TokenAuthError {
+    fn from(error: TokenIssuerError) -> Self {
+        match error {
+            TokenIssuerError::TokenError(e) => TokenAuthError::TokenError(e),
+            TokenIssuerError::UnexpectedError(e) => TokenAuthError::UnexpectedError(eyre!(e)),
+        }
+    }
+}
+
 pub fn extract_token<'a>(jar: &'a CookieJar, cookie_name: &str) -> Result<&'a str, TokenAuthError> {
     match jar.get(cookie_name) {
         Some(cookie) => Ok(cookie.value()),
@@ -48,31 +78,148 @@ pub fn extract_token<'a>(jar: &'a CookieJar, cookie_name: &str) -> Result<&'a st
     }
 }
 
+/// Signs the tokens the routes issue and adds the claims of its enricher
+///
+/// Without a custom issuer each token type is signed by a [`LocalJwtIssuer`] with its
+/// configured secret. Tokens are always verified with the configured secrets, so a
+/// custom issuer has to sign with those too, e.g. from a KMS holding the keys.
+#[derive(Clone)]
+pub struct TokenMinter {
+    issuer: Option<Arc<dyn TokenIssuer>>,
+    enricher: Arc<dyn ClaimsEnricher>,
+}
+
+impl Default for TokenMinter {
+    fn default() -> Self {
+        Self {
+            issuer: None,
+            enricher: Arc::new(NoopClaimsEnricher),
+        }
+    }
+}
+
+impl TokenMinter {
+    pub fn new(issuer: impl TokenIssuer + 'static) -> Self {
+        Self::default().with_issuer(issuer)
+    }
+
+    pub fn with_issuer(mut self, issuer: impl TokenIssuer + 'static) -> Self {
+        self.issuer = Some(Arc::new(issuer));
+        self
+    }
+
+    pub fn with_claims_enricher(mut self, enricher: impl ClaimsEnricher + 'static) -> Self {
+        self.enricher = Arc::new(enricher);
+        self
+    }
+
+    async fn mint(
+        &self,
+        user_id: &UserId,
+        email: &Email,
+        token_type: TokenType,
+        scope: Option<ElevationScope>,
+        jwt_config: &JWTConfig,
+    ) -> Result<String, TokenAuthError> {
+        let token_ttl = jwt_config.time_to_live;
+        match &self.issuer {
+            Some(issuer) => {
+                generate_auth_token(
+                    issuer.as_ref(),
+                    self.enricher.as_ref(),
+                    user_id,
+                    email,
+                    token_type,
+                    scope,
+                    token_ttl,
+                )
+                .await
+            }
+            None => {
+                let issuer = LocalJwtIssuer::new(jwt_config.secret.expose_secret().as_bytes());
+                generate_auth_token(
+                    &issuer,
+                    self.enricher.as_ref(),
+                    user_id,
+                    email,
+                    token_type,
+                    scope,
+                    token_ttl,
+                )
+                .await
+            }
+        }
+    }
+}
+
 // Create cookie with a new JWT auth token
-pub fn generate_auth_cookie(
+pub async fn generate_auth_cookie(
+    minter: &TokenMinter,
+    user_id: &UserId,
     email: &Email,
     config: &Arc<Config>,
 ) -> Result<Cookie<'static>, TokenAuthError> {
-    let token_ttl = config.auth.jwt.time_to_live;
-    let jwt_secret = config.auth.jwt.secret.expose_secret().as_bytes();
-
-    let token = generate_auth_token(email, token_ttl, jwt_secret)?;
-    Ok(create_auth_cookie(token, *JWT_COOKIE_NAME))
+    let token = minter
+        .mint(user_id, email, TokenType::Access, None, &config.auth.jwt)
+        .await?;
+
+    let mut cookie = create_auth_cookie(token, *JWT_COOKIE_NAME);
+    cookie.set_http_only(config.auth.jwt.http_only);
+    if let Some(domain) = &config.auth.jwt.cookie_domain {
+        cookie.set_domain(domain.clone());
+    }
+    Ok(cookie)
 }
 
-pub fn generate_elevated_auth_cookie(
+// Create cookie with a new elevated JWT, restricted to `scope` if one is given
+pub async fn generate_elevated_auth_cookie(
+    minter: &TokenMinter,
+    user_id: &UserId,
     email: &Email,
+    scope: Option<ElevationScope>,
     config: &Arc<Config>,
 ) -> Result<Cookie<'static>, TokenAuthError> {
-    let token_ttl = config.auth.elevated_jwt.time_to_live;
-    let jwt_secret = config.auth.elevated_jwt.secret.expose_secret().as_bytes();
+    let token = minter
+        .mint(
+            user_id,
+            email,
+            TokenType::Elevated,
+            scope,
+            &config.auth.elevated_jwt,
+        )
+        .await?;
+
+    let mut cookie = create_auth_cookie(token, *JWT_ELEVATED_COOKIE_NAME);
+    cookie.set_http_only(config.auth.elevated_jwt.http_only);
+    if let Some(domain) = &config.auth.elevated_jwt.cookie_domain {
+        cookie.set_domain(domain.clone());
+    }
+    Ok(cookie)
+}
+
+// Create the signer for stateless 2FA attempt tokens, keyed off the JWT secret
+pub fn create_two_fa_token_signer(config: &Config) -> TwoFaTokenSigner {
+    TwoFaTokenSigner::new(
+        config.auth.jwt.secret.expose_secret().as_bytes(),
+        config.auth.two_fa.time_to_live_in_seconds,
+    )
+}
 
-    let token = generate_auth_token(email, token_ttl, jwt_secret)?;
-    Ok(create_auth_cookie(token, *JWT_ELEVATED_COOKIE_NAME))
+/// Hasher for stored 2FA codes as configured by `auth.two_fa.hash_codes`
+pub fn create_two_fa_code_hasher(config: &Config) -> TwoFaCodeHasher {
+    if config.auth.two_fa.hash_codes {
+        TwoFaCodeHasher::new(config.auth.jwt.secret.expose_secret().as_bytes())
+    } else {
+        TwoFaCodeHasher::plaintext()
+    }
 }
 
-pub fn create_removal_cookie(cookie_name: &str) -> Cookie<'_> {
+// Create a cookie that clears `cookie_name`, the domain must match the one it was set with
+pub fn create_removal_cookie<'a>(cookie_name: &'a str, domain: Option<&str>) -> Cookie<'a> {
     let mut cookie = create_auth_cookie(String::new(), cookie_name);
+    if let Some(domain) = domain {
+        cookie.set_domain(domain.to_owned());
+    }
     cookie.make_removal();
     cookie
 }
@@ -87,114 +234,205 @@ pub fn create_auth_cookie(token: String, cookie_name: &str) -> Cookie<'_> {
         .build()
 }
 
-// Create JWT auth token
-fn generate_auth_token(
+// Create JWT auth token with the given issuer, adding the claims derived by `enricher`
+pub async fn generate_auth_token<I, C>(
+    issuer: &I,
+    enricher: &C,
+    user_id: &UserId,
     email: &Email,
+    token_type: TokenType,
+    scope: Option<ElevationScope>,
     token_ttl_seconds: i64,
-    secret: &[u8],
-) -> Result<String, TokenAuthError> {
-    let delta = chrono::Duration::try_seconds(token_ttl_seconds).ok_or(
-        TokenAuthError::UnexpectedError(eyre!("Failed to create auth token duration")),
-    )?;
-
-    // Create JWT expiration time
-    let exp = Utc::now()
-        .checked_add_signed(delta)
-        .ok_or(TokenAuthError::UnexpectedError(eyre!(
-            "Duration out of range",
-        )))?
-        .timestamp();
-
-    // Cast exp to a usize, which is what Claims expects
-    let exp: usize = exp
-        .try_into()
-        .map_err(|_| TokenAuthError::UnexpectedError(eyre!("Failed to cast i64 to usize")))?;
-
-    let sub = Clone::clone(email.as_ref());
-
-    let claims = Claims { sub, exp };
+) -> Result<String, TokenAuthError>
+where
+    I: TokenIssuer + ?Sized,
+    C: ClaimsEnricher + ?Sized,
+{
+    let extra_claims = enricher.enrich(email).await;
+    Ok(issuer
+        .issue(user_id, token_ttl_seconds, token_type, scope, extra_claims)
+        .await?)
+}
 
-    create_token(&claims, secret)
+/// Issue an access token for the user `user_id` without their credentials, for
+/// trusted server-to-server flows such as support impersonation or an SSO bridge
+///
+/// This is not exposed as a route. The token carries an `act` claim naming `actor`,
+/// see [`Claims::impersonated_by`], and the impersonation is recorded with `audit_sink`.
+pub async fn issue_token_for<I, C, A>(
+    issuer: &I,
+    enricher: &C,
+    audit_sink: &A,
+    user_id: &UserId,
+    email: &Email,
+    actor: &str,
+    token_ttl_seconds: i64,
+) -> Result<String, TokenAuthError>
+where
+    I: TokenIssuer,
+    C: ClaimsEnricher,
+    A: AuditSink,
+{
+    let mut extra_claims = enricher.enrich(email).await;
+    extra_claims.insert(ACTOR_CLAIM.to_owned(), serde_json::json!({ "sub": actor }));
+    let token = issuer
+        .issue(
+            user_id,
+            token_ttl_seconds,
+            TokenType::Access,
+            None,
+            extra_claims,
+        )
+        .await?;
+
+    audit_sink
+        .record(AuditEvent::Impersonation {
+            subject: email.clone(),
+            actor: actor.to_owned(),
+        })
+        .await;
+
+    Ok(token)
 }
 
 // Check if JWT auth token is valid by decoding it using the JWT secret
-pub async fn validate_auth_token(
+pub async fn validate_auth_token<B>(
     token: &str,
-    banned_token_store: &dyn BannedTokenStore,
-) -> Result<Claims, TokenAuthError> {
+    banned_token_store: &B,
+) -> Result<Claims, TokenAuthError>
+where
+    B: BannedTokenStore + Clone,
+{
     let config = AuthServiceSetting::load();
-    let jwt_secret = config.auth.jwt.secret.expose_secret().as_bytes();
-    validate_token(token, banned_token_store, jwt_secret).await
+    validate_token_with_rotation(
+        token,
+        banned_token_store,
+        &config.auth.jwt,
+        TokenType::Access,
+    )
+    .await
 }
 
-pub async fn validate_elevated_auth_token(
+pub async fn validate_elevated_auth_token<B>(
     token: &str,
-    banned_token_store: &dyn BannedTokenStore,
-) -> Result<Claims, TokenAuthError> {
+    banned_token_store: &B,
+) -> Result<Claims, TokenAuthError>
+where
+    B: BannedTokenStore + Clone,
+{
     let config = AuthServiceSetting::load();
-    let jwt_secret = config.auth.elevated_jwt.secret.expose_secret().as_bytes();
-    validate_token(token, banned_token_store, jwt_secret).await
-}
-
-async fn validate_token(
-    token: &str,
-    banned_token_store: &dyn BannedTokenStore,
-    secret: &[u8],
-) -> Result<Claims, TokenAuthError> {
-    let claims = decode::<Claims>(
+    validate_token_with_rotation(
         token,
-        &DecodingKey::from_secret(secret),
-        &Validation::default(),
+        banned_token_store,
+        &config.auth.elevated_jwt,
+        TokenType::Elevated,
     )
-    .map(|data| data.claims)
-    .map_err(TokenAuthError::TokenError)?;
-
-    let token = create_token(&claims, secret)?;
-
-    let is_banned = banned_token_store
-        .contains_token(&token)
-        .await
-        .map_err(|e| TokenAuthError::UnexpectedError(eyre!(e)))?;
+    .await
+}
 
-    if is_banned {
-        return Err(TokenAuthError::TokenIsBanned);
+// Validate an elevated token and check that it was minted for `scope`, unscoped
+// tokens only pass with `auth.elevate.allow_unscoped_tokens`
+pub async fn validate_elevated_auth_token_for<B>(
+    token: &str,
+    banned_token_store: &B,
+    scope: ElevationScope,
+) -> Result<Claims, TokenAuthError>
+where
+    B: BannedTokenStore + Clone,
+{
+    let claims = validate_elevated_auth_token(token, banned_token_store).await?;
+    let allow_unscoped = AuthServiceSetting::load()
+        .auth
+        .elevate
+        .allow_unscoped_tokens;
+    if !(claims.allows(scope) || allow_unscoped && claims.scope.is_none()) {
+        return Err(TokenAuthError::InsufficientScope);
     }
-
     Ok(claims)
 }
 
-// Create JWT auth token by encoding claims using the JWT secret
-fn create_token(claims: &Claims, secret: &[u8]) -> Result<String, TokenAuthError> {
-    encode(
-        &jsonwebtoken::Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret),
-    )
-    .map_err(TokenAuthError::TokenError)
+// Check if a token is valid using a custom issuer, and that it is not banned
+pub async fn validate_token_with_issuer<B, I>(
+    token: &str,
+    banned_token_store: &B,
+    issuer: &I,
+) -> Result<Claims, TokenAuthError>
+where
+    B: BannedTokenStore + Clone,
+    I: TokenIssuer,
+{
+    let use_case = VerifyTokenUseCase::new(banned_token_store.clone());
+    Ok(use_case.execute_with_issuer(token, issuer).await?)
 }
 
-#[derive(Debug, Deserialize)]
-pub struct Claims {
-    pub sub: Secret<String>,
-    pub exp: usize,
+/// Look up the user a validated token was issued for by the id in its subject, or by
+/// email for tokens issued before the subject was the user id
+pub async fn resolve_token_user<U>(claims: &Claims, user_store: &U) -> Result<User, TokenAuthError>
+where
+    U: UserStore,
+{
+    let result = match claims.user_id() {
+        Some(user_id) => user_store.get_user_by_id(&user_id).await,
+        None => {
+            let email = Email::try_from(Clone::clone(&claims.sub))
+                .map_err(|_| TokenAuthError::InvalidToken)?;
+            user_store.get_user(&email).await
+        }
+    };
+
+    result.map_err(|e| match e {
+        UserStoreError::UserNotFound => TokenAuthError::InvalidToken,
+        e => TokenAuthError::UnexpectedError(eyre!(e)),
+    })
 }
 
-impl Serialize for Claims {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let mut state = serializer.serialize_struct("Claims", 2)?;
-        state.serialize_field("sub", &self.sub.expose_secret())?;
-        state.serialize_field("exp", &self.exp)?;
-        state.end()
+// Try the primary secret, then each previous secret, so rotated out tokens keep working until they expire.
+// A token of another type is rejected even when both types share a secret.
+async fn validate_token_with_rotation<B>(
+    token: &str,
+    banned_token_store: &B,
+    jwt_config: &JWTConfig,
+    token_type: TokenType,
+) -> Result<Claims, TokenAuthError>
+where
+    B: BannedTokenStore + Clone,
+{
+    let mut result = Err(TokenAuthError::InvalidToken);
+    for secret in jwt_config.verification_secrets() {
+        result = validate_token(token, banned_token_store, secret.expose_secret().as_bytes()).await;
+        match &result {
+            Err(TokenAuthError::TokenError(e)) if *e.kind() == ErrorKind::InvalidSignature => {
+                continue;
+            }
+            _ => break,
+        }
+    }
+
+    match result {
+        Ok(claims) if !claims.is_token_type(token_type) => Err(TokenAuthError::InvalidToken),
+        result => result,
     }
 }
 
+async fn validate_token<B>(
+    token: &str,
+    banned_token_store: &B,
+    secret: &[u8],
+) -> Result<Claims, TokenAuthError>
+where
+    B: BannedTokenStore + Clone,
+{
+    let use_case = VerifyTokenUseCase::new(banned_token_store.clone());
+    Ok(use_case.execute(token, secret).await?)
+}
+
 #[cfg(test)]
 mod tests {
+    use chrono::Utc;
     use secrecy::{ExposeSecret, Secret};
+    use tempered_application::token_ban_key;
 
+    use crate::persistence::HashMapUserStore;
     use crate::persistence::hashset_banned_token_store::HashSetBannedTokenStore;
 
     use super::*;
@@ -203,7 +441,9 @@ mod tests {
     async fn test_generate_auth_cookie() {
         let config = AuthServiceSetting::load();
         let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
-        let cookie = generate_auth_cookie(&email, &config).unwrap();
+        let cookie = generate_auth_cookie(&TokenMinter::default(), &UserId::new(), &email, &config)
+            .await
+            .unwrap();
         assert_eq!(cookie.name(), config.auth.jwt.cookie_name);
         assert_eq!(cookie.value().split('.').count(), 3);
         assert_eq!(cookie.path(), Some("/"));
@@ -211,6 +451,134 @@ mod tests {
         assert_eq!(cookie.same_site(), Some(SameSite::Lax));
     }
 
+    #[tokio::test]
+    async fn test_generate_auth_cookie_with_custom_issuer() {
+        let config = AuthServiceSetting::load();
+        let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
+        let issuer = LocalJwtIssuer::new(b"custom issuer secret");
+        let minter = TokenMinter::new(issuer.clone());
+
+        let user_id = UserId::new();
+        let cookie = generate_auth_cookie(&minter, &user_id, &email, &config)
+            .await
+            .unwrap();
+
+        let claims = issuer.verify(cookie.value()).await.unwrap();
+        assert_eq!(claims.user_id(), Some(user_id));
+        assert!(claims.is_token_type(TokenType::Access));
+    }
+
+    struct TenantEnricher;
+
+    #[async_trait::async_trait]
+    impl ClaimsEnricher for TenantEnricher {
+        async fn enrich(&self, _email: &Email) -> tempered_application::ExtraClaims {
+            let mut claims = tempered_application::ExtraClaims::new();
+            claims.insert("tenant".to_owned(), serde_json::json!("acme"));
+            claims
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_elevated_auth_cookie_with_claims_enricher() {
+        let config = AuthServiceSetting::load();
+        let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
+        let minter = TokenMinter::default().with_claims_enricher(TenantEnricher);
+
+        let cookie = generate_elevated_auth_cookie(&minter, &UserId::new(), &email, None, &config)
+            .await
+            .unwrap();
+
+        let issuer =
+            LocalJwtIssuer::new(config.auth.elevated_jwt.secret.expose_secret().as_bytes());
+        let claims = issuer.verify(cookie.value()).await.unwrap();
+        assert_eq!(claims.extra.get("tenant"), Some(&serde_json::json!("acme")));
+    }
+
+    #[derive(Default)]
+    struct RecordingAuditSink(std::sync::Mutex<Vec<AuditEvent>>);
+
+    #[async_trait::async_trait]
+    impl AuditSink for RecordingAuditSink {
+        async fn record(&self, event: AuditEvent) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_issue_token_for_records_impersonation() {
+        let issuer = LocalJwtIssuer::new(b"test-secret");
+        let audit_sink = RecordingAuditSink::default();
+        let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
+        let user_id = UserId::new();
+
+        let token = issue_token_for(
+            &issuer,
+            &NoopClaimsEnricher,
+            &audit_sink,
+            &user_id,
+            &email,
+            "support@example.com",
+            600,
+        )
+        .await
+        .unwrap();
+
+        let claims = issuer.verify(&token).await.unwrap();
+        assert_eq!(claims.user_id(), Some(user_id));
+        assert_eq!(claims.token_type, Some(TokenType::Access));
+        assert_eq!(claims.impersonated_by(), Some("support@example.com"));
+        assert_eq!(
+            *audit_sink.0.lock().unwrap(),
+            [AuditEvent::Impersonation {
+                subject: email,
+                actor: "support@example.com".to_owned(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_auth_cookie_without_http_only() {
+        let mut config = Config::new().unwrap();
+        config.auth.jwt.http_only = false;
+        let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
+
+        let cookie = generate_auth_cookie(
+            &TokenMinter::default(),
+            &UserId::new(),
+            &email,
+            &Arc::new(config),
+        )
+        .await
+        .unwrap();
+        assert_eq!(cookie.http_only(), Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_cookie_domain() {
+        let mut config = Config::new().unwrap();
+        config.auth.jwt.cookie_domain = Some("example.com".to_owned());
+        let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
+
+        let cookie = generate_auth_cookie(
+            &TokenMinter::default(),
+            &UserId::new(),
+            &email,
+            &Arc::new(config),
+        )
+        .await
+        .unwrap();
+        assert!(cookie.to_string().contains("Domain=example.com"));
+
+        let removal = create_removal_cookie("jwt", Some("example.com"));
+        assert!(removal.to_string().contains("Domain=example.com"));
+        assert!(
+            !create_removal_cookie("jwt", None)
+                .to_string()
+                .contains("Domain=")
+        );
+    }
+
     #[tokio::test]
     async fn test_create_auth_cookie() {
         let config = AuthServiceSetting::load();
@@ -228,24 +596,77 @@ mod tests {
     async fn test_generate_auth_token() {
         let config = AuthServiceSetting::load();
         let token_ttl = config.auth.jwt.time_to_live;
-        let jwt_secret = config.auth.jwt.secret.expose_secret().as_bytes();
+        let issuer = LocalJwtIssuer::new(config.auth.jwt.secret.expose_secret().as_bytes());
         let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
-        let result = generate_auth_token(&email, token_ttl, jwt_secret).unwrap();
+        let result = generate_auth_token(
+            &issuer,
+            &NoopClaimsEnricher,
+            &UserId::new(),
+            &email,
+            TokenType::Access,
+            None,
+            token_ttl,
+        )
+        .await
+        .unwrap();
         assert_eq!(result.split('.').count(), 3);
     }
 
+    #[tokio::test]
+    async fn test_generate_auth_token_with_enricher() {
+        struct TenantEnricher;
+
+        #[async_trait::async_trait]
+        impl ClaimsEnricher for TenantEnricher {
+            async fn enrich(&self, _email: &Email) -> tempered_application::ExtraClaims {
+                let mut claims = tempered_application::ExtraClaims::new();
+                claims.insert("tenant".to_owned(), "acme".into());
+                claims
+            }
+        }
+
+        let issuer = LocalJwtIssuer::new(b"test-secret");
+        let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
+        let token = generate_auth_token(
+            &issuer,
+            &TenantEnricher,
+            &UserId::new(),
+            &email,
+            TokenType::Access,
+            None,
+            600,
+        )
+        .await
+        .unwrap();
+
+        let claims = issuer.verify(&token).await.unwrap();
+        assert_eq!(claims.extra["tenant"], "acme");
+    }
+
     #[tokio::test]
     async fn test_validate_token_with_valid_token() {
         let config = AuthServiceSetting::load();
         let token_ttl = config.auth.jwt.time_to_live;
-        let jwt_secret = config.auth.jwt.secret.expose_secret().as_bytes();
+        let issuer = LocalJwtIssuer::new(config.auth.jwt.secret.expose_secret().as_bytes());
+        let user_id = UserId::new();
         let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
         let banned_token_store = HashSetBannedTokenStore::default();
-        let token = generate_auth_token(&email, token_ttl, jwt_secret).unwrap();
+        let token = generate_auth_token(
+            &issuer,
+            &NoopClaimsEnricher,
+            &user_id,
+            &email,
+            TokenType::Access,
+            None,
+            token_ttl,
+        )
+        .await
+        .unwrap();
         let result = validate_auth_token(&token, &banned_token_store)
             .await
             .unwrap();
-        assert_eq!(result.sub.expose_secret(), "test@example.com");
+        assert_eq!(result.sub.expose_secret(), &user_id.to_string());
+        assert_eq!(result.user_id(), Some(user_id));
 
         let exp = Utc::now()
             .checked_add_signed(chrono::Duration::try_minutes(9).expect("valid duration"))
@@ -263,17 +684,214 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_validate_token_signed_with_previous_secret() {
+        let config = AuthServiceSetting::load();
+        let previous_secret = "p".repeat(32);
+        let issuer = LocalJwtIssuer::new(previous_secret.as_bytes());
+        let user_id = UserId::new();
+        let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
+        let banned_token_store = HashSetBannedTokenStore::default();
+        let token = generate_auth_token(
+            &issuer,
+            &NoopClaimsEnricher,
+            &user_id,
+            &email,
+            TokenType::Access,
+            None,
+            600,
+        )
+        .await
+        .unwrap();
+
+        let mut jwt_config = JWTConfig {
+            cookie_name: config.auth.jwt.cookie_name.clone(),
+            cookie_prefix: None,
+            cookie_domain: None,
+            legacy_cookie_names: Vec::new(),
+            secret: config.auth.jwt.secret.clone(),
+            previous_secrets: Vec::new(),
+            time_to_live: config.auth.jwt.time_to_live,
+            renewal_window: config.auth.jwt.renewal_window,
+            http_only: true,
+        };
+        let result = validate_token_with_rotation(
+            &token,
+            &banned_token_store,
+            &jwt_config,
+            TokenType::Access,
+        )
+        .await;
+        assert!(matches!(result, Err(TokenAuthError::TokenError(_))));
+
+        jwt_config.previous_secrets = vec![Secret::new(previous_secret)];
+        let result = validate_token_with_rotation(
+            &token,
+            &banned_token_store,
+            &jwt_config,
+            TokenType::Access,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.user_id(), Some(user_id));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_token_user() {
+        let user_store = HashMapUserStore::default();
+        let user = User::parse(
+            Secret::from("test@example.com".to_owned()),
+            Secret::from("password123".to_owned()),
+            false,
+        )
+        .unwrap();
+        user_store.add_user(user.clone()).await.unwrap();
+
+        let issuer = LocalJwtIssuer::new(b"test-secret");
+        let token = generate_auth_token(
+            &issuer,
+            &NoopClaimsEnricher,
+            user.id(),
+            user.email(),
+            TokenType::Access,
+            None,
+            600,
+        )
+        .await
+        .unwrap();
+        let claims = issuer.verify(&token).await.unwrap();
+        assert_eq!(
+            resolve_token_user(&claims, &user_store).await.unwrap(),
+            user
+        );
+
+        // Tokens issued before the subject was the user id are looked up by email
+        let legacy_claims = Claims {
+            sub: Clone::clone(user.email().as_ref()),
+            ..claims
+        };
+        assert_eq!(legacy_claims.user_id(), None);
+        assert_eq!(
+            resolve_token_user(&legacy_claims, &user_store)
+                .await
+                .unwrap(),
+            user
+        );
+
+        let unknown_claims = Claims {
+            sub: Secret::from(UserId::new().to_string()),
+            ..legacy_claims
+        };
+        assert!(matches!(
+            resolve_token_user(&unknown_claims, &user_store).await,
+            Err(TokenAuthError::InvalidToken)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_validate_rejects_other_token_type() {
+        let config = AuthServiceSetting::load();
+        // Signed with the access secret, as if both types shared it
+        let issuer = LocalJwtIssuer::new(config.auth.jwt.secret.expose_secret().as_bytes());
+        let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
+        let banned_token_store = HashSetBannedTokenStore::default();
+        let token = generate_auth_token(
+            &issuer,
+            &NoopClaimsEnricher,
+            &UserId::new(),
+            &email,
+            TokenType::Elevated,
+            None,
+            600,
+        )
+        .await
+        .unwrap();
+
+        let result = validate_auth_token(&token, &banned_token_store).await;
+        assert!(matches!(result, Err(TokenAuthError::InvalidToken)));
+    }
+
     #[tokio::test]
     async fn test_ban_token() {
         let config = AuthServiceSetting::load();
         let token_ttl = config.auth.jwt.time_to_live;
-        let jwt_secret = config.auth.jwt.secret.expose_secret().as_bytes();
+        let issuer = LocalJwtIssuer::new(config.auth.jwt.secret.expose_secret().as_bytes());
         let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
         let banned_token_store = HashSetBannedTokenStore::default();
-        let token = generate_auth_token(&email, token_ttl, jwt_secret).unwrap();
+        let token = generate_auth_token(
+            &issuer,
+            &NoopClaimsEnricher,
+            &UserId::new(),
+            &email,
+            TokenType::Access,
+            None,
+            token_ttl,
+        )
+        .await
+        .unwrap();
 
-        banned_token_store.ban_token(token.clone()).await.unwrap();
+        banned_token_store
+            .ban_token(token_ban_key(&token))
+            .await
+            .unwrap();
         let result = validate_auth_token(&token, &banned_token_store).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_validate_elevated_token_scope() {
+        let config = AuthServiceSetting::load();
+        let token_ttl = config.auth.elevated_jwt.time_to_live;
+        let issuer =
+            LocalJwtIssuer::new(config.auth.elevated_jwt.secret.expose_secret().as_bytes());
+        let email = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
+        let banned_token_store = HashSetBannedTokenStore::default();
+        let token = generate_auth_token(
+            &issuer,
+            &NoopClaimsEnricher,
+            &UserId::new(),
+            &email,
+            TokenType::Elevated,
+            Some(ElevationScope::ChangePassword),
+            token_ttl,
+        )
+        .await
+        .unwrap();
+
+        let result = validate_elevated_auth_token_for(
+            &token,
+            &banned_token_store,
+            ElevationScope::ChangePassword,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.scope, Some(ElevationScope::ChangePassword));
+
+        let result = validate_elevated_auth_token_for(
+            &token,
+            &banned_token_store,
+            ElevationScope::DeleteAccount,
+        )
+        .await;
+        assert!(matches!(result, Err(TokenAuthError::InsufficientScope)));
+
+        let unscoped = generate_auth_token(
+            &issuer,
+            &NoopClaimsEnricher,
+            &UserId::new(),
+            &email,
+            TokenType::Elevated,
+            None,
+            token_ttl,
+        )
+        .await
+        .unwrap();
+        let result = validate_elevated_auth_token_for(
+            &unscoped,
+            &banned_token_store,
+            ElevationScope::ChangePassword,
+        )
+        .await;
+        assert!(matches!(result, Err(TokenAuthError::InsufficientScope)));
+    }
 }
@@ -0,0 +1,154 @@
+use axum::{
+    extract::Request,
+    http::{
+        HeaderValue, StatusCode,
+        header::{COOKIE, SET_COOKIE},
+    },
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::{CookieJar, cookie::Cookie};
+use secrecy::ExposeSecret;
+use tempered_application::{LocalJwtIssuer, TokenIssuer, TokenType};
+
+use crate::config::settings::{AuthServiceSetting, JWTConfig};
+
+use super::jwt::{create_auth_cookie, create_removal_cookie};
+
+/// Middleware that moves auth cookies from one of their `legacy_cookie_names`
+/// to the configured cookie name
+///
+/// A legacy cookie holding a validly signed token of the right type is passed on
+/// to the handler under the current name. Unless the handler rejects it or sets
+/// the cookie itself, the response re-issues it under the current name and clears
+/// the legacy cookie. Legacy cookies are assumed to share the configured domain.
+pub async fn migrate_legacy_cookies(jar: CookieJar, mut request: Request, next: Next) -> Response {
+    let config = AuthServiceSetting::load();
+    let mut migrations = Vec::new();
+
+    for (jwt_config, token_type) in [
+        (&config.auth.jwt, TokenType::Access),
+        (&config.auth.elevated_jwt, TokenType::Elevated),
+    ] {
+        if jar.get(&jwt_config.cookie_name).is_some() {
+            continue;
+        }
+        let Some(legacy) = find_legacy_cookie(&jar, jwt_config) else {
+            continue;
+        };
+        if !is_valid_token(legacy.value(), jwt_config, token_type).await {
+            continue;
+        }
+
+        let cookie = format!("{}={}", jwt_config.cookie_name, legacy.value());
+        if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+            request.headers_mut().append(COOKIE, cookie);
+            migrations.push((jwt_config, legacy.clone()));
+        }
+    }
+
+    let mut response = next.run(request).await;
+    if migrations.is_empty() || response.status() == StatusCode::UNAUTHORIZED {
+        return response;
+    }
+
+    for (jwt_config, legacy) in migrations {
+        let mut set_cookies = Vec::new();
+        if !sets_cookie(&response, &jwt_config.cookie_name) {
+            let mut cookie = create_auth_cookie(legacy.value().to_owned(), &jwt_config.cookie_name);
+            cookie.set_http_only(jwt_config.http_only);
+            if let Some(domain) = &jwt_config.cookie_domain {
+                cookie.set_domain(domain.clone());
+            }
+            set_cookies.push(cookie.to_string());
+        }
+        set_cookies.push(
+            create_removal_cookie(legacy.name(), jwt_config.cookie_domain.as_deref()).to_string(),
+        );
+
+        for cookie in set_cookies {
+            if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+                response.headers_mut().append(SET_COOKIE, cookie);
+            }
+        }
+    }
+    response
+}
+
+/// The first of `jwt_config`'s legacy cookies present in `jar`
+fn find_legacy_cookie<'a>(
+    jar: &'a CookieJar,
+    jwt_config: &JWTConfig,
+) -> Option<&'a Cookie<'static>> {
+    jwt_config
+        .legacy_cookie_names
+        .iter()
+        .find_map(|name| jar.get(name))
+}
+
+/// Whether `token` is an unexpired `token_type` token signed with one of
+/// `jwt_config`'s secrets. Bans are left to the handler.
+async fn is_valid_token(token: &str, jwt_config: &JWTConfig, token_type: TokenType) -> bool {
+    for secret in jwt_config.verification_secrets() {
+        let issuer = LocalJwtIssuer::new(secret.expose_secret().as_bytes());
+        if let Ok(claims) = issuer.verify(token).await {
+            return claims.is_token_type(token_type);
+        }
+    }
+    false
+}
+
+/// Whether a handler already set or removed `cookie_name` on `response`
+fn sets_cookie(response: &Response, cookie_name: &str) -> bool {
+    response
+        .headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| {
+            value
+                .split_once('=')
+                .is_some_and(|(name, _)| name.trim() == cookie_name)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use tempered_core::UserId;
+
+    use super::*;
+
+    #[test]
+    fn test_sets_cookie() {
+        let mut response = Response::new(axum::body::Body::empty());
+        response
+            .headers_mut()
+            .append(SET_COOKIE, HeaderValue::from_static("jwt_old=; Max-Age=0"));
+        assert!(!sets_cookie(&response, "jwt"));
+
+        response
+            .headers_mut()
+            .append(SET_COOKIE, HeaderValue::from_static("jwt=token; Path=/"));
+        assert!(sets_cookie(&response, "jwt"));
+    }
+
+    #[tokio::test]
+    async fn test_is_valid_token() {
+        let config = AuthServiceSetting::load();
+        let secret = config.auth.jwt.secret.expose_secret().as_bytes();
+        let token = LocalJwtIssuer::new(secret)
+            .issue(
+                &UserId::new(),
+                600,
+                TokenType::Access,
+                None,
+                Default::default(),
+            )
+            .await
+            .unwrap();
+
+        assert!(is_valid_token(&token, &config.auth.jwt, TokenType::Access).await);
+        assert!(!is_valid_token(&token, &config.auth.jwt, TokenType::Elevated).await);
+        assert!(!is_valid_token("not-a-jwt", &config.auth.jwt, TokenType::Access).await);
+    }
+}
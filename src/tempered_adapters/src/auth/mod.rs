@@ -1,7 +1,22 @@
+pub mod api_key;
+pub mod audit;
+pub mod bearer;
+pub mod cookie_signer;
+pub mod csrf;
 pub mod jwt;
+pub mod legacy_cookies;
 
+pub use api_key::{API_KEY_HEADER, extract_api_key};
+pub use audit::TracingAuditSink;
+pub use bearer::{AuthorizationHeaderError, bearer_token};
+pub use cookie_signer::CookieSigner;
+pub use csrf::{create_csrf_cookie, generate_csrf_token};
 pub use jwt::{
-    Claims, TokenAuthError, create_auth_cookie, create_removal_cookie, extract_token,
-    generate_auth_cookie, generate_elevated_auth_cookie, validate_auth_token,
-    validate_elevated_auth_token,
+    ACTOR_CLAIM, Claims, ClaimsEnricher, ElevationScope, LocalJwtIssuer, NoopClaimsEnricher,
+    TokenAuthError, TokenIssuer, TokenMinter, TokenType, create_auth_cookie, create_removal_cookie,
+    create_two_fa_code_hasher, create_two_fa_token_signer, extract_token, generate_auth_cookie,
+    generate_auth_token, generate_elevated_auth_cookie, issue_token_for, resolve_token_user,
+    validate_auth_token, validate_elevated_auth_token, validate_elevated_auth_token_for,
+    validate_token_with_issuer,
 };
+pub use legacy_cookies::migrate_legacy_cookies;
@@ -5,13 +5,15 @@ use crate::config::settings::AuthServiceSetting;
 pub mod env {
     pub const JWT_SECRET_ENV_VAR: &str = "JWT_SECRET";
     pub const JWT_ELEVATED_SECRET_ENV_VAR: &str = "JWT_ELEVATED_SECRET";
+    pub const JWT_SECRET_FILE_ENV_VAR: &str = "JWT_SECRET_FILE";
+    pub const JWT_ELEVATED_SECRET_FILE_ENV_VAR: &str = "JWT_ELEVATED_SECRET_FILE";
     pub const AUTH_SERVICE_ALLOWED_ORIGINS_ENV_VAR: &str = "AUTH_SERVICE_ALLOWED_ORIGINS";
     pub const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
     pub const REDIS_HOST_NAME_ENV_VAR: &str = "REDIS_HOST_NAME";
     pub const POSTMARK_AUTH_TOKEN_ENV_VAR: &str = "POSTMARK_AUTH_TOKEN";
 }
 
-pub const JWT_COOKIE_NAME: LazyLock<&'static str> = LazyLock::new(|| {
+pub static JWT_COOKIE_NAME: LazyLock<&'static str> = LazyLock::new(|| {
     let cookie_name = AuthServiceSetting::load().auth.jwt.cookie_name.clone();
     Box::leak(cookie_name.into_boxed_str())
 });
@@ -2,4 +2,10 @@ pub mod constants;
 pub mod settings;
 
 pub use constants::*;
-pub use settings::{AllowedOrigins, AuthServiceSetting, Config};
+pub use settings::{
+    AdminConfig, AllowedOrigins, AuthServiceSetting, ChangePasswordConfig, Config,
+    ConfigValidationError, CookiePrefix, CsrfConfig, ElevateConfig, EmailTemplateConfig,
+    EmailTemplates, ErrorFormat, JsonCasing, LoginConfig, LogoutConfig, PasswordHashingConfig,
+    SecurityConfig, SecurityFinding, SecurityHeadersConfig, SignupConfig, TwoFaConfig,
+    WwwAuthenticateConfig,
+};
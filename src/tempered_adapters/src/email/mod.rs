@@ -1,5 +1,11 @@
 pub mod mock_email_client;
 pub mod postmark_email_client;
+pub mod spawning_email_client;
+pub mod templating_email_client;
+pub mod tracing_email_client;
 
 pub use mock_email_client::MockEmailClient;
 pub use postmark_email_client::PostmarkEmailClient;
+pub use spawning_email_client::SpawningEmailClient;
+pub use templating_email_client::{EmailTemplate, TemplateError, TemplatingEmailClient};
+pub use tracing_email_client::TracingEmailClient;
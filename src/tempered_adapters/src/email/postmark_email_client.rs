@@ -1,6 +1,6 @@
 use reqwest::{Client, Url};
 use secrecy::{ExposeSecret, Secret};
-use tempered_core::{Email, EmailClient};
+use tempered_core::{Email, EmailClient, EmailKind};
 
 #[derive(Clone)]
 pub struct PostmarkEmailClient {
@@ -8,6 +8,7 @@ pub struct PostmarkEmailClient {
     base_url: String,
     sender: Email,
     authorization_token: Secret<String>,
+    two_fa_template_id: Option<u64>,
 }
 
 impl PostmarkEmailClient {
@@ -22,31 +23,42 @@ impl PostmarkEmailClient {
             base_url,
             sender,
             authorization_token,
+            two_fa_template_id: None,
         }
     }
-}
 
-#[async_trait::async_trait]
-impl EmailClient for PostmarkEmailClient {
-    #[tracing::instrument(name = "Sending email", skip_all)]
-    async fn send_email(
+    /// Send 2FA codes through a Postmark template instead of as a plain email
+    ///
+    /// Only emails sent as [`EmailKind::TwoFaCode`] use it, with `subject` and
+    /// `code` as its model.
+    pub fn with_two_fa_template(mut self, template_id: Option<u64>) -> Self {
+        self.two_fa_template_id = template_id;
+        self
+    }
+
+    /// Send an email rendered by the Postmark template `template_id`
+    #[tracing::instrument(name = "Sending Postmark template email", skip(self, recipient, model))]
+    pub async fn send_template<M: serde::Serialize>(
         &self,
         recipient: &Email,
-        subject: &str,
-        content: &str,
+        template_id: u64,
+        model: &M,
     ) -> Result<(), String> {
-        let base = Url::parse(&self.base_url).map_err(|e| e.to_string())?;
-        let url = base.join("/email").map_err(|e| e.to_string())?;
-
-        let request_body = SendEmailRequest {
+        let request_body = SendTemplateEmailRequest {
             from: self.sender.as_ref().expose_secret(),
             to: recipient.as_ref().expose_secret(),
-            subject,
-            html_body: content,
-            text_body: content,
+            template_id,
+            template_model: model,
             message_stream: MESSAGE_STREAM,
         };
 
+        self.post("/email/withTemplate", &request_body).await
+    }
+
+    async fn post<B: serde::Serialize>(&self, path: &str, body: &B) -> Result<(), String> {
+        let base = Url::parse(&self.base_url).map_err(|e| e.to_string())?;
+        let url = base.join(path).map_err(|e| e.to_string())?;
+
         let request = self
             .http_client
             .post(url)
@@ -54,7 +66,7 @@ impl EmailClient for PostmarkEmailClient {
                 POSTMARK_AUTH_HEADER,
                 self.authorization_token.expose_secret(),
             )
-            .json(&request_body);
+            .json(body);
 
         request
             .send()
@@ -67,6 +79,44 @@ impl EmailClient for PostmarkEmailClient {
     }
 }
 
+#[async_trait::async_trait]
+impl EmailClient for PostmarkEmailClient {
+    #[tracing::instrument(name = "Sending email", skip_all)]
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), String> {
+        let request_body = SendEmailRequest {
+            from: self.sender.as_ref().expose_secret(),
+            to: recipient.as_ref().expose_secret(),
+            subject,
+            html_body: content,
+            text_body: content,
+            message_stream: MESSAGE_STREAM,
+        };
+
+        self.post("/email", &request_body).await
+    }
+
+    async fn send_email_of_kind(
+        &self,
+        kind: EmailKind,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), String> {
+        match (kind, self.two_fa_template_id) {
+            (EmailKind::TwoFaCode, Some(template_id)) => {
+                let model = serde_json::json!({ "subject": subject, "code": content });
+                self.send_template(recipient, template_id, &model).await
+            }
+            _ => self.send_email(recipient, subject, content).await,
+        }
+    }
+}
+
 const MESSAGE_STREAM: &str = "outbound";
 const POSTMARK_AUTH_HEADER: &str = "X-Postmark-Server-Token";
 
@@ -80,3 +130,13 @@ struct SendEmailRequest<'a> {
     text_body: &'a str,
     message_stream: &'a str,
 }
+
+#[derive(serde::Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct SendTemplateEmailRequest<'a, M> {
+    from: &'a str,
+    to: &'a str,
+    template_id: u64,
+    template_model: &'a M,
+    message_stream: &'a str,
+}
@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use tempered_core::{AuditEvent, AuditSink, Email, EmailClient, EmailKind};
+use tokio::sync::mpsc;
+
+struct EmailJob {
+    kind: Option<EmailKind>,
+    recipient: Email,
+    subject: String,
+    content: String,
+}
+
+/// Email client that hands emails to a background task, so a slow provider doesn't
+/// delay the response that triggered the email
+///
+/// Emails wait in a bounded queue and `send_email` fails once it is full. Delivery
+/// failures can't reach the caller anymore and are recorded as
+/// [`AuditEvent::EmailDeliveryFailed`] instead. The task sends what is left in the
+/// queue and exits once every clone of the client has been dropped.
+pub struct SpawningEmailClient<E> {
+    inner: Arc<E>,
+    queue: Option<mpsc::Sender<EmailJob>>,
+}
+
+impl<E> SpawningEmailClient<E>
+where
+    E: EmailClient + 'static,
+{
+    /// Send through `inner` on a task with room for `queue_size` waiting emails.
+    /// A `queue_size` of 0 sends inline instead.
+    ///
+    /// Must be called within a Tokio runtime unless `queue_size` is 0.
+    pub fn new<S>(inner: E, queue_size: usize, audit_sink: S) -> Self
+    where
+        S: AuditSink + 'static,
+    {
+        let inner = Arc::new(inner);
+        if queue_size == 0 {
+            return Self { inner, queue: None };
+        }
+
+        let (sender, mut receiver) = mpsc::channel::<EmailJob>(queue_size);
+        let worker_client = inner.clone();
+        tokio::spawn(async move {
+            while let Some(job) = receiver.recv().await {
+                let result = match job.kind {
+                    Some(kind) => {
+                        worker_client
+                            .send_email_of_kind(kind, &job.recipient, &job.subject, &job.content)
+                            .await
+                    }
+                    None => {
+                        worker_client
+                            .send_email(&job.recipient, &job.subject, &job.content)
+                            .await
+                    }
+                };
+                if let Err(error) = result {
+                    audit_sink
+                        .record(AuditEvent::EmailDeliveryFailed {
+                            recipient: job.recipient,
+                            subject: job.subject,
+                            error,
+                        })
+                        .await;
+                }
+            }
+        });
+
+        Self {
+            inner,
+            queue: Some(sender),
+        }
+    }
+}
+
+impl<E> Clone for SpawningEmailClient<E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<E> EmailClient for SpawningEmailClient<E>
+where
+    E: EmailClient + 'static,
+{
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), String> {
+        let Some(queue) = &self.queue else {
+            return self.inner.send_email(recipient, subject, content).await;
+        };
+
+        enqueue(queue, None, recipient, subject, content)
+    }
+
+    async fn send_email_of_kind(
+        &self,
+        kind: EmailKind,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), String> {
+        let Some(queue) = &self.queue else {
+            return self
+                .inner
+                .send_email_of_kind(kind, recipient, subject, content)
+                .await;
+        };
+
+        enqueue(queue, Some(kind), recipient, subject, content)
+    }
+}
+
+fn enqueue(
+    queue: &mpsc::Sender<EmailJob>,
+    kind: Option<EmailKind>,
+    recipient: &Email,
+    subject: &str,
+    content: &str,
+) -> Result<(), String> {
+    let job = EmailJob {
+        kind,
+        recipient: recipient.clone(),
+        subject: subject.to_owned(),
+        content: content.to_owned(),
+    };
+    queue.try_send(job).map_err(|e| match e {
+        mpsc::error::TrySendError::Full(_) => "Email queue is full".to_owned(),
+        mpsc::error::TrySendError::Closed(_) => "Email task has stopped".to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use secrecy::Secret;
+    use tokio::sync::{Mutex, Notify};
+
+    use super::*;
+
+    /// Fails every email after signalling that it started sending
+    #[derive(Default)]
+    struct StalledEmailClient {
+        started: Arc<Notify>,
+        release: Arc<Notify>,
+    }
+
+    #[async_trait::async_trait]
+    impl EmailClient for StalledEmailClient {
+        async fn send_email(&self, _: &Email, _: &str, _: &str) -> Result<(), String> {
+            self.started.notify_one();
+            self.release.notified().await;
+            Err("provider unavailable".to_owned())
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingAuditSink {
+        failures: Arc<Mutex<Vec<String>>>,
+        recorded: Arc<Notify>,
+    }
+
+    #[async_trait::async_trait]
+    impl AuditSink for RecordingAuditSink {
+        async fn record(&self, event: AuditEvent) {
+            if let AuditEvent::EmailDeliveryFailed { error, .. } = event {
+                self.failures.lock().await.push(error);
+                self.recorded.notify_one();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sends_in_background_and_records_failures() {
+        let recipient = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
+        let email_client = StalledEmailClient::default();
+        let (started, release) = (email_client.started.clone(), email_client.release.clone());
+        let audit_sink = RecordingAuditSink::default();
+        let client = SpawningEmailClient::new(email_client, 1, audit_sink.clone());
+
+        // Returns while the provider is still stalled
+        assert_eq!(client.send_email(&recipient, "2FA Code", "1").await, Ok(()));
+        started.notified().await;
+
+        // One email waits in the queue, the next doesn't fit
+        assert_eq!(client.send_email(&recipient, "2FA Code", "2").await, Ok(()));
+        assert_eq!(
+            client.send_email(&recipient, "2FA Code", "3").await,
+            Err("Email queue is full".to_owned())
+        );
+
+        release.notify_one();
+        tokio::time::timeout(Duration::from_secs(1), audit_sink.recorded.notified())
+            .await
+            .unwrap();
+        assert_eq!(
+            *audit_sink.failures.lock().await,
+            ["provider unavailable".to_owned()]
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use handlebars::Handlebars;
+use serde::Serialize;
+use serde_json::json;
+use tempered_core::{Email, EmailClient, EmailKind};
+use thiserror::Error;
+
+const TEMPLATE_EXTENSION: &str = "hbs";
+
+/// The emails the auth service can send, each rendered from `<name>.hbs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTemplate {
+    TwoFa,
+    PasswordReset,
+    EmailVerify,
+}
+
+impl EmailTemplate {
+    pub const ALL: [EmailTemplate; 3] = [Self::TwoFa, Self::PasswordReset, Self::EmailVerify];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TwoFa => "two_fa",
+            Self::PasswordReset => "password_reset",
+            Self::EmailVerify => "email_verify",
+        }
+    }
+
+    /// Subject used when none is configured in `email_client.templates`
+    pub fn default_subject(&self) -> &'static str {
+        match self {
+            Self::TwoFa => "2FA Code",
+            Self::PasswordReset => "Password Reset",
+            Self::EmailVerify => "Verify Your Email",
+        }
+    }
+}
+
+impl From<EmailKind> for EmailTemplate {
+    fn from(kind: EmailKind) -> Self {
+        match kind {
+            EmailKind::TwoFaCode => Self::TwoFa,
+            EmailKind::PasswordReset => Self::PasswordReset,
+            EmailKind::EmailVerify => Self::EmailVerify,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("Missing email template '{name}' at {}", path.display())]
+    MissingTemplate { name: &'static str, path: PathBuf },
+    #[error("Invalid email template '{name}': {reason}")]
+    InvalidTemplate { name: &'static str, reason: String },
+    #[error("Failed to render email template: {0}")]
+    RenderError(String),
+}
+
+/// Email client decorator that renders handlebars templates before handing
+/// the HTML to the inner client
+///
+/// All templates are loaded when the client is created, so a missing or broken
+/// template is reported at startup rather than on the first login.
+#[derive(Clone)]
+pub struct TemplatingEmailClient<E> {
+    inner: E,
+    registry: Arc<Handlebars<'static>>,
+}
+
+impl<E> TemplatingEmailClient<E>
+where
+    E: EmailClient,
+{
+    /// Create a templating client
+    ///
+    /// # Arguments
+    /// * `inner` - Client used to deliver the rendered email
+    /// * `template_dir` - Directory containing `two_fa.hbs`, `password_reset.hbs` and `email_verify.hbs`
+    ///
+    /// # Returns
+    /// The client, or an error if any template is missing or fails to parse
+    pub fn new(inner: E, template_dir: impl AsRef<Path>) -> Result<Self, TemplateError> {
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+
+        for template in EmailTemplate::ALL {
+            let name = template.name();
+            let path = template_dir
+                .as_ref()
+                .join(format!("{name}.{TEMPLATE_EXTENSION}"));
+
+            if !path.is_file() {
+                return Err(TemplateError::MissingTemplate { name, path });
+            }
+
+            registry.register_template_file(name, &path).map_err(|e| {
+                TemplateError::InvalidTemplate {
+                    name,
+                    reason: e.to_string(),
+                }
+            })?;
+        }
+
+        Ok(Self {
+            inner,
+            registry: Arc::new(registry),
+        })
+    }
+
+    /// Render a template with the given context
+    pub fn render<C: Serialize>(
+        &self,
+        template: EmailTemplate,
+        context: &C,
+    ) -> Result<String, TemplateError> {
+        self.registry
+            .render(template.name(), context)
+            .map_err(|e| TemplateError::RenderError(e.to_string()))
+    }
+
+    /// Render a template and send the result through the inner client
+    #[tracing::instrument(name = "Sending templated email", skip(self, recipient, context))]
+    pub async fn send_template<C: Serialize>(
+        &self,
+        recipient: &Email,
+        subject: &str,
+        template: EmailTemplate,
+        context: &C,
+    ) -> Result<(), String> {
+        let content = self.render(template, context).map_err(|e| e.to_string())?;
+
+        self.inner.send_email(recipient, subject, &content).await
+    }
+}
+
+/// Emails sent with `send_email_of_kind` are rendered with the kind's template.
+/// The `two_fa` template receives `code` and `subject` in its context, the others
+/// `link` and `subject`. Plain `send_email` calls are passed on unrendered.
+#[async_trait::async_trait]
+impl<E> EmailClient for TemplatingEmailClient<E>
+where
+    E: EmailClient,
+{
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), String> {
+        self.inner.send_email(recipient, subject, content).await
+    }
+
+    async fn send_email_of_kind(
+        &self,
+        kind: EmailKind,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), String> {
+        let context = match kind {
+            EmailKind::TwoFaCode => json!({ "code": content, "subject": subject }),
+            EmailKind::PasswordReset | EmailKind::EmailVerify => {
+                json!({ "link": content, "subject": subject })
+            }
+        };
+        self.send_template(recipient, subject, kind.into(), &context)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use secrecy::Secret;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingEmailClient {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl EmailClient for RecordingEmailClient {
+        async fn send_email(
+            &self,
+            _recipient: &Email,
+            subject: &str,
+            content: &str,
+        ) -> Result<(), String> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((subject.to_string(), content.to_string()));
+            Ok(())
+        }
+    }
+
+    fn template_dir(templates: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tempered_templates_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, content) in templates {
+            std::fs::write(dir.join(format!("{name}.{TEMPLATE_EXTENSION}")), content).unwrap();
+        }
+        dir
+    }
+
+    fn all_templates() -> PathBuf {
+        template_dir(&[
+            ("two_fa", "<p>Your code is <b>{{code}}</b></p>"),
+            ("password_reset", "<a href=\"{{link}}\">Reset</a>"),
+            ("email_verify", "<a href=\"{{link}}\">Verify</a>"),
+        ])
+    }
+
+    fn recipient() -> Email {
+        Email::try_from(Secret::from("user@example.com".to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_missing_template_is_an_error() {
+        let dir = template_dir(&[("two_fa", "{{code}}")]);
+
+        let result = TemplatingEmailClient::new(RecordingEmailClient::default(), &dir);
+
+        assert!(matches!(
+            result,
+            Err(TemplateError::MissingTemplate {
+                name: "password_reset",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_template_is_an_error() {
+        let dir = template_dir(&[
+            ("two_fa", "{{#if code}}"),
+            ("password_reset", ""),
+            ("email_verify", ""),
+        ]);
+
+        let result = TemplatingEmailClient::new(RecordingEmailClient::default(), &dir);
+
+        assert!(matches!(
+            result,
+            Err(TemplateError::InvalidTemplate { name: "two_fa", .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_email_of_kind_renders_its_template() {
+        let client =
+            TemplatingEmailClient::new(RecordingEmailClient::default(), all_templates()).unwrap();
+
+        client
+            .send_email_of_kind(EmailKind::TwoFaCode, &recipient(), "2FA Code", "123456")
+            .await
+            .unwrap();
+        client
+            .send_email_of_kind(
+                EmailKind::EmailVerify,
+                &recipient(),
+                "Verify",
+                "https://example.com/verify",
+            )
+            .await
+            .unwrap();
+        client
+            .send_email(&recipient(), "Plain", "<p>Hello</p>")
+            .await
+            .unwrap();
+
+        let sent = client.inner.sent.lock().unwrap();
+        assert_eq!(
+            sent.as_slice(),
+            &[
+                (
+                    "2FA Code".to_string(),
+                    "<p>Your code is <b>123456</b></p>".to_string()
+                ),
+                (
+                    "Verify".to_string(),
+                    "<a href=\"https://example.com/verify\">Verify</a>".to_string()
+                ),
+                ("Plain".to_string(), "<p>Hello</p>".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_escapes_html_and_requires_context() {
+        let client =
+            TemplatingEmailClient::new(RecordingEmailClient::default(), all_templates()).unwrap();
+
+        let rendered = client
+            .render(
+                EmailTemplate::PasswordReset,
+                &json!({ "link": "https://example.com/?a=<b>" }),
+            )
+            .unwrap();
+        assert_eq!(
+            rendered,
+            "<a href=\"https://example.com/?a&#x3D;&lt;b&gt;\">Reset</a>"
+        );
+
+        let missing = client.render(EmailTemplate::EmailVerify, &json!({}));
+        assert!(matches!(missing, Err(TemplateError::RenderError(_))));
+    }
+}
@@ -0,0 +1,77 @@
+use secrecy::ExposeSecret;
+use tempered_core::{Email, EmailClient};
+
+/// Email client that sends nothing and logs each email as a tracing event instead,
+/// for staging environments where mail should be observable but never delivered
+///
+/// Digits in the body are masked so 2FA codes don't end up in logs, unless
+/// [`TracingEmailClient::with_unmasked_content`] is set.
+#[derive(Debug, Clone, Default)]
+pub struct TracingEmailClient {
+    unmasked_content: bool,
+}
+
+impl TracingEmailClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Log the body as is, including any 2FA code
+    pub fn with_unmasked_content(mut self, unmasked_content: bool) -> Self {
+        self.unmasked_content = unmasked_content;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailClient for TracingEmailClient {
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), String> {
+        let content = if self.unmasked_content {
+            content.to_owned()
+        } else {
+            mask_digits(content)
+        };
+
+        tracing::info!(
+            recipient = recipient.as_ref().expose_secret(),
+            subject,
+            content,
+            "Email not sent, logged by TracingEmailClient"
+        );
+        Ok(())
+    }
+}
+
+fn mask_digits(content: &str) -> String {
+    content
+        .chars()
+        .map(|c| if c.is_ascii_digit() { '*' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::Secret;
+
+    use super::*;
+
+    #[test]
+    fn test_mask_digits() {
+        assert_eq!(mask_digits("Your code is 123456"), "Your code is ******");
+    }
+
+    #[tokio::test]
+    async fn test_send_email_succeeds() {
+        let recipient = Email::try_from(Secret::from("test@example.com".to_owned())).unwrap();
+
+        let result = TracingEmailClient::new()
+            .send_email(&recipient, "2FA Code", "123456")
+            .await;
+        assert!(result.is_ok());
+    }
+}
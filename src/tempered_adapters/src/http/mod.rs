@@ -1,3 +1,5 @@
+pub mod request;
 pub mod routes;
 
+pub use request::{request_host, request_origin};
 pub use routes::*;
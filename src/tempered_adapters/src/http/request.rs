@@ -0,0 +1,77 @@
+use axum::http::{
+    header::{HOST, ORIGIN},
+    request::Parts,
+};
+
+/// Host the request was sent to, e.g. to pick per-tenant settings
+///
+/// Reads the `Host` header, falling back to the URI authority, which carries
+/// `:authority` for HTTP/2 requests. Includes the port if one was given.
+pub fn request_host(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(HOST)
+        .and_then(|host| host.to_str().ok())
+        .or_else(|| parts.uri.authority().map(|authority| authority.as_str()))
+        .filter(|host| !host.is_empty())
+}
+
+/// Value of the `Origin` header, `None` if it is missing or not valid UTF-8
+pub fn request_origin(parts: &Parts) -> Option<&str> {
+    parts.headers.get(ORIGIN)?.to_str().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+
+    use super::*;
+
+    fn parts(request: Request<()>) -> Parts {
+        request.into_parts().0
+    }
+
+    #[test]
+    fn test_request_host_from_header() {
+        let parts = parts(
+            Request::get("/login")
+                .header(HOST, "tenant.example.com:8443")
+                .body(())
+                .unwrap(),
+        );
+        assert_eq!(request_host(&parts), Some("tenant.example.com:8443"));
+    }
+
+    #[test]
+    fn test_request_host_from_authority() {
+        let parts = parts(
+            Request::get("https://tenant.example.com/login")
+                .body(())
+                .unwrap(),
+        );
+        assert_eq!(request_host(&parts), Some("tenant.example.com"));
+    }
+
+    #[test]
+    fn test_request_host_missing() {
+        let parts = parts(Request::get("/login").body(()).unwrap());
+        assert_eq!(request_host(&parts), None);
+    }
+
+    #[test]
+    fn test_request_origin() {
+        let parts = parts(
+            Request::get("/login")
+                .header(ORIGIN, "https://app.example.com")
+                .body(())
+                .unwrap(),
+        );
+        assert_eq!(request_origin(&parts), Some("https://app.example.com"));
+    }
+
+    #[test]
+    fn test_request_origin_missing() {
+        let parts = parts(Request::get("/login").body(()).unwrap());
+        assert_eq!(request_origin(&parts), None);
+    }
+}
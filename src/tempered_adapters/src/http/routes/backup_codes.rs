@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use tempered_application::GenerateBackupCodesUseCase;
+use tempered_core::{BackupCodeStore, BannedTokenStore, UserStore};
+
+use crate::auth::{
+    ElevationScope, extract_token, resolve_token_user, validate_elevated_auth_token_for,
+};
+
+use super::error::AuthApiError;
+use super::json::CasedJson;
+
+/// Backup code store shared with `/verify-2fa` as a request extension
+pub type BackupCodes = Arc<dyn BackupCodeStore>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupCodesResponse {
+    /// The plaintext codes, only returned this once
+    pub codes: Vec<String>,
+}
+
+/// Replace the user's 2FA backup codes with a fresh set, guarded by an elevated
+/// token for [`ElevationScope::TwoFaSettings`]
+#[tracing::instrument(name = "Regenerate backup codes", skip_all)]
+pub async fn regenerate_backup_codes<U, C, B>(
+    State((user_store, backup_code_store, banned_token_store)): State<(U, C, B)>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AuthApiError>
+where
+    U: UserStore + Clone + 'static,
+    C: BackupCodeStore + Clone + 'static,
+    B: BannedTokenStore + Clone + 'static,
+{
+    let config = crate::config::AuthServiceSetting::load();
+    let jwt_elevated_cookie_name = &config.auth.elevated_jwt.cookie_name;
+
+    // Extract and validate elevated token
+    let token = extract_token(&jar, jwt_elevated_cookie_name)?;
+    let claims =
+        validate_elevated_auth_token_for(token, &banned_token_store, ElevationScope::TwoFaSettings)
+            .await?;
+
+    let email = resolve_token_user(&claims, &user_store)
+        .await?
+        .email()
+        .clone();
+
+    let use_case = GenerateBackupCodesUseCase::new(backup_code_store);
+    let codes = use_case.execute(email).await?;
+
+    let response = BackupCodesResponse {
+        codes: codes
+            .iter()
+            .map(|code| code.expose_secret().to_owned())
+            .collect(),
+    };
+
+    Ok((jar, (StatusCode::OK, CasedJson(response))))
+}
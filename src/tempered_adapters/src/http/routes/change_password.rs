@@ -1,23 +1,30 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum::{Extension, Json, extract::State, http::StatusCode, response::IntoResponse};
 use axum_extra::extract::CookieJar;
 use secrecy::Secret;
 use serde::Deserialize;
-use tempered_application::ChangePasswordUseCase;
-use tempered_core::{BannedTokenStore, Email, Password, UserStore};
+use tempered_application::{ChangePasswordUseCase, PasswordStrengthPolicy};
+use tempered_core::{BannedTokenStore, Password, UserStore};
 
-use crate::auth::{extract_token, validate_elevated_auth_token};
+use crate::auth::{
+    ElevationScope, extract_token, resolve_token_user, validate_elevated_auth_token_for,
+};
 
 use super::error::AuthApiError;
 
 #[derive(Deserialize)]
 pub struct ChangePasswordRequest {
+    #[serde(alias = "newPassword")]
     new_password: Secret<String>,
+    /// Required when `auth.change_password.require_current_password` is set
+    #[serde(default, alias = "currentPassword")]
+    current_password: Option<Secret<String>>,
 }
 
 #[tracing::instrument(name = "Change Password", skip_all)]
 pub async fn change_password<U, B>(
     State((user_store, banned_token_store)): State<(U, B)>,
     jar: CookieJar,
+    password_strength_policy: Option<Extension<PasswordStrengthPolicy>>,
     Json(request): Json<ChangePasswordRequest>,
 ) -> Result<impl IntoResponse, AuthApiError>
 where
@@ -29,15 +36,34 @@ where
 
     // Extract and validate elevated token
     let token = extract_token(&jar, jwt_elevated_cookie_name)?;
-    let claim = validate_elevated_auth_token(token, &banned_token_store).await?;
+    let claim = validate_elevated_auth_token_for(
+        token,
+        &banned_token_store,
+        ElevationScope::ChangePassword,
+    )
+    .await?;
 
     // Parse domain entities
-    let email = Email::try_from(claim.sub)?;
+    let email = resolve_token_user(&claim, &user_store)
+        .await?
+        .email()
+        .clone();
     let new_password = Password::try_from(request.new_password)?;
 
     // Use the change password use case
-    let use_case = ChangePasswordUseCase::new(user_store);
-    use_case.execute(email, new_password).await?;
+    let use_case = ChangePasswordUseCase::new(user_store)
+        .with_password_strength_policy(password_strength_policy.map(|Extension(policy)| policy));
+    if config.auth.change_password.require_current_password {
+        let current_password = request.current_password.ok_or_else(|| {
+            AuthApiError::InvalidInput("current password is required".to_string())
+        })?;
+        let current_password = Password::try_from(current_password)?;
+        use_case
+            .execute_with_current_password(email, current_password, new_password)
+            .await?;
+    } else {
+        use_case.execute(email, new_password).await?;
+    }
 
     Ok((jar, StatusCode::OK))
 }
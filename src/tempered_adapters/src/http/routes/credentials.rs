@@ -0,0 +1,171 @@
+use axum::{
+    Json,
+    extract::{FromRequest, Request, rejection::JsonRejection},
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use super::error::AuthApiError;
+
+/// JSON extractor for credential payloads.
+///
+/// Behaves like [`Json`], but rejects with a [`CredentialsRejection`] that
+/// names the offending field instead of surfacing the raw serde error.
+#[derive(Debug)]
+pub struct Credentials<T>(pub T);
+
+/// Rejection produced when a credentials body cannot be parsed
+#[derive(Debug, Error)]
+pub enum CredentialsRejection {
+    #[error("field '{0}' is required")]
+    MissingField(String),
+
+    #[error("field '{field}' has the wrong type, expected {expected}")]
+    InvalidType { field: String, expected: String },
+
+    #[error("request body is not valid JSON")]
+    MalformedBody,
+
+    #[error("expected request with `Content-Type: application/json`")]
+    MissingJsonContentType,
+
+    #[error("{0}")]
+    Other(String),
+}
+
+impl<T, S> FromRequest<S> for Credentials<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = CredentialsRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        // Let axum handle content type and JSON syntax, then deserialize ourselves
+        // so the failing field is known
+        let Json(value) = Json::<serde_json::Value>::from_request(req, state).await?;
+
+        serde_path_to_error::deserialize(value)
+            .map(Credentials)
+            .map_err(CredentialsRejection::from)
+    }
+}
+
+impl From<JsonRejection> for CredentialsRejection {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::JsonSyntaxError(_) => CredentialsRejection::MalformedBody,
+            JsonRejection::MissingJsonContentType(_) => {
+                CredentialsRejection::MissingJsonContentType
+            }
+            other => CredentialsRejection::Other(other.body_text()),
+        }
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_json::Error>> for CredentialsRejection {
+    fn from(error: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        let path = error.path().to_string();
+        let message = error.inner().to_string();
+
+        // serde_json formats these as "missing field `name`" and
+        // "invalid type: <found>, expected <expected>"
+        if let Some(field) = message
+            .strip_prefix("missing field `")
+            .and_then(|rest| rest.split('`').next())
+        {
+            let field = match path.as_str() {
+                "." => field.to_owned(),
+                parent => format!("{parent}.{field}"),
+            };
+            return CredentialsRejection::MissingField(field);
+        }
+
+        if message.starts_with("invalid type") {
+            let expected = message
+                .split_once(", expected ")
+                .map(|(_, expected)| expected.to_owned())
+                .unwrap_or_default();
+            return CredentialsRejection::InvalidType {
+                field: path,
+                expected,
+            };
+        }
+
+        CredentialsRejection::Other(message)
+    }
+}
+
+impl IntoResponse for CredentialsRejection {
+    fn into_response(self) -> Response {
+        AuthApiError::InvalidInput(self.to_string()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct TestCredentials {
+        email: String,
+        password: String,
+        #[serde(rename = "requires2FA")]
+        requires_2fa: bool,
+    }
+
+    fn rejection_for(body: serde_json::Value) -> CredentialsRejection {
+        serde_path_to_error::deserialize::<_, TestCredentials>(body)
+            .expect_err("body should be rejected")
+            .into()
+    }
+
+    #[test]
+    fn missing_field_names_the_field() {
+        let rejection = rejection_for(serde_json::json!({
+            "email": "test@example.com",
+            "requires2FA": false,
+        }));
+
+        assert_eq!(rejection.to_string(), "field 'password' is required");
+    }
+
+    #[test]
+    fn request_fields_accept_snake_case_aliases() {
+        let signup: crate::http::routes::SignupRequest =
+            serde_json::from_value(serde_json::json!({
+                "email": "test@example.com",
+                "password": "password123",
+                "requires_2fa": true,
+            }))
+            .unwrap();
+        assert!(signup.requires_2fa);
+
+        let verify: crate::http::routes::Verify2FARequest =
+            serde_json::from_value(serde_json::json!({
+                "email": "test@example.com",
+                "login_attempt_id": "attempt",
+                "two_fa_code": "123456",
+            }))
+            .unwrap();
+        assert_eq!(verify.login_attempt_id, "attempt");
+        assert_eq!(verify.two_factor_code, "123456");
+    }
+
+    #[test]
+    fn wrong_type_names_the_field_and_expected_type() {
+        let rejection = rejection_for(serde_json::json!({
+            "email": "test@example.com",
+            "password": "password123",
+            "requires2FA": 10,
+        }));
+
+        assert_eq!(
+            rejection.to_string(),
+            "field 'requires2FA' has the wrong type, expected a boolean"
+        );
+    }
+}
@@ -1,9 +1,11 @@
 use axum::{extract::State, http::StatusCode, response::IntoResponse};
 use axum_extra::extract::CookieJar;
 use tempered_application::DeleteAccountUseCase;
-use tempered_core::{BannedTokenStore, Email, UserStore};
+use tempered_core::{BannedTokenStore, UserStore};
 
-use crate::auth::{extract_token, validate_elevated_auth_token};
+use crate::auth::{
+    ElevationScope, extract_token, resolve_token_user, validate_elevated_auth_token_for,
+};
 
 use super::error::AuthApiError;
 
@@ -21,14 +23,19 @@ where
 
     // Extract and validate elevated token
     let elevated_token = extract_token(&jar, jwt_elevated_cookie_name)?;
-    let claims = validate_elevated_auth_token(elevated_token, &banned_token_store).await?;
+    let claims = validate_elevated_auth_token_for(
+        elevated_token,
+        &banned_token_store,
+        ElevationScope::DeleteAccount,
+    )
+    .await?;
 
-    // Parse email from claims
-    let user_email = Email::try_from(claims.sub)?;
+    // Look up the user the token was issued for
+    let user = resolve_token_user(&claims, &user_store).await?;
 
     // Use the delete account use case
     let use_case = DeleteAccountUseCase::new(user_store);
-    use_case.execute(user_email).await?;
+    use_case.execute(user.email().clone()).await?;
 
     Ok((jar, StatusCode::NO_CONTENT))
 }
@@ -1,11 +1,15 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use std::time::Duration;
+
+use axum::{Extension, Json, extract::State, http::StatusCode, response::IntoResponse};
 use axum_extra::extract::CookieJar;
 use secrecy::Secret;
 use serde::Deserialize;
 use tempered_application::ElevateUseCase;
-use tempered_core::{BannedTokenStore, Email, Password, UserStore};
+use tempered_core::{BannedTokenStore, Email, Password, RateLimitStore, UserStore};
 
-use crate::auth::{generate_elevated_auth_cookie, validate_auth_token};
+use crate::auth::{
+    ElevationScope, TokenMinter, generate_elevated_auth_cookie, validate_auth_token,
+};
 use crate::config::AuthServiceSetting;
 
 use super::error::AuthApiError;
@@ -14,17 +18,22 @@ use super::error::AuthApiError;
 pub struct ElevateRequest {
     pub email: Secret<String>,
     pub password: Secret<String>,
+    /// Restrict the elevated token to a single operation
+    #[serde(default)]
+    pub scope: Option<ElevationScope>,
 }
 
 #[tracing::instrument(name = "Elevate auth", skip_all)]
-pub async fn elevate<U, B>(
-    State((user_store, banned_token_store)): State<(U, B)>,
+pub async fn elevate<U, B, R>(
+    State((user_store, banned_token_store, rate_limit_store)): State<(U, B, R)>,
+    token_minter: Option<Extension<TokenMinter>>,
     jar: CookieJar,
     Json(request): Json<ElevateRequest>,
 ) -> Result<impl IntoResponse, AuthApiError>
 where
     U: UserStore + Clone + 'static,
     B: BannedTokenStore + Clone + 'static,
+    R: RateLimitStore + Clone + 'static,
 {
     let config = AuthServiceSetting::load();
 
@@ -40,11 +49,32 @@ where
     let password = Password::try_from(request.password)?;
 
     // Use the elevate use case to re-authenticate
-    let use_case = ElevateUseCase::new(user_store);
-    let verified_email = use_case.execute(email, password).await?;
+    let use_case = ElevateUseCase::new(user_store.clone());
+    let verified_email = match config.auth.elevate.min_interval_in_seconds {
+        0 => use_case.execute(email, password).await?,
+        seconds => {
+            use_case
+                .execute_with_cooldown(
+                    email,
+                    password,
+                    &rate_limit_store,
+                    Duration::from_secs(seconds),
+                )
+                .await?
+        }
+    };
 
     // Generate elevated auth cookie
-    let elevated_cookie = generate_elevated_auth_cookie(&verified_email, &config)?;
+    let user = user_store.get_user(&verified_email).await?;
+    let token_minter = token_minter.map(|Extension(m)| m).unwrap_or_default();
+    let elevated_cookie = generate_elevated_auth_cookie(
+        &token_minter,
+        user.id(),
+        &verified_email,
+        request.scope,
+        &config,
+    )
+    .await?;
 
     Ok((jar.add(elevated_cookie), StatusCode::OK))
 }
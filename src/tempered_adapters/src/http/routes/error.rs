@@ -1,22 +1,63 @@
 use axum::{
     Json,
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
 use tempered_application::{
-    ChangePasswordError, DeleteAccountError, ElevateError, LoginError, LogoutError, Verify2FaError,
+    ApiKeyError, BackupCodeError, ChangePasswordError, DeleteAccountError, ElevateError,
+    ForceLogoutError, GetProfileError, LoginError, LogoutError, SignupError, TwoFaSettingsError,
+    Verify2FaError, VerifyPasswordError, VerifyTokenError, WeakPassword,
 };
 use tempered_core::{
-    BannedTokenStoreError, TwoFaCodeStoreError, TwoFaError, UserError, UserStoreError,
+    ApiKeyStoreError, BannedTokenStoreError, TwoFaCodeStoreError, TwoFaError, UserError,
+    UserStoreError,
 };
 use thiserror::Error;
 
-use crate::auth::TokenAuthError;
+use crate::auth::{AuthorizationHeaderError, TokenAuthError};
+use crate::config::{AuthServiceSetting, ErrorFormat};
+
+const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+// Returned for both an unknown email and a wrong password when `auth.login.uniform_auth_errors` is set
+const UNIFORM_AUTH_ERROR_MESSAGE: &str = "Invalid email or password";
 
 #[derive(Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: String,
+    /// How to fix the request, only set for weak passwords
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+    /// Pending login attempt the client can request a new 2FA code for
+    #[serde(
+        rename = "loginAttemptId",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub login_attempt_id: Option<String>,
+}
+
+/// RFC 9457 problem details, used when `auth.error_format` is `problem_json`
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    /// Stable machine-readable error code
+    pub code: String,
+    /// How to fix the request, only set for weak passwords
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+    /// Pending login attempt the client can request a new 2FA code for
+    #[serde(
+        rename = "loginAttemptId",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub login_attempt_id: Option<String>,
 }
 
 #[derive(Debug, Error)]
@@ -27,49 +68,205 @@ pub enum AuthApiError {
     #[error("User already exists")]
     UserAlreadyExists,
 
+    #[error("Username is already taken")]
+    UsernameTaken,
+
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
     #[error("Missing token")]
     MissingToken,
 
+    /// Rejected by the password strength policy, with the estimator's advice
+    #[error("Password is too weak")]
+    WeakPassword { suggestions: Vec<String> },
+
     #[error("Authentication failed: {0}")]
     AuthenticationError(String),
 
+    /// A rejected auth token, kept apart from failed credential checks so the
+    /// 401 can carry a `WWW-Authenticate` challenge
+    #[error("Authentication failed: {reason}")]
+    InvalidToken { reason: String, expired: bool },
+
     #[error("Invalid login attempt ID")]
     InvalidLoginAttemptId,
 
     #[error("Invalid two-factor authentication code")]
     InvalidTwoFaCode,
 
+    #[error("Two-factor authentication code has already been used")]
+    TwoFaCodeAlreadyUsed,
+
+    /// The login attempt timed out, the client should start a new login
+    #[error("Two-factor authentication attempt has expired, log in again")]
+    TwoFaAttemptExpired,
+
+    /// The 2FA code of a stored attempt could not be sent, the client can request
+    /// a new one at `/resend-2fa-code`
+    #[error("Two-factor authentication code could not be delivered")]
+    TwoFaCodeDeliveryFailed { attempt_id: String },
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
 }
 
-impl IntoResponse for AuthApiError {
-    fn into_response(self) -> Response {
-        let (status_code, error_message) = match self {
-            AuthApiError::InvalidInput(_) | AuthApiError::MissingToken => {
-                (StatusCode::BAD_REQUEST, self.to_string())
-            }
+impl AuthApiError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AuthApiError::InvalidInput(_)
+            | AuthApiError::MissingToken
+            | AuthApiError::WeakPassword { .. } => StatusCode::BAD_REQUEST,
 
-            AuthApiError::UserAlreadyExists => (StatusCode::CONFLICT, self.to_string()),
+            AuthApiError::UserAlreadyExists
+            | AuthApiError::UsernameTaken
+            | AuthApiError::TwoFaCodeAlreadyUsed => StatusCode::CONFLICT,
 
             AuthApiError::AuthenticationError(_)
+            | AuthApiError::InvalidToken { .. }
             | AuthApiError::UserNotFound
             | AuthApiError::InvalidLoginAttemptId
-            | AuthApiError::InvalidTwoFaCode => (StatusCode::UNAUTHORIZED, self.to_string()),
+            | AuthApiError::InvalidTwoFaCode => StatusCode::UNAUTHORIZED,
+
+            AuthApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+
+            AuthApiError::TwoFaAttemptExpired => StatusCode::GONE,
 
-            AuthApiError::UnexpectedError(_) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, self.to_string())
+            AuthApiError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+
+            AuthApiError::TwoFaCodeDeliveryFailed { .. } => StatusCode::SERVICE_UNAVAILABLE,
+
+            AuthApiError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Stable machine-readable code for the error, independent of the message
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthApiError::UserNotFound => "user_not_found",
+            AuthApiError::UserAlreadyExists => "user_already_exists",
+            AuthApiError::UsernameTaken => "username_taken",
+            AuthApiError::InvalidInput(_) => "invalid_input",
+            AuthApiError::MissingToken => "missing_token",
+            AuthApiError::WeakPassword { .. } => "weak_password",
+            AuthApiError::AuthenticationError(_) | AuthApiError::InvalidToken { .. } => {
+                "authentication_failed"
             }
+            AuthApiError::InvalidLoginAttemptId => "invalid_login_attempt_id",
+            AuthApiError::InvalidTwoFaCode => "invalid_two_fa_code",
+            AuthApiError::TwoFaCodeAlreadyUsed => "two_fa_code_already_used",
+            AuthApiError::TwoFaAttemptExpired => "two_fa_attempt_expired",
+            AuthApiError::TwoFaCodeDeliveryFailed { .. } => "two_fa_code_delivery_failed",
+            AuthApiError::Forbidden(_) => "forbidden",
+            AuthApiError::TooManyRequests(_) => "too_many_requests",
+            AuthApiError::UnexpectedError(_) => "unexpected_error",
+        }
+    }
+
+    /// Advice included in the response body, empty for most errors
+    pub fn suggestions(&self) -> Vec<String> {
+        match self {
+            AuthApiError::WeakPassword { suggestions } => suggestions.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Login attempt included in the response body, only set when its code wasn't delivered
+    pub fn login_attempt_id(&self) -> Option<String> {
+        match self {
+            AuthApiError::TwoFaCodeDeliveryFailed { attempt_id } => Some(attempt_id.clone()),
+            _ => None,
+        }
+    }
+
+    /// Map a failed credential check without revealing whether the email is registered
+    pub fn uniform_credentials_error(error: UserStoreError) -> Self {
+        match error {
+            UserStoreError::UserNotFound | UserStoreError::IncorrectPassword => {
+                AuthApiError::AuthenticationError(UNIFORM_AUTH_ERROR_MESSAGE.to_string())
+            }
+            error => error.into(),
+        }
+    }
+
+    /// RFC 6750 `WWW-Authenticate` challenge for a rejected token, `None` for other errors
+    pub fn www_authenticate(&self, realm: &str) -> Option<String> {
+        let AuthApiError::InvalidToken { expired, .. } = self else {
+            return None;
+        };
+        let description = if *expired {
+            "The access token expired"
+        } else {
+            "The access token is invalid"
         };
 
-        let body = Json(ErrorResponse {
-            error: error_message,
-        });
+        Some(format!(
+            r#"Bearer realm="{realm}", error="invalid_token", error_description="{description}""#
+        ))
+    }
 
-        (status_code, body).into_response()
+    pub fn problem_details(&self) -> ProblemDetails {
+        let status = self.status_code();
+
+        ProblemDetails {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: self.to_string(),
+            code: self.code().to_string(),
+            suggestions: self.suggestions(),
+            login_attempt_id: self.login_attempt_id(),
+        }
+    }
+}
+
+impl IntoResponse for AuthApiError {
+    fn into_response(self) -> Response {
+        let status_code = self.status_code();
+        let config = AuthServiceSetting::load();
+
+        let challenge = config
+            .auth
+            .www_authenticate
+            .enabled
+            .then(|| self.www_authenticate(&config.auth.www_authenticate.realm))
+            .flatten()
+            .and_then(|challenge| HeaderValue::from_str(&challenge).ok());
+
+        let mut response = match config.auth.error_format {
+            ErrorFormat::Simple => {
+                let body = Json(ErrorResponse {
+                    error: self.to_string(),
+                    suggestions: self.suggestions(),
+                    login_attempt_id: self.login_attempt_id(),
+                });
+
+                (status_code, body).into_response()
+            }
+            ErrorFormat::ProblemJson => {
+                let body = Json(self.problem_details());
+
+                (
+                    status_code,
+                    [(header::CONTENT_TYPE, PROBLEM_JSON_CONTENT_TYPE)],
+                    body,
+                )
+                    .into_response()
+            }
+        };
+
+        if let Some(challenge) = challenge {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, challenge);
+        }
+        response
     }
 }
 
@@ -87,6 +284,9 @@ impl From<UserStoreError> for AuthApiError {
             UserStoreError::IncorrectPassword => {
                 AuthApiError::AuthenticationError(error.to_string())
             }
+            UserStoreError::InvalidPasswordHash(e) | UserStoreError::InvalidSearch(e) => {
+                AuthApiError::InvalidInput(e)
+            }
             UserStoreError::UnexpectedError(e) => AuthApiError::UnexpectedError(e),
         }
     }
@@ -97,8 +297,16 @@ impl From<TokenAuthError> for AuthApiError {
         match error {
             TokenAuthError::InvalidToken
             | TokenAuthError::TokenError(_)
-            | TokenAuthError::TokenIsBanned => AuthApiError::AuthenticationError(error.to_string()),
+            | TokenAuthError::TokenIsBanned => AuthApiError::InvalidToken {
+                expired: matches!(
+                    &error,
+                    TokenAuthError::TokenError(e)
+                        if *e.kind() == jsonwebtoken::errors::ErrorKind::ExpiredSignature
+                ),
+                reason: error.to_string(),
+            },
             TokenAuthError::MissingToken => AuthApiError::MissingToken,
+            TokenAuthError::InsufficientScope => AuthApiError::Forbidden(error.to_string()),
             TokenAuthError::UnexpectedError(e) => AuthApiError::UnexpectedError(e.to_string()),
         }
     }
@@ -117,6 +325,8 @@ impl From<TwoFaCodeStoreError> for AuthApiError {
             TwoFaCodeStoreError::InvalidAttemptId | TwoFaCodeStoreError::Invalid2FACode => {
                 AuthApiError::AuthenticationError(error.to_string())
             }
+            TwoFaCodeStoreError::CodeAlreadyUsed => AuthApiError::TwoFaCodeAlreadyUsed,
+            TwoFaCodeStoreError::AttemptExpired => AuthApiError::TwoFaAttemptExpired,
             TwoFaCodeStoreError::UnexpectedError(e) => AuthApiError::UnexpectedError(e),
         }
     }
@@ -133,11 +343,37 @@ impl From<LoginError> for AuthApiError {
         match error {
             LoginError::UserStoreError(e) => e.into(),
             LoginError::TwoFaCodeStoreError(e) => e.into(),
+            LoginError::TwoFaCodeDeliveryFailed { attempt_id, .. } => {
+                AuthApiError::TwoFaCodeDeliveryFailed {
+                    attempt_id: attempt_id.to_string(),
+                }
+            }
+            LoginError::TwoFaTokenError(e) => AuthApiError::UnexpectedError(e.to_string()),
             LoginError::EmailError(e) => AuthApiError::UnexpectedError(e),
         }
     }
 }
 
+impl From<SignupError> for AuthApiError {
+    fn from(error: SignupError) -> Self {
+        match error {
+            SignupError::UserStoreError(e) => e.into(),
+            SignupError::RegistrationHookFailed(e) => AuthApiError::UnexpectedError(e),
+            SignupError::EmailDomainNotAllowed => AuthApiError::InvalidInput(error.to_string()),
+            SignupError::UsernameTaken => AuthApiError::UsernameTaken,
+            SignupError::WeakPassword(e) => e.into(),
+        }
+    }
+}
+
+impl From<WeakPassword> for AuthApiError {
+    fn from(error: WeakPassword) -> Self {
+        AuthApiError::WeakPassword {
+            suggestions: error.suggestions,
+        }
+    }
+}
+
 impl From<LogoutError> for AuthApiError {
     fn from(error: LogoutError) -> Self {
         match error {
@@ -146,6 +382,14 @@ impl From<LogoutError> for AuthApiError {
     }
 }
 
+impl From<ForceLogoutError> for AuthApiError {
+    fn from(error: ForceLogoutError) -> Self {
+        match error {
+            ForceLogoutError::BannedTokenStoreError(e) => e.into(),
+        }
+    }
+}
+
 impl From<Verify2FaError> for AuthApiError {
     fn from(error: Verify2FaError) -> Self {
         match error {
@@ -153,6 +397,13 @@ impl From<Verify2FaError> for AuthApiError {
             Verify2FaError::TwoFaError(e) => e.into(),
             Verify2FaError::InvalidLoginAttemptId => AuthApiError::InvalidLoginAttemptId,
             Verify2FaError::InvalidTwoFaCode => AuthApiError::InvalidTwoFaCode,
+            Verify2FaError::CodeAlreadyUsed => AuthApiError::TwoFaCodeAlreadyUsed,
+            Verify2FaError::AttemptExpired => AuthApiError::TwoFaAttemptExpired,
+            Verify2FaError::BannedTokenStoreError(e) => e.into(),
+            Verify2FaError::TwoFaTokenError(e) => AuthApiError::UnexpectedError(e),
+            Verify2FaError::RateLimitStoreError(e) => AuthApiError::UnexpectedError(e.to_string()),
+            Verify2FaError::TooManyAttempts => AuthApiError::TooManyRequests(error.to_string()),
+            Verify2FaError::BackupCodeStoreError(e) => AuthApiError::UnexpectedError(e.to_string()),
         }
     }
 }
@@ -161,6 +412,18 @@ impl From<ElevateError> for AuthApiError {
     fn from(error: ElevateError) -> Self {
         match error {
             ElevateError::UserStoreError(e) => e.into(),
+            ElevateError::RateLimitStoreError(e) => AuthApiError::UnexpectedError(e.to_string()),
+            ElevateError::TooManyAttempts => AuthApiError::TooManyRequests(error.to_string()),
+        }
+    }
+}
+
+impl From<GetProfileError> for AuthApiError {
+    fn from(error: GetProfileError) -> Self {
+        match error {
+            // The token outlived its user, treat it like an invalid token
+            GetProfileError::UserNotFound => AuthApiError::AuthenticationError(error.to_string()),
+            GetProfileError::UserStoreError(e) => e.into(),
         }
     }
 }
@@ -169,6 +432,25 @@ impl From<ChangePasswordError> for AuthApiError {
     fn from(error: ChangePasswordError) -> Self {
         match error {
             ChangePasswordError::UserStoreError(e) => e.into(),
+            ChangePasswordError::PasswordReused => AuthApiError::InvalidInput(error.to_string()),
+            ChangePasswordError::IncorrectCurrentPassword => {
+                AuthApiError::AuthenticationError(error.to_string())
+            }
+            ChangePasswordError::WeakPassword(e) => e.into(),
+        }
+    }
+}
+
+impl From<VerifyPasswordError> for AuthApiError {
+    fn from(error: VerifyPasswordError) -> Self {
+        match error {
+            VerifyPasswordError::UserStoreError(e) => AuthApiError::uniform_credentials_error(e),
+            VerifyPasswordError::RateLimitStoreError(e) => {
+                AuthApiError::UnexpectedError(e.to_string())
+            }
+            VerifyPasswordError::TooManyAttempts => {
+                AuthApiError::TooManyRequests(error.to_string())
+            }
         }
     }
 }
@@ -180,3 +462,144 @@ impl From<DeleteAccountError> for AuthApiError {
         }
     }
 }
+
+impl From<TwoFaSettingsError> for AuthApiError {
+    fn from(error: TwoFaSettingsError) -> Self {
+        match error {
+            TwoFaSettingsError::UserStoreError(e) => e.into(),
+            TwoFaSettingsError::TwoFaCodeStoreError(e) => e.into(),
+        }
+    }
+}
+
+impl From<VerifyTokenError> for AuthApiError {
+    fn from(error: VerifyTokenError) -> Self {
+        TokenAuthError::from(error).into()
+    }
+}
+
+impl From<ApiKeyError> for AuthApiError {
+    fn from(error: ApiKeyError) -> Self {
+        match error {
+            ApiKeyError::InvalidApiKey => AuthApiError::AuthenticationError(error.to_string()),
+            ApiKeyError::ApiKeyStoreError(ApiKeyStoreError::KeyNotFound) => {
+                AuthApiError::AuthenticationError(error.to_string())
+            }
+            ApiKeyError::ApiKeyStoreError(e) => AuthApiError::UnexpectedError(e.to_string()),
+        }
+    }
+}
+
+impl From<AuthorizationHeaderError> for AuthApiError {
+    fn from(error: AuthorizationHeaderError) -> Self {
+        AuthApiError::AuthenticationError(error.to_string())
+    }
+}
+
+impl From<BackupCodeError> for AuthApiError {
+    fn from(error: BackupCodeError) -> Self {
+        match error {
+            BackupCodeError::BackupCodeStoreError(e) => {
+                AuthApiError::UnexpectedError(e.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempered_core::TwoFaAttemptId;
+
+    use super::*;
+
+    #[test]
+    fn test_problem_details() {
+        let error = AuthApiError::InvalidInput("field 'email' is required".to_string());
+
+        assert_eq!(
+            error.problem_details(),
+            ProblemDetails {
+                problem_type: "about:blank".to_string(),
+                title: "Bad Request".to_string(),
+                status: 400,
+                detail: "Invalid input: field 'email' is required".to_string(),
+                code: "invalid_input".to_string(),
+                suggestions: Vec::new(),
+                login_attempt_id: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_undelivered_code_keeps_attempt_id() {
+        let attempt_id = TwoFaAttemptId::new();
+        let error = AuthApiError::from(LoginError::TwoFaCodeDeliveryFailed {
+            attempt_id: attempt_id.clone(),
+            reason: "email service unavailable".to_string(),
+        });
+        assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let value = serde_json::to_value(error.problem_details()).unwrap();
+        assert_eq!(value["code"], "two_fa_code_delivery_failed");
+        assert_eq!(value["loginAttemptId"], attempt_id.to_string());
+    }
+
+    #[test]
+    fn test_weak_password_suggestions() {
+        let error = AuthApiError::from(WeakPassword {
+            score: 1,
+            suggestions: vec!["Add another word or two".to_string()],
+        });
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+
+        let value = serde_json::to_value(error.problem_details()).unwrap();
+        assert_eq!(value["code"], "weak_password");
+        assert_eq!(value["suggestions"][0], "Add another word or two");
+
+        let value = serde_json::to_value(AuthApiError::MissingToken.problem_details()).unwrap();
+        assert!(value.get("suggestions").is_none());
+    }
+
+    #[test]
+    fn test_problem_details_serializes_type_field() {
+        let value = serde_json::to_value(AuthApiError::MissingToken.problem_details()).unwrap();
+
+        assert_eq!(value["type"], "about:blank");
+        assert_eq!(value["status"], 400);
+        assert_eq!(value["code"], "missing_token");
+    }
+
+    #[test]
+    fn test_uniform_credentials_error() {
+        let not_found = AuthApiError::uniform_credentials_error(UserStoreError::UserNotFound);
+        let wrong_password =
+            AuthApiError::uniform_credentials_error(UserStoreError::IncorrectPassword);
+
+        assert_eq!(not_found.to_string(), wrong_password.to_string());
+        assert_eq!(not_found.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(wrong_password.code(), "authentication_failed");
+    }
+
+    #[test]
+    fn test_www_authenticate() {
+        let expired: AuthApiError =
+            TokenAuthError::TokenError(jsonwebtoken::errors::ErrorKind::ExpiredSignature.into())
+                .into();
+        let banned: AuthApiError = TokenAuthError::TokenIsBanned.into();
+
+        assert_eq!(
+            expired.www_authenticate("tempered").unwrap(),
+            r#"Bearer realm="tempered", error="invalid_token", error_description="The access token expired""#
+        );
+        assert_eq!(
+            banned.www_authenticate("tempered").unwrap(),
+            r#"Bearer realm="tempered", error="invalid_token", error_description="The access token is invalid""#
+        );
+        assert_eq!(banned.status_code(), StatusCode::UNAUTHORIZED);
+        assert!(
+            AuthApiError::AuthenticationError("Incorrect password".to_string())
+                .www_authenticate("tempered")
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use tempered_application::{ForceLogoutUseCase, ValidateApiKeyUseCase};
+use tempered_core::{ApiKeyRecord, ApiKeyStore, BannedTokenStore, Email, UserStore};
+
+use crate::auth::{TracingAuditSink, extract_api_key};
+use crate::config::AuthServiceSetting;
+
+use super::error::AuthApiError;
+
+/// Scope an API key needs to use the admin routes
+pub const ADMIN_SCOPE: &str = "admin";
+
+#[derive(Debug, Deserialize)]
+pub struct ForceLogoutRequest {
+    pub email: Secret<String>,
+}
+
+/// Revoke every token of the user in the request, guarded by an API key with the
+/// [`ADMIN_SCOPE`]. The key's owner is recorded as the actor of the audit event.
+#[tracing::instrument(name = "Force logout", skip_all)]
+pub async fn force_logout<U, B, K>(
+    State((user_store, banned_token_store, api_key_store)): State<(U, B, K)>,
+    headers: HeaderMap,
+    Json(request): Json<ForceLogoutRequest>,
+) -> Result<impl IntoResponse, AuthApiError>
+where
+    U: UserStore + Clone + 'static,
+    B: BannedTokenStore + Clone + 'static,
+    K: ApiKeyStore + Clone + 'static,
+{
+    let record = authorize_admin(&headers, api_key_store).await?;
+
+    let email = Email::try_from(request.email)?;
+    let user = user_store.get_user(&email).await?;
+
+    let config = AuthServiceSetting::load();
+    let max_token_ttl = config
+        .auth
+        .jwt
+        .time_to_live
+        .max(config.auth.elevated_jwt.time_to_live)
+        .max(0) as u64;
+
+    let use_case = ForceLogoutUseCase::new(
+        banned_token_store,
+        TracingAuditSink,
+        Duration::from_secs(max_token_ttl),
+    );
+    use_case
+        .execute(&user, record.email.as_ref().expose_secret().to_owned())
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Validate the request's API key and require the [`ADMIN_SCOPE`]
+pub(crate) async fn authorize_admin<K>(
+    headers: &HeaderMap,
+    api_key_store: K,
+) -> Result<ApiKeyRecord, AuthApiError>
+where
+    K: ApiKeyStore,
+{
+    let api_key = extract_api_key(headers)?.ok_or(AuthApiError::MissingToken)?;
+    let record = ValidateApiKeyUseCase::new(api_key_store)
+        .execute(&api_key)
+        .await?;
+    if !record.has_scope(ADMIN_SCOPE) {
+        return Err(AuthApiError::Forbidden(format!(
+            "API key lacks the {ADMIN_SCOPE} scope"
+        )));
+    }
+    Ok(record)
+}
@@ -0,0 +1,123 @@
+use axum::{
+    Json,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::config::{AuthServiceSetting, JsonCasing};
+
+use super::error::AuthApiError;
+
+/// JSON response body with its keys in the configured `auth.json_casing`
+///
+/// Every auth route builds its body through this, so responses follow one convention.
+#[derive(Debug)]
+pub struct CasedJson<T>(pub T);
+
+impl<T> IntoResponse for CasedJson<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        let casing = AuthServiceSetting::load().auth.json_casing;
+        match serde_json::to_value(&self.0) {
+            Ok(value) => Json(recase_keys(value, casing)).into_response(),
+            Err(e) => AuthApiError::UnexpectedError(e.to_string()).into_response(),
+        }
+    }
+}
+
+/// Convert the keys of every object in `value` to `casing`
+pub fn recase_keys(value: Value, casing: JsonCasing) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (recase_key(&key, casing), recase_keys(value, casing)))
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| recase_keys(value, casing))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+fn recase_key(key: &str, casing: JsonCasing) -> String {
+    match casing {
+        JsonCasing::CamelCase => to_camel_case(key),
+        JsonCasing::SnakeCase => to_snake_case(key),
+    }
+}
+
+fn to_camel_case(key: &str) -> String {
+    let mut camel = String::with_capacity(key.len());
+    let mut upper_next = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper_next = !camel.is_empty();
+        } else if upper_next {
+            camel.push(c.to_ascii_uppercase());
+            upper_next = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+// A new word starts at an uppercase letter or digit following a lowercase letter,
+// so acronyms stay together: `requires2FA` becomes `requires_2fa`
+fn to_snake_case(key: &str) -> String {
+    let mut snake = String::with_capacity(key.len() + 4);
+    let mut previous: Option<char> = None;
+    for c in key.chars() {
+        let starts_word = (c.is_ascii_uppercase() || c.is_ascii_digit())
+            && previous.is_some_and(|p| p.is_ascii_lowercase());
+        if starts_word {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+        previous = Some(c);
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_snake_case() {
+        let value = json!({
+            "loginAttemptId": "id",
+            "requires2FA": true,
+            "stores": [{ "latencyMs": 1.0 }],
+            "message": "2FA required",
+        });
+        assert_eq!(
+            recase_keys(value, JsonCasing::SnakeCase),
+            json!({
+                "login_attempt_id": "id",
+                "requires_2fa": true,
+                "stores": [{ "latency_ms": 1.0 }],
+                "message": "2FA required",
+            })
+        );
+    }
+
+    #[test]
+    fn test_camel_case_keeps_camel_keys() {
+        let value = json!({ "loginAttemptId": "id", "requires2FA": true, "banned_tokens": 1 });
+        assert_eq!(
+            recase_keys(value, JsonCasing::CamelCase),
+            json!({ "loginAttemptId": "id", "requires2FA": true, "bannedTokens": 1 })
+        );
+    }
+}
@@ -1,17 +1,30 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::{Extension, extract::State, http::StatusCode, response::IntoResponse};
 use axum_extra::extract::CookieJar;
 use secrecy::Secret;
 use serde::{Deserialize, Serialize};
-use tempered_application::{LoginResponse, LoginUseCase};
-use tempered_core::{Email, EmailClient, Password, TwoFaCodeStore, UserStore};
+use tempered_application::{LoginError, LoginResponse, LoginUseCase};
+use tempered_core::{
+    AttemptIdGenerator, Email, EmailClient, Identifier, Password, TwoFaCodeStore, UserStore,
+};
 
-use crate::auth::generate_auth_cookie;
-use crate::config::AuthServiceSetting;
+use crate::auth::{
+    TokenMinter, create_csrf_cookie, create_two_fa_token_signer, generate_auth_cookie,
+    generate_csrf_token,
+};
+use crate::config::{AuthServiceSetting, Config};
+use crate::email::EmailTemplate;
 
+use super::credentials::Credentials;
 use super::error::AuthApiError;
+use super::json::CasedJson;
 
 #[derive(Debug, Deserialize)]
 pub struct LoginRequest {
+    /// Email address, or a username when `auth.login.allow_username` is enabled
+    #[serde(alias = "identifier")]
     pub email: Secret<String>,
     pub password: Secret<String>,
 }
@@ -20,56 +33,195 @@ pub struct LoginRequest {
 #[serde(untagged)]
 pub enum LoginHttpResponse {
     RegularAuth,
+    RegularAuthWithCsrf(CsrfTokenResponse),
     TwoFactorAuth(TwoFactorAuthResponse),
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CsrfTokenResponse {
+    #[serde(rename = "csrfToken")]
+    pub csrf_token: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TwoFactorAuthResponse {
     pub message: String,
     #[serde(rename = "loginAttemptId")]
     pub attempt_id: String,
+    /// How long the 2FA code stays valid, from `auth.two_fa.time_to_live_in_seconds`
+    #[serde(rename = "expiresInSeconds")]
+    pub expires_in_seconds: i64,
 }
 
-#[tracing::instrument(name = "Login", skip_all)]
+/// Attempt ID generator injected by `AuthService::with_attempt_id_generator`
+pub type SharedAttemptIdGenerator = Arc<dyn AttemptIdGenerator>;
+
+type LoginResult = Result<(CookieJar, (StatusCode, CasedJson<LoginHttpResponse>)), AuthApiError>;
+
+/// Records `outcome` (`success`, `requires_2fa` or `failed`) and `elapsed_ms` on the span
+#[tracing::instrument(
+    name = "Login",
+    skip_all,
+    fields(outcome = tracing::field::Empty, elapsed_ms = tracing::field::Empty)
+)]
 pub async fn login<U, T, E>(
-    State((user_store, two_fa_store, email_client)): State<(U, T, E)>,
+    state: State<(U, T, E)>,
+    attempt_id_generator: Option<Extension<SharedAttemptIdGenerator>>,
+    token_minter: Option<Extension<TokenMinter>>,
     jar: CookieJar,
-    Json(request): Json<LoginRequest>,
+    credentials: Credentials<LoginRequest>,
 ) -> Result<impl IntoResponse, AuthApiError>
 where
     U: UserStore + Clone + 'static,
     T: TwoFaCodeStore + Clone + 'static,
     E: EmailClient + Clone + 'static,
 {
-    let use_case = LoginUseCase::new(user_store, two_fa_store, email_client);
+    let started = Instant::now();
+    let attempt_id_generator = attempt_id_generator.map(|Extension(g)| g);
+    let token_minter = token_minter.map(|Extension(m)| m).unwrap_or_default();
+    let result = handle_login(state, attempt_id_generator, &token_minter, jar, credentials).await;
+
+    // An undeliverable code still triggered 2FA
+    let outcome = match &result {
+        Ok((_, (_, CasedJson(LoginHttpResponse::TwoFactorAuth(_)))))
+        | Err(AuthApiError::TwoFaCodeDeliveryFailed { .. }) => "requires_2fa",
+        Ok(_) => "success",
+        Err(_) => "failed",
+    };
+    let span = tracing::Span::current();
+    span.record("outcome", outcome);
+    span.record("elapsed_ms", started.elapsed().as_millis() as u64);
 
-    let email = Email::try_from(request.email)?;
+    result
+}
+
+async fn handle_login<U, T, E>(
+    State((user_store, two_fa_store, email_client)): State<(U, T, E)>,
+    attempt_id_generator: Option<SharedAttemptIdGenerator>,
+    token_minter: &TokenMinter,
+    jar: CookieJar,
+    Credentials(request): Credentials<LoginRequest>,
+) -> LoginResult
+where
+    U: UserStore + Clone + 'static,
+    T: TwoFaCodeStore + Clone + 'static,
+    E: EmailClient + Clone + 'static,
+{
+    let config = AuthServiceSetting::load();
+    let identifier = if config.auth.login.allow_username {
+        Identifier::parse(request.email)?
+    } else {
+        Identifier::Email(Email::try_from(request.email)?)
+    };
     let password = Password::try_from(request.password)?;
 
-    let login_response = use_case.execute(email, password).await?;
+    let result = start_login(
+        (user_store.clone(), two_fa_store, email_client),
+        attempt_id_generator,
+        &config,
+        identifier,
+        password,
+    )
+    .await;
 
-    match login_response {
-        LoginResponse::Requires2Fa { attempt_id, .. } => {
-            let two_factor_auth_response = TwoFactorAuthResponse {
-                message: "2FA required".to_string(),
-                attempt_id: attempt_id.to_string(),
-            };
+    let login_response = match result {
+        Ok(response) => response,
+        Err(LoginError::UserStoreError(e)) if config.auth.login.uniform_auth_errors => {
+            if config.auth.login.log_failure_reason {
+                tracing::info!(reason = %e, "Login failed");
+            }
+            return Err(AuthApiError::uniform_credentials_error(e));
+        }
+        Err(e) => return Err(e.into()),
+    };
 
-            Ok((
-                jar,
-                (
-                    StatusCode::PARTIAL_CONTENT,
-                    Json(LoginHttpResponse::TwoFactorAuth(two_factor_auth_response)),
-                ),
-            ))
+    match login_response {
+        LoginResponse::Requires2Fa { attempt_id, .. } => Ok((
+            jar,
+            two_fa_required_response(attempt_id.to_string(), &config),
+        )),
+        // In stateless mode the signed token takes the place of the attempt ID
+        LoginResponse::Requires2FaToken { token, .. } => {
+            Ok((jar, two_fa_required_response(token, &config)))
         }
         LoginResponse::Success(email) => {
-            let config = AuthServiceSetting::load();
-            let auth_cookie = generate_auth_cookie(&email, &config)?;
+            let user = user_store.get_user(&email).await?;
+            let auth_cookie =
+                generate_auth_cookie(token_minter, user.id(), &email, &config).await?;
 
             let jar = jar.add(auth_cookie);
 
-            Ok((jar, (StatusCode::OK, Json(LoginHttpResponse::RegularAuth))))
+            let (jar, body) = match add_csrf_token(jar, &config) {
+                (jar, Some(response)) => (jar, LoginHttpResponse::RegularAuthWithCsrf(response)),
+                (jar, None) => (jar, LoginHttpResponse::RegularAuth),
+            };
+
+            Ok((jar, (StatusCode::OK, CasedJson(body))))
         }
     }
 }
+
+/// Check the credentials and, for users who require 2FA, send a code and start a
+/// login attempt, statelessly when `auth.two_fa.stateless` is set
+pub(super) async fn start_login<U, T, E>(
+    (user_store, two_fa_store, email_client): (U, T, E),
+    attempt_id_generator: Option<SharedAttemptIdGenerator>,
+    config: &Config,
+    identifier: Identifier,
+    password: Password,
+) -> Result<LoginResponse, LoginError>
+where
+    U: UserStore + Clone + 'static,
+    T: TwoFaCodeStore + Clone + 'static,
+    E: EmailClient + Clone + 'static,
+{
+    let mut use_case = LoginUseCase::new(user_store, two_fa_store, email_client)
+        .with_two_fa_subject(config.email_client.templates.subject(EmailTemplate::TwoFa))
+        .with_failed_auth_delay(config.auth.login.failed_auth_delay());
+    if let Some(generator) = attempt_id_generator {
+        use_case = use_case.with_attempt_id_generator(generator);
+    }
+
+    if config.auth.two_fa.stateless {
+        let signer = create_two_fa_token_signer(config);
+        use_case
+            .execute_stateless_with_identifier(identifier, password, &signer)
+            .await
+    } else {
+        use_case.execute_with_identifier(identifier, password).await
+    }
+}
+
+/// Response asking the client to verify the 2FA code sent for `attempt_id`
+pub(super) fn two_fa_required_response(
+    attempt_id: String,
+    config: &Config,
+) -> (StatusCode, CasedJson<LoginHttpResponse>) {
+    let two_factor_auth_response = TwoFactorAuthResponse {
+        message: "2FA required".to_string(),
+        attempt_id,
+        expires_in_seconds: config.auth.two_fa.time_to_live_in_seconds,
+    };
+
+    (
+        config.auth.two_fa.required_status_code(),
+        CasedJson(LoginHttpResponse::TwoFactorAuth(two_factor_auth_response)),
+    )
+}
+
+/// Mint a CSRF token alongside a freshly issued auth cookie, if enabled in the config.
+/// The token is set as a cookie and returned for the response body, the auth token
+/// itself is never reflected in the body.
+pub(super) fn add_csrf_token(
+    jar: CookieJar,
+    config: &Config,
+) -> (CookieJar, Option<CsrfTokenResponse>) {
+    if !config.auth.csrf.enabled {
+        return (jar, None);
+    }
+
+    let csrf_token = generate_csrf_token();
+    let cookie = create_csrf_cookie(csrf_token.clone(), &config.auth.csrf.cookie_name).into_owned();
+
+    (jar.add(cookie), Some(CsrfTokenResponse { csrf_token }))
+}
@@ -1,9 +1,9 @@
 use axum::{extract::State, http::StatusCode, response::IntoResponse};
-use axum_extra::extract::{CookieJar, cookie::Cookie};
+use axum_extra::extract::CookieJar;
 use tempered_application::LogoutUseCase;
 use tempered_core::BannedTokenStore;
 
-use crate::auth::{extract_token, validate_auth_token};
+use crate::auth::{TokenAuthError, create_removal_cookie, extract_token, validate_auth_token};
 use crate::config::AuthServiceSetting;
 
 use super::error::AuthApiError;
@@ -19,9 +19,24 @@ where
     let config = AuthServiceSetting::load();
     let jwt_cookie_name = config.auth.jwt.cookie_name.clone();
     let jwt_elevated_cookie_name = config.auth.elevated_jwt.cookie_name.clone();
+    let jwt_cookie_domain = config.auth.jwt.cookie_domain.as_deref();
+    let jwt_elevated_cookie_domain = config.auth.elevated_jwt.cookie_domain.as_deref();
 
-    // Extract the main token (must be present)
-    let token = extract_token(&jar, &jwt_cookie_name)?.to_owned();
+    // Extract the main token, nothing to log out without one
+    let token = match extract_token(&jar, &jwt_cookie_name) {
+        Ok(token) => token.to_owned(),
+        Err(TokenAuthError::MissingToken) if config.auth.logout.allow_missing_token => {
+            let updated_jar = match jar.get(&jwt_elevated_cookie_name) {
+                Some(_) => jar.remove(
+                    create_removal_cookie(&jwt_elevated_cookie_name, jwt_elevated_cookie_domain)
+                        .into_owned(),
+                ),
+                None => jar,
+            };
+            return Ok((updated_jar, StatusCode::OK));
+        }
+        Err(e) => return Err(e.into()),
+    };
 
     // Validate the token first
     validate_auth_token(&token, &banned_token_store).await?;
@@ -35,11 +50,16 @@ where
     let use_case = LogoutUseCase::new(banned_token_store);
     use_case.execute(token, elevated_token).await?;
 
-    // Remove both cookies - create removal cookies inline
+    // Remove both cookies with the attributes they were set with, which
+    // browsers require to clear `__Host-` and `__Secure-` prefixed cookies
     let has_elevated = jar.get(&jwt_elevated_cookie_name).is_some();
-    let mut updated_jar = jar.remove(Cookie::from(jwt_cookie_name.clone()));
+    let mut updated_jar =
+        jar.remove(create_removal_cookie(&jwt_cookie_name, jwt_cookie_domain).into_owned());
     if has_elevated {
-        updated_jar = updated_jar.remove(Cookie::from(jwt_elevated_cookie_name));
+        updated_jar = updated_jar.remove(
+            create_removal_cookie(&jwt_elevated_cookie_name, jwt_elevated_cookie_domain)
+                .into_owned(),
+        );
     }
 
     Ok((updated_jar, StatusCode::OK))
@@ -0,0 +1,46 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use tempered_application::GetProfileUseCase;
+use tempered_core::{BannedTokenStore, UserStore};
+
+use crate::auth::{extract_token, resolve_token_user, validate_auth_token};
+use crate::config::AuthServiceSetting;
+
+use super::error::AuthApiError;
+use super::json::CasedJson;
+
+/// Public profile of the authenticated user, never includes credentials
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserProfile {
+    pub email: String,
+    #[serde(rename = "requires2FA")]
+    pub requires_2fa: bool,
+}
+
+#[tracing::instrument(name = "Me", skip_all)]
+pub async fn me<U, B>(
+    State((user_store, banned_token_store)): State<(U, B)>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AuthApiError>
+where
+    U: UserStore + Clone + 'static,
+    B: BannedTokenStore + Clone + 'static,
+{
+    let config = AuthServiceSetting::load();
+
+    let token = extract_token(&jar, &config.auth.jwt.cookie_name)?;
+    let claims = validate_auth_token(token, &banned_token_store).await?;
+    let user = resolve_token_user(&claims, &user_store).await?;
+
+    let use_case = GetProfileUseCase::new(user_store);
+    let profile = use_case.execute(user.email().clone()).await?;
+
+    let response = UserProfile {
+        email: profile.email.as_ref().expose_secret().to_owned(),
+        requires_2fa: profile.requires_2fa,
+    };
+
+    Ok((StatusCode::OK, CasedJson(response)))
+}
@@ -0,0 +1,38 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use tempered_core::{ApiKeyStore, BannedTokenStore};
+
+use super::error::AuthApiError;
+use super::force_logout::authorize_admin;
+use super::json::CasedJson;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetricsResponse {
+    /// Number of tokens currently on the ban list
+    #[serde(rename = "bannedTokens")]
+    pub banned_tokens: usize,
+}
+
+/// Report operational counters, e.g. to alert on an unexpectedly growing ban list,
+/// guarded by an API key with the [`super::ADMIN_SCOPE`]
+#[tracing::instrument(name = "Metrics", skip_all)]
+pub async fn metrics<B, K>(
+    State((banned_token_store, api_key_store)): State<(B, K)>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AuthApiError>
+where
+    B: BannedTokenStore + Clone + 'static,
+    K: ApiKeyStore + Clone + 'static,
+{
+    authorize_admin(&headers, api_key_store).await?;
+
+    let response = MetricsResponse {
+        banned_tokens: banned_token_store.banned_count().await?,
+    };
+
+    Ok((StatusCode::OK, CasedJson(response)))
+}
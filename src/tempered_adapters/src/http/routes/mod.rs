@@ -1,21 +1,45 @@
+pub mod backup_codes;
 pub mod change_password;
+pub mod credentials;
 pub mod delete_account;
 pub mod elevate;
 pub mod error;
+pub mod force_logout;
+pub mod json;
 pub mod login;
 pub mod logout;
+pub mod me;
+pub mod metrics;
+pub mod ready;
+pub mod resend_2fa_code;
+pub mod session;
 pub mod signup;
+pub mod two_fa_settings;
 pub mod verify_2fa;
 pub mod verify_elevated_token;
+pub mod verify_password;
 pub mod verify_token;
 
+pub use backup_codes::{BackupCodes, BackupCodesResponse, regenerate_backup_codes};
 pub use change_password::{ChangePasswordRequest, change_password};
+pub use credentials::{Credentials, CredentialsRejection};
 pub use delete_account::delete_account;
 pub use elevate::{ElevateRequest, elevate};
 pub use error::AuthApiError;
-pub use login::{LoginHttpResponse, LoginRequest, TwoFactorAuthResponse, login};
+pub use force_logout::{ADMIN_SCOPE, ForceLogoutRequest, force_logout};
+pub use json::{CasedJson, recase_keys};
+pub use login::{
+    LoginHttpResponse, LoginRequest, SharedAttemptIdGenerator, TwoFactorAuthResponse, login,
+};
 pub use logout::logout;
-pub use signup::{SignupRequest, signup};
+pub use me::{UserProfile, me};
+pub use metrics::{MetricsResponse, metrics};
+pub use ready::{ReadinessProbes, ReadinessResponse, StoreStatus, ready};
+pub use resend_2fa_code::{Resend2FaCodeRequest, resend_2fa_code};
+pub use session::{SessionResponse, session};
+pub use signup::{SignupAutoLogin, SignupRequest, SignupResponse, create_signup_response, signup};
+pub use two_fa_settings::{TwoFaSettingsRequest, TwoFaSettingsResponse, two_fa_settings};
 pub use verify_2fa::{Verify2FARequest, verify_2fa};
 pub use verify_elevated_token::{VerifyElevatedTokenRequest, verify_elevated_token};
+pub use verify_password::{VerifyPasswordRequest, verify_password};
 pub use verify_token::{VerifyTokenRequest, verify_token};
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+use tempered_core::ProbeableStore;
+
+use super::json::CasedJson;
+
+/// Named stores probed by the readiness route
+pub type ReadinessProbes = Arc<Vec<(String, Arc<dyn ProbeableStore>)>>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StoreStatus {
+    pub name: String,
+    pub healthy: bool,
+    #[serde(rename = "latencyMs")]
+    pub latency_ms: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    /// Whether every store is healthy
+    pub ready: bool,
+    pub stores: Vec<StoreStatus>,
+}
+
+/// Probe every store and report each one's health and latency, so a slow
+/// dependency can be told apart from one that is down
+///
+/// Answers 503 Service Unavailable if any store is unhealthy.
+#[tracing::instrument(name = "Ready", skip_all)]
+pub async fn ready(State(probes): State<ReadinessProbes>) -> impl IntoResponse {
+    let mut stores = Vec::with_capacity(probes.len());
+    for (name, store) in probes.iter() {
+        let probe = store.probe().await;
+        stores.push(StoreStatus {
+            name: name.clone(),
+            healthy: probe.healthy,
+            latency_ms: probe.latency.as_secs_f64() * 1000.0,
+        });
+    }
+
+    let ready = stores.iter().all(|store| store.healthy);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, CasedJson(ReadinessResponse { ready, stores }))
+}
@@ -0,0 +1,65 @@
+use axum::{Extension, Json, extract::State, response::IntoResponse};
+use secrecy::Secret;
+use serde::Deserialize;
+use tempered_application::LoginUseCase;
+use tempered_core::{
+    Email, EmailClient, RateLimitStore, TwoFaAttemptId, TwoFaCodeStore, UserStore,
+};
+
+use crate::config::AuthServiceSetting;
+use crate::email::EmailTemplate;
+
+use super::error::AuthApiError;
+use super::login::{SharedAttemptIdGenerator, two_fa_required_response};
+
+#[derive(Debug, Deserialize)]
+pub struct Resend2FaCodeRequest {
+    pub email: Secret<String>,
+    #[serde(rename = "loginAttemptId", alias = "login_attempt_id")]
+    pub login_attempt_id: String,
+}
+
+/// Send a new 2FA code for a pending login attempt, e.g. after login answered 503
+/// because the first code could not be delivered
+///
+/// The attempt is replaced by a new one, whose ID is returned like a login that
+/// requires 2FA. Only available when 2FA is not stateless. Resends count towards
+/// the 2FA verification rate limit.
+#[tracing::instrument(name = "Resend 2FA code", skip_all)]
+pub async fn resend_2fa_code<U, T, E, R>(
+    State((user_store, two_fa_code_store, email_client, rate_limit_store)): State<(U, T, E, R)>,
+    attempt_id_generator: Option<Extension<SharedAttemptIdGenerator>>,
+    Json(request): Json<Resend2FaCodeRequest>,
+) -> Result<impl IntoResponse, AuthApiError>
+where
+    U: UserStore + Clone + 'static,
+    T: TwoFaCodeStore + Clone + 'static,
+    E: EmailClient + Clone + 'static,
+    R: RateLimitStore + Clone + 'static,
+{
+    let config = AuthServiceSetting::load();
+    if config.auth.two_fa.stateless {
+        return Err(AuthApiError::InvalidInput(
+            "Resending codes is not supported with stateless 2FA".to_string(),
+        ));
+    }
+
+    let email = Email::try_from(request.email)?;
+    let login_attempt_id = TwoFaAttemptId::parse(&request.login_attempt_id)?;
+
+    if let Some(rate_limit) = config.auth.two_fa.verify_rate_limit() {
+        rate_limit.check(&rate_limit_store, &email, None).await?;
+    }
+
+    let mut use_case = LoginUseCase::new(user_store, two_fa_code_store, email_client)
+        .with_two_fa_subject(config.email_client.templates.subject(EmailTemplate::TwoFa));
+    if let Some(Extension(generator)) = attempt_id_generator {
+        use_case = use_case.with_attempt_id_generator(generator);
+    }
+
+    let attempt_id = use_case
+        .resend_two_fa_code(email, &login_attempt_id)
+        .await?;
+
+    Ok(two_fa_required_response(attempt_id.to_string(), &config))
+}
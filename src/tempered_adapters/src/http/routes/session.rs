@@ -0,0 +1,71 @@
+use axum::{Extension, extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use tempered_core::{BannedTokenStore, UserStore};
+
+use crate::auth::{
+    TokenMinter, extract_token, generate_auth_cookie, resolve_token_user, validate_auth_token,
+};
+use crate::config::AuthServiceSetting;
+
+use super::error::AuthApiError;
+use super::json::CasedJson;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionResponse {
+    pub email: String,
+    #[serde(rename = "expiresInSeconds")]
+    pub expires_in_seconds: i64,
+    /// Whether a fresh auth cookie was issued with this response
+    pub renewed: bool,
+}
+
+/// Validate the current auth cookie and, if it is within the renewal window
+/// of expiring, replace it with a fresh one (sliding session)
+#[tracing::instrument(name = "Session", skip_all)]
+pub async fn session<U, B>(
+    State((user_store, banned_token_store)): State<(U, B)>,
+    token_minter: Option<Extension<TokenMinter>>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AuthApiError>
+where
+    U: UserStore + Clone + 'static,
+    B: BannedTokenStore + Clone + 'static,
+{
+    let config = AuthServiceSetting::load();
+
+    let token = extract_token(&jar, &config.auth.jwt.cookie_name)?.to_owned();
+    let claims = validate_auth_token(&token, &banned_token_store).await?;
+    let user = resolve_token_user(&claims, &user_store).await?;
+    let email = user.email().as_ref().expose_secret().to_owned();
+
+    let remaining = claims.remaining_ttl(Utc::now()).as_secs() as i64;
+
+    if remaining > config.auth.jwt.renewal_window {
+        let response = SessionResponse {
+            email,
+            expires_in_seconds: remaining,
+            renewed: false,
+        };
+        return Ok((jar, (StatusCode::OK, CasedJson(response))));
+    }
+
+    // Renewed tokens carry the user id even when the replaced one predates it
+    let token_minter = token_minter.map(|Extension(m)| m).unwrap_or_default();
+    let auth_cookie = generate_auth_cookie(&token_minter, user.id(), user.email(), &config).await?;
+
+    // The replaced token must not stay usable alongside the new one
+    banned_token_store
+        .ban_token_until(claims.ban_key(&token), claims.ban_expires_at())
+        .await?;
+
+    let response = SessionResponse {
+        email,
+        expires_in_seconds: config.auth.jwt.time_to_live,
+        renewed: true,
+    };
+
+    Ok((jar.add(auth_cookie), (StatusCode::OK, CasedJson(response))))
+}
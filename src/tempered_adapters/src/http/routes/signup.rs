@@ -1,38 +1,183 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
-use secrecy::Secret;
-use serde::Deserialize;
-use tempered_application::SignupUseCase;
-use tempered_core::{Email, Password, UserStore};
+use axum::{
+    Extension,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::CookieJar;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use tempered_application::{LoginResponse, PasswordStrengthPolicy, SignupOutcome, SignupUseCase};
+use tempered_core::{
+    Email, EmailClient, Identifier, Password, RegistrationHook, TwoFaCodeStore, UserStore, Username,
+};
 
+use crate::auth::{TokenMinter, generate_auth_cookie};
+use crate::config::AuthServiceSetting;
+
+use super::credentials::Credentials;
 use super::error::AuthApiError;
+use super::json::CasedJson;
+use super::login::{
+    SharedAttemptIdGenerator, add_csrf_token, start_login, two_fa_required_response,
+};
 
 #[derive(Deserialize)]
 pub struct SignupRequest {
     pub email: Secret<String>,
     pub password: Secret<String>,
+    #[serde(rename = "requires2FA", alias = "requires_2fa")]
+    pub requires_2fa: bool,
+    /// Username to log in with, only accepted when `auth.login.allow_username` is enabled
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+/// Body of a successful signup response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignupResponse {
+    pub message: String,
+    pub email: String,
     #[serde(rename = "requires2FA")]
     pub requires_2fa: bool,
+    /// Whether a verification email was sent, so the client knows to prompt for it
+    #[serde(rename = "emailVerificationSent")]
+    pub email_verification_sent: bool,
+    /// Only set when the user was logged in by the signup and CSRF protection is enabled
+    #[serde(rename = "csrfToken", default, skip_serializing_if = "Option::is_none")]
+    pub csrf_token: Option<String>,
 }
 
+/// Whether signup logs the new user in, set by `AuthService::with_signup_auto_login`
+/// to override `auth.signup.auto_login`
+#[derive(Debug, Clone, Copy)]
+pub struct SignupAutoLogin(pub bool);
+
+/// With `auth.signup.auto_login` the new user is logged in right away, or for users
+/// who require 2FA a code is sent and the 2FA response of `/login` returned
 #[tracing::instrument(name = "Signup", skip_all)]
-pub async fn signup<U>(
-    State(user_store): State<U>,
-    Json(request): Json<SignupRequest>,
-) -> Result<impl IntoResponse, AuthApiError>
+pub async fn signup<U, H, T, E>(
+    State((user_store, registration_hook, two_fa_store, email_client)): State<(U, H, T, E)>,
+    jar: CookieJar,
+    password_strength_policy: Option<Extension<PasswordStrengthPolicy>>,
+    token_minter: Option<Extension<TokenMinter>>,
+    attempt_id_generator: Option<Extension<SharedAttemptIdGenerator>>,
+    auto_login: Option<Extension<SignupAutoLogin>>,
+    Credentials(request): Credentials<SignupRequest>,
+) -> Result<Response, AuthApiError>
 where
     U: UserStore + Clone + 'static,
+    H: RegistrationHook + Clone + 'static,
+    T: TwoFaCodeStore + Clone + 'static,
+    E: EmailClient + Clone + 'static,
 {
-    let use_case = SignupUseCase::new(user_store);
+    let config = AuthServiceSetting::load();
+    let use_case = SignupUseCase::with_registration_hook(
+        user_store.clone(),
+        registration_hook,
+        config.auth.signup.rollback_on_hook_failure,
+    )
+    .with_email_domain_policy(config.auth.signup.email_domain_policy.clone())
+    .with_password_strength_policy(password_strength_policy.map(|Extension(policy)| policy));
 
     let email = Email::try_from(request.email)?;
     let password = Password::try_from(request.password)?;
+    let username = match request.username {
+        Some(_) if !config.auth.login.allow_username => {
+            return Err(AuthApiError::InvalidInput(
+                "Usernames are not enabled".to_string(),
+            ));
+        }
+        Some(username) => Some(Username::try_from(username)?),
+        None => None,
+    };
 
-    use_case
-        .execute(email, password, request.requires_2fa)
+    let outcome = use_case
+        .execute_with_username(email, password.clone(), request.requires_2fa, username)
         .await?;
 
-    Ok((
-        StatusCode::CREATED,
-        String::from("User created successfully!"),
-    ))
+    let auto_login = auto_login.map_or(config.auth.signup.auto_login, |Extension(a)| a.0);
+    let SignupOutcome::Created(email) = &outcome;
+    let auto_login = auto_login.then(|| email.clone());
+
+    let (status, CasedJson(mut response)) = create_signup_response(outcome, request.requires_2fa);
+    let Some(email) = auto_login else {
+        return Ok((jar, (status, CasedJson(response))).into_response());
+    };
+
+    if request.requires_2fa {
+        let login_response = start_login(
+            (user_store, two_fa_store, email_client),
+            attempt_id_generator.map(|Extension(g)| g),
+            &config,
+            Identifier::Email(email),
+            password,
+        )
+        .await?;
+        // In stateless mode the signed token takes the place of the attempt ID
+        let attempt_id = match login_response {
+            LoginResponse::Requires2Fa { attempt_id, .. } => attempt_id.to_string(),
+            LoginResponse::Requires2FaToken { token, .. } => token,
+            LoginResponse::Success(_) => {
+                return Ok((jar, (status, CasedJson(response))).into_response());
+            }
+        };
+        return Ok((jar, two_fa_required_response(attempt_id, &config)).into_response());
+    }
+
+    let user = user_store.get_user(&email).await?;
+    let token_minter = token_minter.map(|Extension(m)| m).unwrap_or_default();
+    let jar = jar.add(generate_auth_cookie(&token_minter, user.id(), &email, &config).await?);
+    let (jar, csrf) = add_csrf_token(jar, &config);
+    response.csrf_token = csrf.map(|csrf| csrf.csrf_token);
+
+    Ok((jar, (status, CasedJson(response))).into_response())
+}
+
+/// Render a signup outcome as 201 Created
+pub fn create_signup_response(
+    outcome: SignupOutcome,
+    requires_2fa: bool,
+) -> (StatusCode, CasedJson<SignupResponse>) {
+    let SignupOutcome::Created(email) = outcome;
+
+    let response = SignupResponse {
+        message: "User created successfully!".to_owned(),
+        email: email.as_ref().expose_secret().to_owned(),
+        requires_2fa,
+        email_verification_sent: false,
+        csrf_token: None,
+    };
+    (StatusCode::CREATED, CasedJson(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_signup_response() {
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+
+        let (status, CasedJson(response)) =
+            create_signup_response(SignupOutcome::Created(email), true);
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(response.email, "test@example.com");
+        assert!(response.requires_2fa);
+        assert!(!response.email_verification_sent);
+    }
+
+    #[test]
+    fn test_csrf_token_only_serialized_when_logged_in() {
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let (_, CasedJson(mut response)) =
+            create_signup_response(SignupOutcome::Created(email), false);
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json.get("csrfToken").is_none());
+
+        response.csrf_token = Some("token".to_owned());
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["csrfToken"], "token");
+    }
 }
@@ -0,0 +1,60 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+use tempered_application::UpdateTwoFaSettingsUseCase;
+use tempered_core::{BannedTokenStore, TwoFaCodeStore, UserStore};
+
+use crate::auth::{
+    ElevationScope, extract_token, resolve_token_user, validate_elevated_auth_token_for,
+};
+
+use super::error::AuthApiError;
+use super::json::CasedJson;
+
+#[derive(Deserialize)]
+pub struct TwoFaSettingsRequest {
+    #[serde(rename = "requires2FA", alias = "requires_2fa")]
+    pub requires_2fa: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TwoFaSettingsResponse {
+    #[serde(rename = "requires2FA")]
+    pub requires_2fa: bool,
+}
+
+#[tracing::instrument(name = "2FA Settings", skip_all)]
+pub async fn two_fa_settings<U, T, B>(
+    State((user_store, two_fa_code_store, banned_token_store)): State<(U, T, B)>,
+    jar: CookieJar,
+    Json(request): Json<TwoFaSettingsRequest>,
+) -> Result<impl IntoResponse, AuthApiError>
+where
+    U: UserStore + Clone + 'static,
+    T: TwoFaCodeStore + Clone + 'static,
+    B: BannedTokenStore + Clone + 'static,
+{
+    let config = crate::config::AuthServiceSetting::load();
+    let jwt_elevated_cookie_name = &config.auth.elevated_jwt.cookie_name;
+
+    // Extract and validate elevated token
+    let token = extract_token(&jar, jwt_elevated_cookie_name)?;
+    let claims =
+        validate_elevated_auth_token_for(token, &banned_token_store, ElevationScope::TwoFaSettings)
+            .await?;
+
+    let email = resolve_token_user(&claims, &user_store)
+        .await?
+        .email()
+        .clone();
+
+    // Use the 2FA settings use case
+    let use_case = UpdateTwoFaSettingsUseCase::new(user_store, two_fa_code_store);
+    use_case.execute(email, request.requires_2fa).await?;
+
+    let response = TwoFaSettingsResponse {
+        requires_2fa: request.requires_2fa,
+    };
+
+    Ok((jar, (StatusCode::OK, CasedJson(response))))
+}
@@ -1,49 +1,136 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use std::net::SocketAddr;
+
+use axum::{
+    Extension, Json,
+    extract::{ConnectInfo, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
 use axum_extra::extract::CookieJar;
-use secrecy::Secret;
+use secrecy::{ExposeSecret, Secret};
 use serde::Deserialize;
-use tempered_application::Verify2FaUseCase;
-use tempered_core::{Email, TwoFaAttemptId, TwoFaCode, TwoFaCodeStore};
+use tempered_application::{StatelessVerify2FaUseCase, Verify2FaUseCase};
+use tempered_core::{
+    BackupCode, BannedTokenStore, Email, RateLimitStore, TwoFaAttemptId, TwoFaCode, TwoFaCodeStore,
+    UserStore,
+};
 
-use crate::auth::generate_auth_cookie;
+use crate::auth::{TokenMinter, create_two_fa_token_signer, generate_auth_cookie};
 use crate::config::AuthServiceSetting;
 
+use super::backup_codes::BackupCodes;
 use super::error::AuthApiError;
+use super::json::CasedJson;
+use super::login::add_csrf_token;
 
 #[derive(Debug, Deserialize)]
 pub struct Verify2FARequest {
     pub email: Secret<String>,
-    #[serde(rename = "loginAttemptId")]
+    #[serde(rename = "loginAttemptId", alias = "login_attempt_id")]
     pub login_attempt_id: String,
-    #[serde(rename = "2FACode")]
+    #[serde(rename = "2FACode", alias = "two_fa_code", default)]
     pub two_factor_code: String,
+    /// One of the user's backup codes, sent instead of the emailed code
+    #[serde(rename = "backupCode", alias = "backup_code", default)]
+    pub backup_code: Option<Secret<String>>,
 }
 
 #[tracing::instrument(name = "Verify 2FA", skip_all)]
-pub async fn verify_2fa<T>(
-    State(two_fa_code_store): State<T>,
+pub async fn verify_2fa<U, T, B, R>(
+    State((user_store, two_fa_code_store, banned_token_store, rate_limit_store)): State<(
+        U,
+        T,
+        B,
+        R,
+    )>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    backup_codes: Option<Extension<BackupCodes>>,
+    token_minter: Option<Extension<TokenMinter>>,
     jar: CookieJar,
     Json(request): Json<Verify2FARequest>,
-) -> Result<impl IntoResponse, AuthApiError>
+) -> Result<Response, AuthApiError>
 where
+    U: UserStore + Clone + 'static,
     T: TwoFaCodeStore + Clone + 'static,
+    B: BannedTokenStore + Clone + 'static,
+    R: RateLimitStore + Clone + 'static,
 {
     let config = AuthServiceSetting::load();
 
     // Parse domain entities
     let email = Email::try_from(request.email)?;
-    let login_attempt_id = TwoFaAttemptId::parse(&request.login_attempt_id)?;
-    let two_fa_code = TwoFaCode::parse(request.two_factor_code)?;
 
-    // Use the verify 2FA use case
-    let use_case = Verify2FaUseCase::new(two_fa_code_store);
-    let verified_email = use_case
-        .execute(email, login_attempt_id, two_fa_code)
-        .await?;
+    // The client IP is only known when the server is run with connect info
+    if let Some(rate_limit) = config.auth.two_fa.verify_rate_limit() {
+        let ip = connect_info.map(|Extension(ConnectInfo(addr))| addr.ip());
+        rate_limit.check(&rate_limit_store, &email, ip).await?;
+    }
+
+    let verified_email = if let Some(backup_code) = request.backup_code {
+        let Some(Extension(backup_code_store)) = backup_codes else {
+            return Err(AuthApiError::InvalidInput(
+                "Backup codes are not enabled".to_string(),
+            ));
+        };
+        // Stateless attempt tokens are bound to the emailed code
+        if config.auth.two_fa.stateless {
+            return Err(AuthApiError::InvalidInput(
+                "Backup codes are not supported with stateless 2FA".to_string(),
+            ));
+        }
+        let backup_code = BackupCode::parse(backup_code.expose_secret())?;
+        let login_attempt_id = TwoFaAttemptId::parse(&request.login_attempt_id)?;
+        let use_case = Verify2FaUseCase::new(two_fa_code_store);
+        use_case
+            .execute_with_backup_code(
+                email,
+                login_attempt_id,
+                backup_code,
+                backup_code_store.as_ref(),
+            )
+            .await?
+    } else if config.auth.two_fa.stateless {
+        let two_fa_code = TwoFaCode::parse(request.two_factor_code)?;
+        // The login attempt ID field carries the signed 2FA token
+        let signer = create_two_fa_token_signer(&config);
+        let use_case = StatelessVerify2FaUseCase::new(banned_token_store);
+        match config.auth.two_fa.stateless_max_failed_codes {
+            0 => {
+                use_case
+                    .execute(email, request.login_attempt_id, two_fa_code, &signer)
+                    .await?
+            }
+            max_failed_codes => {
+                use_case
+                    .execute_with_failure_limit(
+                        email,
+                        request.login_attempt_id,
+                        two_fa_code,
+                        &signer,
+                        &rate_limit_store,
+                        max_failed_codes,
+                    )
+                    .await?
+            }
+        }
+    } else {
+        let two_fa_code = TwoFaCode::parse(request.two_factor_code)?;
+        let login_attempt_id = TwoFaAttemptId::parse(&request.login_attempt_id)?;
+        let use_case = Verify2FaUseCase::new(two_fa_code_store);
+        use_case
+            .execute(email, login_attempt_id, two_fa_code)
+            .await?
+    };
 
     // Generate auth cookie
-    let auth_cookie = generate_auth_cookie(&verified_email, &config)?;
+    let user = user_store.get_user(&verified_email).await?;
+    let token_minter = token_minter.map(|Extension(m)| m).unwrap_or_default();
+    let auth_cookie =
+        generate_auth_cookie(&token_minter, user.id(), &verified_email, &config).await?;
     let updated_jar = jar.add(auth_cookie);
 
-    Ok((updated_jar, StatusCode::OK))
+    match add_csrf_token(updated_jar, &config) {
+        (jar, Some(csrf)) => Ok((jar, (StatusCode::OK, CasedJson(csrf))).into_response()),
+        (jar, None) => Ok((jar, StatusCode::OK).into_response()),
+    }
 }
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+use secrecy::Secret;
+use serde::Deserialize;
+use tempered_application::VerifyPasswordUseCase;
+use tempered_core::{BannedTokenStore, Password, RateLimitStore, UserStore};
+
+use crate::auth::{extract_token, resolve_token_user, validate_auth_token};
+use crate::config::AuthServiceSetting;
+
+use super::error::AuthApiError;
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyPasswordRequest {
+    pub password: Secret<String>,
+}
+
+/// Check the logged in user's password without issuing a token, 200 if it is
+/// correct and 401 otherwise
+///
+/// Attempts count towards the `auth.elevate.min_interval_in_seconds` cooldown
+/// shared with `/elevate`.
+#[tracing::instrument(name = "Verify password", skip_all)]
+pub async fn verify_password<U, B, R>(
+    State((user_store, banned_token_store, rate_limit_store)): State<(U, B, R)>,
+    jar: CookieJar,
+    Json(request): Json<VerifyPasswordRequest>,
+) -> Result<impl IntoResponse, AuthApiError>
+where
+    U: UserStore + Clone + 'static,
+    B: BannedTokenStore + Clone + 'static,
+    R: RateLimitStore + Clone + 'static,
+{
+    let config = AuthServiceSetting::load();
+
+    let token = extract_token(&jar, &config.auth.jwt.cookie_name)?;
+    let claims = validate_auth_token(token, &banned_token_store).await?;
+
+    let email = resolve_token_user(&claims, &user_store)
+        .await?
+        .email()
+        .clone();
+    let password = Password::try_from(request.password)?;
+
+    let use_case = VerifyPasswordUseCase::new(user_store);
+    match config.auth.elevate.min_interval_in_seconds {
+        0 => use_case.execute(email, password).await?,
+        seconds => {
+            use_case
+                .execute_with_cooldown(
+                    email,
+                    password,
+                    &rate_limit_store,
+                    Duration::from_secs(seconds),
+                )
+                .await?
+        }
+    }
+
+    Ok(StatusCode::OK)
+}
@@ -1,8 +1,11 @@
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use secrecy::ExposeSecret;
 use serde::Deserialize;
+use tempered_application::{TokenType, VerifyTokenUseCase};
 use tempered_core::BannedTokenStore;
 
-use crate::auth::validate_auth_token;
+use crate::auth::TokenAuthError;
+use crate::config::AuthServiceSetting;
 
 use super::error::AuthApiError;
 
@@ -19,10 +22,16 @@ pub async fn verify_token<B>(
 where
     B: BannedTokenStore + Clone + 'static,
 {
-    let banned_token_store = banned_token_store;
+    let config = AuthServiceSetting::load();
+    let jwt_secret = config.auth.jwt.secret.expose_secret().as_bytes();
+
+    let use_case = VerifyTokenUseCase::new(banned_token_store);
 
     // Validate the token - this checks if it's valid and not banned
-    let _claims = validate_auth_token(&token_request.token, &banned_token_store).await?;
+    let claims = use_case.execute(&token_request.token, jwt_secret).await?;
+    if !claims.is_token_type(TokenType::Access) {
+        return Err(TokenAuthError::InvalidToken.into());
+    }
 
     Ok(StatusCode::OK)
 }
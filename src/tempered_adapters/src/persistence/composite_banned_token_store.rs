@@ -0,0 +1,152 @@
+use tempered_core::{BannedTokenStore, BannedTokenStoreError, UserId};
+
+/// Banned token store over two stores, e.g. the old and new Redis during a migration
+///
+/// A token is banned if either store has it. Bans are written to both stores unless
+/// secondary writes are turned off with [`CompositeBannedTokenStore::with_secondary_writes`].
+#[derive(Debug, Clone)]
+pub struct CompositeBannedTokenStore<P, S> {
+    primary: P,
+    secondary: S,
+    write_secondary: bool,
+}
+
+impl<P, S> CompositeBannedTokenStore<P, S>
+where
+    P: BannedTokenStore,
+    S: BannedTokenStore,
+{
+    pub fn new(primary: P, secondary: S) -> Self {
+        Self {
+            primary,
+            secondary,
+            write_secondary: true,
+        }
+    }
+
+    /// Whether bans are written to the secondary store as well as the primary
+    pub fn with_secondary_writes(mut self, enabled: bool) -> Self {
+        self.write_secondary = enabled;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<P, S> BannedTokenStore for CompositeBannedTokenStore<P, S>
+where
+    P: BannedTokenStore,
+    S: BannedTokenStore,
+{
+    async fn ban_token(&self, token: String) -> Result<(), BannedTokenStoreError> {
+        if self.write_secondary {
+            self.secondary.ban_token(token.clone()).await?;
+        }
+        self.primary.ban_token(token).await
+    }
+
+    async fn ban_token_until(
+        &self,
+        token: String,
+        expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        if self.write_secondary {
+            self.secondary
+                .ban_token_until(token.clone(), expires_at)
+                .await?;
+        }
+        self.primary.ban_token_until(token, expires_at).await
+    }
+
+    /// Atomic in the primary store, a ban only found in the secondary also counts
+    async fn ban_token_if_absent(
+        &self,
+        token: String,
+        expires_at: u64,
+    ) -> Result<bool, BannedTokenStoreError> {
+        if self.secondary.contains_token(&token).await? {
+            return Ok(false);
+        }
+        if !self
+            .primary
+            .ban_token_if_absent(token.clone(), expires_at)
+            .await?
+        {
+            return Ok(false);
+        }
+        if self.write_secondary {
+            self.secondary.ban_token_until(token, expires_at).await?;
+        }
+        Ok(true)
+    }
+
+    async fn contains_token(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
+        Ok(self.primary.contains_token(token).await?
+            || self.secondary.contains_token(token).await?)
+    }
+
+    /// Size of the primary store's ban list, the stores may share entries
+    async fn banned_count(&self) -> Result<usize, BannedTokenStoreError> {
+        self.primary.banned_count().await
+    }
+
+    async fn revoke_user_tokens(
+        &self,
+        user: &UserId,
+        revoked_at: u64,
+        expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        if self.write_secondary {
+            self.secondary
+                .revoke_user_tokens(user, revoked_at, expires_at)
+                .await?;
+        }
+        self.primary
+            .revoke_user_tokens(user, revoked_at, expires_at)
+            .await
+    }
+
+    async fn user_tokens_revoked_at(
+        &self,
+        user: &UserId,
+    ) -> Result<Option<u64>, BannedTokenStoreError> {
+        let primary = self.primary.user_tokens_revoked_at(user).await?;
+        let secondary = self.secondary.user_tokens_revoked_at(user).await?;
+        Ok(primary.max(secondary))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::HashSetBannedTokenStore;
+
+    #[tokio::test]
+    async fn test_token_banned_in_either_store() {
+        let primary = HashSetBannedTokenStore::new();
+        let secondary = HashSetBannedTokenStore::new();
+        let store = CompositeBannedTokenStore::new(primary.clone(), secondary.clone());
+
+        primary.ban_token("token1".to_owned()).await.unwrap();
+        secondary.ban_token("token2".to_owned()).await.unwrap();
+
+        assert!(store.contains_token("token1").await.unwrap());
+        assert!(store.contains_token("token2").await.unwrap());
+        assert!(!store.contains_token("token3").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_ban_token_writes() {
+        let primary = HashSetBannedTokenStore::new();
+        let secondary = HashSetBannedTokenStore::new();
+        let store = CompositeBannedTokenStore::new(primary.clone(), secondary.clone());
+
+        store.ban_token("token1".to_owned()).await.unwrap();
+        assert!(primary.contains_token("token1").await.unwrap());
+        assert!(secondary.contains_token("token1").await.unwrap());
+
+        let store = store.with_secondary_writes(false);
+        store.ban_token("token2".to_owned()).await.unwrap();
+        assert!(primary.contains_token("token2").await.unwrap());
+        assert!(!secondary.contains_token("token2").await.unwrap());
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use tempered_core::{ApiKeyHash, ApiKeyRecord, ApiKeyStore, ApiKeyStoreError};
+
+#[derive(Default, Clone)]
+pub struct HashMapApiKeyStore {
+    keys: Arc<RwLock<HashMap<ApiKeyHash, ApiKeyRecord>>>,
+}
+
+impl HashMapApiKeyStore {
+    pub fn new() -> Self {
+        Self {
+            keys: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyStore for HashMapApiKeyStore {
+    async fn add_key(&self, record: ApiKeyRecord) -> Result<(), ApiKeyStoreError> {
+        let mut keys = self.keys.write().await;
+        if keys.contains_key(&record.key_hash) {
+            return Err(ApiKeyStoreError::KeyAlreadyExists);
+        }
+        keys.insert(record.key_hash.clone(), record);
+        Ok(())
+    }
+
+    async fn get_key(&self, key_hash: &ApiKeyHash) -> Result<ApiKeyRecord, ApiKeyStoreError> {
+        let keys = self.keys.read().await;
+        keys.get(key_hash)
+            .cloned()
+            .ok_or(ApiKeyStoreError::KeyNotFound)
+    }
+
+    async fn delete_key(&self, key_hash: &ApiKeyHash) -> Result<(), ApiKeyStoreError> {
+        let mut keys = self.keys.write().await;
+        keys.remove(key_hash)
+            .map(|_| ())
+            .ok_or(ApiKeyStoreError::KeyNotFound)
+    }
+}
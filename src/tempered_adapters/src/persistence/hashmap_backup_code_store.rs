@@ -0,0 +1,53 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use tempered_core::{BackupCodeHash, BackupCodeStore, BackupCodeStoreError, Email};
+
+#[derive(Default, Clone)]
+pub struct HashMapBackupCodeStore {
+    codes: Arc<RwLock<HashMap<Email, HashSet<BackupCodeHash>>>>,
+}
+
+impl HashMapBackupCodeStore {
+    pub fn new() -> Self {
+        Self {
+            codes: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BackupCodeStore for HashMapBackupCodeStore {
+    async fn replace_codes(
+        &self,
+        user: &Email,
+        code_hashes: Vec<BackupCodeHash>,
+    ) -> Result<(), BackupCodeStoreError> {
+        let mut codes = self.codes.write().await;
+        codes.insert(user.clone(), code_hashes.into_iter().collect());
+        Ok(())
+    }
+
+    async fn consume_code(
+        &self,
+        user: &Email,
+        code_hash: &BackupCodeHash,
+    ) -> Result<(), BackupCodeStoreError> {
+        let mut codes = self.codes.write().await;
+        // Removing the code is what makes it single use
+        if codes
+            .get_mut(user)
+            .is_some_and(|user_codes| user_codes.remove(code_hash))
+        {
+            Ok(())
+        } else {
+            Err(BackupCodeStoreError::CodeNotFound)
+        }
+    }
+
+    async fn remaining_codes(&self, user: &Email) -> Result<usize, BackupCodeStoreError> {
+        let codes = self.codes.read().await;
+        Ok(codes.get(user).map_or(0, HashSet::len))
+    }
+}
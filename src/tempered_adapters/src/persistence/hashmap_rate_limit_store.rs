@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use tempered_core::{RateLimitStore, RateLimitStoreError};
+
+/// In-memory rate limit counters, only suitable for a single instance
+#[derive(Debug, Default, Clone)]
+pub struct HashMapRateLimitStore {
+    // Window start and hit count per key
+    windows: Arc<RwLock<HashMap<String, (Instant, u64)>>>,
+}
+
+impl HashMapRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for HashMapRateLimitStore {
+    async fn hit(&self, key: &str, window: Duration) -> Result<u64, RateLimitStoreError> {
+        let now = Instant::now();
+        let mut windows = self.windows.write().await;
+
+        // Drop expired windows so the map doesn't grow without bound
+        windows.retain(|_, (started, _)| now.duration_since(*started) < window);
+
+        let (_, count) = windows.entry(key.to_owned()).or_insert((now, 0));
+        *count += 1;
+        Ok(*count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_hits_are_counted_per_key() {
+        let store = HashMapRateLimitStore::new();
+        let window = Duration::from_secs(60);
+
+        assert_eq!(store.hit("a", window).await, Ok(1));
+        assert_eq!(store.hit("a", window).await, Ok(2));
+        assert_eq!(store.hit("b", window).await, Ok(1));
+    }
+
+    #[tokio::test]
+    async fn test_window_expires() {
+        let store = HashMapRateLimitStore::new();
+        let window = Duration::from_millis(20);
+
+        assert_eq!(store.hit("a", window).await, Ok(1));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(store.hit("a", window).await, Ok(1));
+    }
+}
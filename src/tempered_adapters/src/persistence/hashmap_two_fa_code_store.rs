@@ -1,20 +1,122 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 use tempered_core::{Email, TwoFaAttemptId, TwoFaCode, TwoFaCodeStore, TwoFaCodeStoreError};
 
+use super::two_fa_code_hasher::TwoFaCodeHasher;
+
+struct PendingAttempt {
+    id: TwoFaAttemptId,
+    // The stored form of the code
+    code: String,
+    created_at: Instant,
+}
+
+type PendingAttempts = Vec<PendingAttempt>;
+
+// IDs of consumed attempts per user, with when the attempt was created
+type ConsumedAttempts = HashMap<Email, Vec<(TwoFaAttemptId, Instant)>>;
+
+// Used markers kept per user without a TTL, the oldest are forgotten first
+const MAX_CONSUMED_ATTEMPTS_PER_USER: usize = 32;
+
 #[derive(Default, Clone)]
 pub struct HashMapTwoFaCodeStore {
-    codes: Arc<RwLock<HashMap<Email, (TwoFaAttemptId, TwoFaCode)>>>,
+    // Pending attempts per user, oldest first
+    codes: Arc<RwLock<HashMap<Email, PendingAttempts>>>,
+    // Attempt IDs whose code has been consumed, to tell reuse apart from unknown attempts
+    consumed: Arc<RwLock<ConsumedAttempts>>,
+    max_attempts: Option<usize>,
+    hasher: TwoFaCodeHasher,
+    ttl: Option<Duration>,
 }
 
 impl HashMapTwoFaCodeStore {
     pub fn new() -> Self {
-        Self {
-            codes: Arc::new(RwLock::new(HashMap::new())),
+        Self::default()
+    }
+
+    /// Keep at most `max_attempts` pending attempts per user, evicting the oldest
+    /// when a new one is stored. `None` keeps every attempt.
+    pub fn with_max_attempts(mut self, max_attempts: Option<usize>) -> Self {
+        self.max_attempts = max_attempts.map(|max| max.max(1));
+        self
+    }
+
+    /// Store codes in the form given by `hasher` instead of as they are
+    pub fn with_code_hasher(mut self, hasher: TwoFaCodeHasher) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Reject codes older than `ttl` with `AttemptExpired`. `None` keeps codes valid
+    /// until they are used.
+    pub fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    fn check_not_expired(&self, attempt: &PendingAttempt) -> Result<(), TwoFaCodeStoreError> {
+        match self.ttl {
+            Some(ttl) if attempt.created_at.elapsed() >= ttl => {
+                Err(TwoFaCodeStoreError::AttemptExpired)
+            }
+            _ => Ok(()),
         }
     }
+
+    /// Mark an attempt as used and remove it, `index` must be in the user's attempts
+    fn remove_attempt(
+        &self,
+        attempts: &mut PendingAttempts,
+        consumed: &mut ConsumedAttempts,
+        user_id: &Email,
+        index: usize,
+    ) {
+        // Markers of expired attempts are no longer needed, their codes are rejected anyway
+        if let Some(ttl) = self.ttl {
+            consumed.retain(|_, ids| {
+                ids.retain(|(_, created_at)| created_at.elapsed() < ttl);
+                !ids.is_empty()
+            });
+        }
+
+        // Other attempts, e.g. on another device, stay valid
+        let attempt = attempts.remove(index);
+        let ids = consumed.entry(user_id.clone()).or_default();
+        ids.push((attempt.id, attempt.created_at));
+        let forgotten = ids.len().saturating_sub(MAX_CONSUMED_ATTEMPTS_PER_USER);
+        ids.drain(..forgotten);
+    }
+
+    /// The user's pending attempts and the index of `login_attempt_id` among them
+    fn find_attempt<'a>(
+        &self,
+        codes: &'a mut HashMap<Email, PendingAttempts>,
+        consumed: &ConsumedAttempts,
+        user_id: &Email,
+        login_attempt_id: &TwoFaAttemptId,
+    ) -> Result<(&'a mut PendingAttempts, usize), TwoFaCodeStoreError> {
+        let already_used = consumed
+            .get(user_id)
+            .is_some_and(|ids| ids.iter().any(|(id, _)| id == login_attempt_id));
+        if already_used {
+            return Err(TwoFaCodeStoreError::CodeAlreadyUsed);
+        }
+
+        let attempts = codes
+            .get_mut(user_id)
+            .filter(|attempts| !attempts.is_empty())
+            .ok_or(TwoFaCodeStoreError::UserNotFound)?;
+        let index = attempts
+            .iter()
+            .position(|attempt| attempt.id == *login_attempt_id)
+            .ok_or(TwoFaCodeStoreError::InvalidAttemptId)?;
+        self.check_not_expired(&attempts[index])?;
+        Ok((attempts, index))
+    }
 }
 
 #[async_trait::async_trait]
@@ -26,7 +128,20 @@ impl TwoFaCodeStore for HashMapTwoFaCodeStore {
         two_fa_code: TwoFaCode,
     ) -> Result<(), TwoFaCodeStoreError> {
         let mut codes = self.codes.write().await;
-        codes.insert(user_id, (login_attempt_id, two_fa_code));
+        let attempts = codes.entry(user_id.clone()).or_default();
+        // Used markers only matter while the user still has attempts in flight
+        if attempts.is_empty() {
+            self.consumed.write().await.remove(&user_id);
+        }
+        attempts.push(PendingAttempt {
+            id: login_attempt_id,
+            code: self.hasher.digest(&two_fa_code),
+            created_at: Instant::now(),
+        });
+        if let Some(max_attempts) = self.max_attempts {
+            let evicted = attempts.len().saturating_sub(max_attempts);
+            attempts.drain(..evicted);
+        }
         Ok(())
     }
 
@@ -37,14 +152,16 @@ impl TwoFaCodeStore for HashMapTwoFaCodeStore {
         two_fa_code: &TwoFaCode,
     ) -> Result<(), TwoFaCodeStoreError> {
         let codes = self.codes.read().await;
-        let Some((id, code)) = codes.get(user_id) else {
-            return Err(TwoFaCodeStoreError::UserNotFound);
-        };
+        let attempts = pending_attempts(&codes, user_id)?;
 
-        if id != login_attempt_id {
+        let Some(attempt) = attempts
+            .iter()
+            .find(|attempt| attempt.id == *login_attempt_id)
+        else {
             return Err(TwoFaCodeStoreError::InvalidAttemptId);
-        }
-        if code != two_fa_code {
+        };
+        self.check_not_expired(attempt)?;
+        if !self.hasher.matches(two_fa_code, &attempt.code) {
             return Err(TwoFaCodeStoreError::Invalid2FACode);
         }
         Ok(())
@@ -55,17 +172,297 @@ impl TwoFaCodeStore for HashMapTwoFaCodeStore {
         user_id: &Email,
     ) -> Result<(TwoFaAttemptId, TwoFaCode), TwoFaCodeStoreError> {
         let codes = self.codes.read().await;
-        let Some((id, code)) = codes.get(user_id) else {
-            return Err(TwoFaCodeStoreError::UserNotFound);
-        };
-        Ok((id.clone(), code.clone()))
+        let attempts = pending_attempts(&codes, user_id)?;
+        let attempt = attempts.last().ok_or(TwoFaCodeStoreError::UserNotFound)?;
+        self.check_not_expired(attempt)?;
+        if !self.hasher.is_plaintext() {
+            return Err(TwoFaCodeStoreError::UnexpectedError(
+                "2FA codes are stored hashed".to_owned(),
+            ));
+        }
+        let code = TwoFaCode::parse(attempt.code.clone())
+            .map_err(|e| TwoFaCodeStoreError::UnexpectedError(e.to_string()))?;
+        Ok((attempt.id.clone(), code))
     }
 
     async fn delete(&self, user_id: &Email) -> Result<(), TwoFaCodeStoreError> {
         let mut codes = self.codes.write().await;
         codes
             .remove(user_id)
+            .filter(|attempts| !attempts.is_empty())
             .ok_or(TwoFaCodeStoreError::UserNotFound)?;
         Ok(())
     }
+
+    async fn consume(
+        &self,
+        user_id: &Email,
+        login_attempt_id: &TwoFaAttemptId,
+        two_fa_code: &TwoFaCode,
+    ) -> Result<(), TwoFaCodeStoreError> {
+        // Holding the write lock makes the check and the removal one step
+        let mut codes = self.codes.write().await;
+        let mut consumed = self.consumed.write().await;
+
+        let (attempts, index) =
+            self.find_attempt(&mut codes, &consumed, user_id, login_attempt_id)?;
+        if !self.hasher.matches(two_fa_code, &attempts[index].code) {
+            return Err(TwoFaCodeStoreError::Invalid2FACode);
+        }
+
+        self.remove_attempt(attempts, &mut consumed, user_id, index);
+        Ok(())
+    }
+
+    async fn discard_attempt(
+        &self,
+        user_id: &Email,
+        login_attempt_id: &TwoFaAttemptId,
+    ) -> Result<(), TwoFaCodeStoreError> {
+        let mut codes = self.codes.write().await;
+        let mut consumed = self.consumed.write().await;
+
+        let (attempts, index) =
+            self.find_attempt(&mut codes, &consumed, user_id, login_attempt_id)?;
+        self.remove_attempt(attempts, &mut consumed, user_id, index);
+        Ok(())
+    }
+}
+
+fn pending_attempts<'a>(
+    codes: &'a HashMap<Email, PendingAttempts>,
+    user_id: &Email,
+) -> Result<&'a [PendingAttempt], TwoFaCodeStoreError> {
+    codes
+        .get(user_id)
+        .map(Vec::as_slice)
+        .filter(|attempts| !attempts.is_empty())
+        .ok_or(TwoFaCodeStoreError::UserNotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::Secret;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_consume_is_single_use() {
+        let store = HashMapTwoFaCodeStore::new();
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let attempt_id = TwoFaAttemptId::new();
+        let code = TwoFaCode::new();
+
+        store
+            .store_code(email.clone(), attempt_id.clone(), code.clone())
+            .await
+            .unwrap();
+
+        let (first, second) = tokio::join!(
+            store.consume(&email, &attempt_id, &code),
+            store.consume(&email, &attempt_id, &code)
+        );
+
+        let mut results = [first, second];
+        results.sort_by_key(|r| r.is_err());
+        assert_eq!(results[0], Ok(()));
+        assert_eq!(results[1], Err(TwoFaCodeStoreError::CodeAlreadyUsed));
+    }
+
+    #[tokio::test]
+    async fn test_parallel_attempts_are_independent() {
+        let store = HashMapTwoFaCodeStore::new();
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let (first_id, first_code) = (TwoFaAttemptId::new(), TwoFaCode::new());
+        let (second_id, second_code) = (TwoFaAttemptId::new(), TwoFaCode::new());
+
+        store
+            .store_code(email.clone(), first_id.clone(), first_code.clone())
+            .await
+            .unwrap();
+        store
+            .store_code(email.clone(), second_id.clone(), second_code.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_login_attempt_id_and_two_fa_code(&email).await,
+            Ok((second_id.clone(), second_code.clone()))
+        );
+        assert_eq!(store.validate(&email, &first_id, &first_code).await, Ok(()));
+        assert_eq!(
+            store
+                .validate(&email, &TwoFaAttemptId::new(), &first_code)
+                .await,
+            Err(TwoFaCodeStoreError::InvalidAttemptId)
+        );
+
+        let (first, second) = tokio::join!(
+            store.consume(&email, &first_id, &first_code),
+            store.consume(&email, &second_id, &second_code)
+        );
+        assert_eq!(first, Ok(()));
+        assert_eq!(second, Ok(()));
+        assert_eq!(
+            store.consume(&email, &first_id, &first_code).await,
+            Err(TwoFaCodeStoreError::CodeAlreadyUsed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_consume_unknown_attempt() {
+        let store = HashMapTwoFaCodeStore::new();
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+
+        let result = store
+            .consume(&email, &TwoFaAttemptId::new(), &TwoFaCode::new())
+            .await;
+
+        assert_eq!(result, Err(TwoFaCodeStoreError::UserNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_max_attempts_evicts_oldest() {
+        let store = HashMapTwoFaCodeStore::new().with_max_attempts(Some(2));
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let attempts: Vec<_> = (0..3)
+            .map(|_| (TwoFaAttemptId::new(), TwoFaCode::new()))
+            .collect();
+
+        for (id, code) in &attempts {
+            store
+                .store_code(email.clone(), id.clone(), code.clone())
+                .await
+                .unwrap();
+        }
+
+        let (oldest_id, oldest_code) = &attempts[0];
+        assert_eq!(
+            store.validate(&email, oldest_id, oldest_code).await,
+            Err(TwoFaCodeStoreError::InvalidAttemptId)
+        );
+        for (id, code) in &attempts[1..] {
+            assert_eq!(store.validate(&email, id, code).await, Ok(()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hashed_codes() {
+        let store = HashMapTwoFaCodeStore::new().with_code_hasher(TwoFaCodeHasher::new(b"secret"));
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let (first_id, first_code) = (TwoFaAttemptId::new(), TwoFaCode::new());
+        let second_id = TwoFaAttemptId::new();
+
+        store
+            .store_code(email.clone(), first_id.clone(), first_code.clone())
+            .await
+            .unwrap();
+        store
+            .store_code(email.clone(), second_id.clone(), TwoFaCode::new())
+            .await
+            .unwrap();
+
+        let stored = store.codes.read().await[&email][0].code.clone();
+        assert_ne!(stored, *first_code);
+        assert!(
+            store
+                .get_login_attempt_id_and_two_fa_code(&email)
+                .await
+                .is_err()
+        );
+
+        assert_eq!(store.consume(&email, &first_id, &first_code).await, Ok(()));
+        assert_eq!(store.discard_attempt(&email, &second_id).await, Ok(()));
+        assert_eq!(
+            store.discard_attempt(&email, &second_id).await,
+            Err(TwoFaCodeStoreError::CodeAlreadyUsed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_attempt() {
+        let store = HashMapTwoFaCodeStore::new().with_ttl(Some(Duration::ZERO));
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let (attempt_id, code) = (TwoFaAttemptId::new(), TwoFaCode::new());
+
+        store
+            .store_code(email.clone(), attempt_id.clone(), code.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.consume(&email, &attempt_id, &code).await,
+            Err(TwoFaCodeStoreError::AttemptExpired)
+        );
+        assert_eq!(
+            store.consume(&email, &TwoFaAttemptId::new(), &code).await,
+            Err(TwoFaCodeStoreError::InvalidAttemptId)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_used_markers_are_bounded() {
+        let store = HashMapTwoFaCodeStore::new();
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let attempts: Vec<_> = (0..MAX_CONSUMED_ATTEMPTS_PER_USER + 1)
+            .map(|_| (TwoFaAttemptId::new(), TwoFaCode::new()))
+            .collect();
+
+        // A pending attempt keeps the markers from being cleared on the next login
+        store
+            .store_code(email.clone(), TwoFaAttemptId::new(), TwoFaCode::new())
+            .await
+            .unwrap();
+        for (id, code) in &attempts {
+            store
+                .store_code(email.clone(), id.clone(), code.clone())
+                .await
+                .unwrap();
+            store.consume(&email, id, code).await.unwrap();
+        }
+
+        assert_eq!(
+            store.consumed.read().await[&email].len(),
+            MAX_CONSUMED_ATTEMPTS_PER_USER
+        );
+        let (oldest_id, oldest_code) = &attempts[0];
+        assert_eq!(
+            store.consume(&email, oldest_id, oldest_code).await,
+            Err(TwoFaCodeStoreError::InvalidAttemptId)
+        );
+        let (latest_id, latest_code) = attempts.last().unwrap();
+        assert_eq!(
+            store.consume(&email, latest_id, latest_code).await,
+            Err(TwoFaCodeStoreError::CodeAlreadyUsed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_used_markers_expire_with_their_codes() {
+        let store = HashMapTwoFaCodeStore::new().with_ttl(Some(Duration::from_millis(50)));
+        let first = Email::try_from(Secret::from("first@example.com".to_string())).unwrap();
+        let second = Email::try_from(Secret::from("second@example.com".to_string())).unwrap();
+        let (first_id, first_code) = (TwoFaAttemptId::new(), TwoFaCode::new());
+        let (second_id, second_code) = (TwoFaAttemptId::new(), TwoFaCode::new());
+
+        store
+            .store_code(first.clone(), first_id.clone(), first_code.clone())
+            .await
+            .unwrap();
+        store.consume(&first, &first_id, &first_code).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        store
+            .store_code(second.clone(), second_id.clone(), second_code.clone())
+            .await
+            .unwrap();
+        store
+            .consume(&second, &second_id, &second_code)
+            .await
+            .unwrap();
+
+        let consumed = store.consumed.read().await;
+        assert!(!consumed.contains_key(&first));
+        assert!(consumed.contains_key(&second));
+    }
 }
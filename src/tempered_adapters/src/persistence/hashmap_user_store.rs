@@ -1,19 +1,41 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use tempered_core::{Email, Password, User, UserStore, UserStoreError, ValidatedUser};
+use secrecy::ExposeSecret;
+use tempered_core::{
+    Email, Identifier, MAX_EMAIL_SEARCH_LIMIT, PASSWORD_HISTORY_LENGTH, Password, User, UserId,
+    UserStore, UserStoreError, Username, ValidatedUser,
+};
 
 #[derive(Default, Clone)]
 pub struct HashMapUserStore {
-    users: Arc<RwLock<HashMap<Email, User>>>,
+    users: Arc<RwLock<Users>>,
+    usernames: Arc<RwLock<HashMap<Username, UserId>>>,
+    // Previous passwords per user, most recent first
+    password_history: Arc<RwLock<HashMap<UserId, VecDeque<Password>>>>,
+}
+
+/// Users keyed by id, with an index from their email
+#[derive(Default)]
+struct Users {
+    by_id: HashMap<UserId, User>,
+    ids: HashMap<Email, UserId>,
+}
+
+impl Users {
+    fn get(&self, email: &Email) -> Option<&User> {
+        self.ids.get(email).and_then(|id| self.by_id.get(id))
+    }
+
+    fn get_mut(&mut self, email: &Email) -> Option<&mut User> {
+        self.ids.get(email).and_then(|id| self.by_id.get_mut(id))
+    }
 }
 
 impl HashMapUserStore {
     pub fn new() -> Self {
-        Self {
-            users: Arc::new(RwLock::new(HashMap::new())),
-        }
+        Self::default()
     }
 }
 
@@ -21,10 +43,12 @@ impl HashMapUserStore {
 impl UserStore for HashMapUserStore {
     async fn add_user(&self, user: User) -> Result<(), UserStoreError> {
         let mut users = self.users.write().await;
-        if users.contains_key(user.email()) {
+        if users.ids.contains_key(user.email()) || users.by_id.contains_key(user.id()) {
             return Err(UserStoreError::UserAlreadyExists);
         }
-        users.insert(user.email().clone(), user);
+
+        users.ids.insert(user.email().clone(), *user.id());
+        users.by_id.insert(*user.id(), user);
         Ok(())
     }
 
@@ -36,10 +60,32 @@ impl UserStore for HashMapUserStore {
         let mut users = self.users.write().await;
         let user = users.get_mut(email).ok_or(UserStoreError::UserNotFound)?;
 
-        *user = User::new(email.clone(), new_password, user.requires_2fa());
+        let mut password_history = self.password_history.write().await;
+        let history = password_history.entry(*user.id()).or_default();
+        history.push_front(user.password().clone());
+        history.truncate(PASSWORD_HISTORY_LENGTH);
+
+        *user = User::new(email.clone(), new_password, user.requires_2fa()).with_id(*user.id());
         Ok(())
     }
 
+    async fn is_password_reused(
+        &self,
+        email: &Email,
+        candidate: &Password,
+    ) -> Result<bool, UserStoreError> {
+        let users = self.users.read().await;
+        let user = users.get(email).ok_or(UserStoreError::UserNotFound)?;
+        let in_history = self
+            .password_history
+            .read()
+            .await
+            .get(user.id())
+            .is_some_and(|history| history.iter().any(|password| password == candidate));
+
+        Ok(user.password_matches(candidate) || in_history)
+    }
+
     async fn authenticate_user(
         &self,
         email: &Email,
@@ -63,9 +109,103 @@ impl UserStore for HashMapUserStore {
             .ok_or(UserStoreError::UserNotFound)
     }
 
+    async fn get_user_by_id(&self, id: &UserId) -> Result<User, UserStoreError> {
+        let users = self.users.read().await;
+        users
+            .by_id
+            .get(id)
+            .cloned()
+            .ok_or(UserStoreError::UserNotFound)
+    }
+
     async fn delete_user(&self, user: &Email) -> Result<(), UserStoreError> {
         let mut users = self.users.write().await;
-        users.remove(user).ok_or(UserStoreError::UserNotFound)?;
+        let id = users.ids.remove(user).ok_or(UserStoreError::UserNotFound)?;
+        users.by_id.remove(&id);
+
+        self.password_history.write().await.remove(&id);
+        self.usernames
+            .write()
+            .await
+            .retain(|_, existing| *existing != id);
+        Ok(())
+    }
+
+    async fn set_requires_2fa(
+        &self,
+        email: &Email,
+        requires_2fa: bool,
+    ) -> Result<(), UserStoreError> {
+        let mut users = self.users.write().await;
+        let user = users.get_mut(email).ok_or(UserStoreError::UserNotFound)?;
+        user.requires_2fa = requires_2fa;
+        Ok(())
+    }
+
+    async fn search_by_email_prefix(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<Email>, UserStoreError> {
+        if prefix.is_empty() {
+            return Err(UserStoreError::InvalidSearch(
+                "email prefix must not be empty".to_string(),
+            ));
+        }
+
+        let users = self.users.read().await;
+        let mut emails: Vec<Email> = users
+            .ids
+            .keys()
+            .filter(|email| email.as_ref().expose_secret().starts_with(prefix))
+            .cloned()
+            .collect();
+        emails.sort_by(|a, b| a.as_ref().expose_secret().cmp(b.as_ref().expose_secret()));
+        emails.truncate(limit.min(MAX_EMAIL_SEARCH_LIMIT));
+        Ok(emails)
+    }
+
+    async fn authenticate_user_by_identifier(
+        &self,
+        identifier: &Identifier,
+        password: &Password,
+    ) -> Result<ValidatedUser, UserStoreError> {
+        let email = match identifier {
+            Identifier::Email(email) => email.clone(),
+            Identifier::Username(username) => {
+                let id = self
+                    .usernames
+                    .read()
+                    .await
+                    .get(username)
+                    .copied()
+                    .ok_or(UserStoreError::UserNotFound)?;
+                self.get_user_by_id(&id).await?.email().clone()
+            }
+        };
+
+        self.authenticate_user(&email, password).await
+    }
+
+    async fn set_username(&self, email: &Email, username: &Username) -> Result<(), UserStoreError> {
+        let id = *self
+            .users
+            .read()
+            .await
+            .ids
+            .get(email)
+            .ok_or(UserStoreError::UserNotFound)?;
+
+        let mut usernames = self.usernames.write().await;
+        if usernames
+            .get(username)
+            .is_some_and(|existing| *existing != id)
+        {
+            return Err(UserStoreError::UserAlreadyExists);
+        }
+
+        usernames.retain(|_, existing| *existing != id);
+        usernames.insert(username.clone(), id);
         Ok(())
     }
 }
@@ -1,19 +1,19 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use tempered_core::{BannedTokenStore, BannedTokenStoreError};
+use tempered_core::{BannedTokenStore, BannedTokenStoreError, UserId};
 
 #[derive(Debug, Default, Clone)]
 pub struct HashSetBannedTokenStore {
     banned_tokens: Arc<RwLock<HashSet<String>>>,
+    // Per user cutoff, tokens issued at or before it are rejected
+    revoked_users: Arc<RwLock<HashMap<UserId, u64>>>,
 }
 
 impl HashSetBannedTokenStore {
     pub fn new() -> Self {
-        Self {
-            banned_tokens: Arc::new(RwLock::new(HashSet::new())),
-        }
+        Self::default()
     }
 }
 
@@ -29,6 +29,38 @@ impl BannedTokenStore for HashSetBannedTokenStore {
         let banned_tokens = self.banned_tokens.read().await;
         Ok(banned_tokens.contains(token))
     }
+
+    async fn ban_token_if_absent(
+        &self,
+        token: String,
+        _expires_at: u64,
+    ) -> Result<bool, BannedTokenStoreError> {
+        // A single insert under the write lock is the check and the ban
+        Ok(self.banned_tokens.write().await.insert(token))
+    }
+
+    async fn banned_count(&self) -> Result<usize, BannedTokenStoreError> {
+        Ok(self.banned_tokens.read().await.len())
+    }
+
+    async fn revoke_user_tokens(
+        &self,
+        user: &UserId,
+        revoked_at: u64,
+        _expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        let mut revoked_users = self.revoked_users.write().await;
+        let cutoff = revoked_users.entry(*user).or_default();
+        *cutoff = (*cutoff).max(revoked_at);
+        Ok(())
+    }
+
+    async fn user_tokens_revoked_at(
+        &self,
+        user: &UserId,
+    ) -> Result<Option<u64>, BannedTokenStoreError> {
+        Ok(self.revoked_users.read().await.get(user).copied())
+    }
 }
 
 #[cfg(test)]
@@ -48,9 +80,45 @@ mod tests {
         assert!(store.contains_token("token1").await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_ban_token_if_absent_only_succeeds_once() {
+        let store = HashSetBannedTokenStore::new();
+        let first = store.ban_token_if_absent("token1".to_string(), 0).await;
+        let second = store.ban_token_if_absent("token1".to_string(), 0).await;
+
+        assert!(first.unwrap());
+        assert!(!second.unwrap());
+        assert!(store.contains_token("token1").await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_token_is_not_banned() {
         let store = HashSetBannedTokenStore::new();
         assert!(!store.contains_token("token2").await.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_banned_count() {
+        let store = HashSetBannedTokenStore::new();
+        assert_eq!(store.banned_count().await.unwrap(), 0);
+
+        store.ban_token("token1".to_string()).await.unwrap();
+        store.ban_token("token2".to_string()).await.unwrap();
+        store.ban_token("token1".to_string()).await.unwrap();
+        assert_eq!(store.banned_count().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user_tokens() {
+        let store = HashSetBannedTokenStore::new();
+        let user = UserId::new();
+        assert_eq!(store.user_tokens_revoked_at(&user).await.unwrap(), None);
+
+        store.revoke_user_tokens(&user, 200, 800).await.unwrap();
+        store.revoke_user_tokens(&user, 100, 700).await.unwrap();
+        assert_eq!(
+            store.user_tokens_revoked_at(&user).await.unwrap(),
+            Some(200)
+        );
+    }
 }
@@ -1,18 +1,36 @@
 // Production persistence adapters
+pub mod composite_banned_token_store;
+pub mod password_hashing_pool;
+pub mod postgres_api_key_store;
+pub mod postgres_rate_limit_store;
 pub mod postgres_user_store;
 pub mod redis_banned_token_store;
+pub mod redis_rate_limit_store;
 pub mod redis_two_fa_code_store;
+pub mod two_fa_code_hasher;
 
 // Test-only persistence adapters
+pub mod hashmap_api_key_store;
+pub mod hashmap_backup_code_store;
+pub mod hashmap_rate_limit_store;
 pub mod hashmap_two_fa_code_store;
 pub mod hashmap_user_store;
 pub mod hashset_banned_token_store;
 
 // Re-exports
-pub use postgres_user_store::PostgresUserStore;
+pub use composite_banned_token_store::CompositeBannedTokenStore;
+pub use password_hashing_pool::PasswordHashingPool;
+pub use postgres_api_key_store::PostgresApiKeyStore;
+pub use postgres_rate_limit_store::PostgresRateLimitStore;
+pub use postgres_user_store::{DeletionMode, PostgresUserStore};
 pub use redis_banned_token_store::RedisBannedTokenStore;
+pub use redis_rate_limit_store::RedisRateLimitStore;
 pub use redis_two_fa_code_store::RedisTwoFaCodeStore;
+pub use two_fa_code_hasher::TwoFaCodeHasher;
 
+pub use hashmap_api_key_store::HashMapApiKeyStore;
+pub use hashmap_backup_code_store::HashMapBackupCodeStore;
+pub use hashmap_rate_limit_store::HashMapRateLimitStore;
 pub use hashmap_two_fa_code_store::HashMapTwoFaCodeStore;
 pub use hashmap_user_store::HashMapUserStore;
 pub use hashset_banned_token_store::HashSetBannedTokenStore;
@@ -0,0 +1,110 @@
+use std::{
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::{mpsc, oneshot};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// Jobs queued per thread before callers wait for a free slot
+const QUEUED_JOBS_PER_THREAD: usize = 16;
+
+/// Dedicated threads for Argon2 hashing, so a login spike can't starve other
+/// `spawn_blocking` work of the shared blocking pool
+///
+/// Cloning shares the threads. They exit once every clone has been dropped.
+#[derive(Clone)]
+pub struct PasswordHashingPool {
+    sender: mpsc::Sender<Job>,
+}
+
+impl PasswordHashingPool {
+    /// Start a pool of `threads` threads, at least one
+    pub fn new(threads: usize) -> Self {
+        let threads = threads.max(1);
+        let (sender, receiver) = mpsc::channel::<Job>(threads * QUEUED_JOBS_PER_THREAD);
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for i in 0..threads {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("password-hashing-{i}"))
+                .spawn(move || {
+                    loop {
+                        // Release the lock before running the job so other threads can pick up work
+                        let job = match receiver.lock() {
+                            Ok(mut receiver) => receiver.blocking_recv(),
+                            Err(_) => None,
+                        };
+                        match job {
+                            // A panicking job drops its result sender, which the caller sees as an error
+                            Some(job) => {
+                                let _ = std::panic::catch_unwind(AssertUnwindSafe(job));
+                            }
+                            None => break,
+                        }
+                    }
+                })
+                .expect("Failed to spawn password hashing thread");
+        }
+
+        Self { sender }
+    }
+
+    /// Run `f` on one of the pool's threads and wait for its result
+    pub async fn run<T, F>(&self, f: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (result_sender, result_receiver) = oneshot::channel();
+        let job: Job = Box::new(move || {
+            // The caller may have given up waiting
+            let _ = result_sender.send(f());
+        });
+
+        self.sender
+            .send(job)
+            .await
+            .map_err(|_| "Password hashing pool has shut down".to_owned())?;
+        result_receiver
+            .await
+            .map_err(|_| "Password hashing job panicked".to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_runs_jobs_on_pool_threads() {
+        let pool = PasswordHashingPool::new(2);
+
+        let thread_name = pool
+            .run(|| std::thread::current().name().map(str::to_owned))
+            .await
+            .unwrap();
+        assert!(thread_name.unwrap().starts_with("password-hashing-"));
+
+        let handles: Vec<_> = (1..=8u32)
+            .map(|i| {
+                let pool = pool.clone();
+                tokio::spawn(async move { pool.run(move || i * 2).await.unwrap() })
+            })
+            .collect();
+        for (i, handle) in (1..=8u32).zip(handles) {
+            assert_eq!(handle.await.unwrap(), i * 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_panicking_job_keeps_thread_alive() {
+        let pool = PasswordHashingPool::new(1);
+
+        let result = pool.run(|| -> u32 { panic!("boom") }).await;
+        assert!(result.is_err());
+        assert_eq!(pool.run(|| 42).await, Ok(42));
+    }
+}
@@ -0,0 +1,153 @@
+use secrecy::{ExposeSecret, Secret};
+use sqlx::PgPool;
+use tempered_core::{ApiKeyHash, ApiKeyRecord, ApiKeyStore, ApiKeyStoreError, Email};
+
+/// API keys in Postgres, stored by their hash
+#[derive(Clone)]
+pub struct PostgresApiKeyStore {
+    pool: PgPool,
+}
+
+impl PostgresApiKeyStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyStore for PostgresApiKeyStore {
+    #[tracing::instrument(name = "Adding API key to PostgreSQL", skip_all)]
+    async fn add_key(&self, record: ApiKeyRecord) -> Result<(), ApiKeyStoreError> {
+        sqlx::query!(
+            r#"
+                INSERT INTO api_keys (key_hash, email, scopes)
+                VALUES ($1, $2, $3)
+            "#,
+            record.key_hash.as_str(),
+            record.email.as_ref().expose_secret(),
+            &record.scopes
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            if let Some(db_err) = e.as_database_error()
+                && db_err.is_unique_violation()
+            {
+                return ApiKeyStoreError::KeyAlreadyExists;
+            }
+            ApiKeyStoreError::UnexpectedError(e.to_string())
+        })?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving API key from PostgreSQL", skip_all)]
+    async fn get_key(&self, key_hash: &ApiKeyHash) -> Result<ApiKeyRecord, ApiKeyStoreError> {
+        let row = sqlx::query!(
+            r#"
+                SELECT key_hash, email, scopes
+                FROM api_keys
+                WHERE key_hash = $1
+            "#,
+            key_hash.as_str()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| ApiKeyStoreError::UnexpectedError(e.to_string()))?
+        .ok_or(ApiKeyStoreError::KeyNotFound)?;
+
+        Ok(ApiKeyRecord {
+            key_hash: ApiKeyHash::new(row.key_hash),
+            email: Email::try_from(Secret::new(row.email))
+                .map_err(|e| ApiKeyStoreError::UnexpectedError(e.to_string()))?,
+            scopes: row.scopes,
+        })
+    }
+
+    #[tracing::instrument(name = "Deleting API key from PostgreSQL", skip_all)]
+    async fn delete_key(&self, key_hash: &ApiKeyHash) -> Result<(), ApiKeyStoreError> {
+        let result = sqlx::query!(
+            r#"
+                DELETE FROM api_keys
+                WHERE key_hash = $1
+            "#,
+            key_hash.as_str()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiKeyStoreError::UnexpectedError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(ApiKeyStoreError::KeyNotFound);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::postgres_user_store::get_postgres_pool;
+    use testcontainers_modules::{
+        postgres,
+        testcontainers::{ContainerAsync, runners::AsyncRunner},
+    };
+
+    async fn setup_and_connect_db_container() -> (ContainerAsync<postgres::Postgres>, PgPool) {
+        let container = postgres::Postgres::default()
+            .start()
+            .await
+            .expect("Failed to start container");
+
+        let db_port = container
+            .get_host_port_ipv4(5432)
+            .await
+            .expect("Failed to get the mapped port of the container");
+
+        let host = container
+            .get_host()
+            .await
+            .expect("Failed to get the container host address");
+
+        let db_url = format!("postgres://postgres:postgres@{}:{}", host, db_port);
+
+        let connection = get_postgres_pool(&db_url, 5)
+            .await
+            .expect("Failed to connect to database");
+
+        sqlx::migrate!()
+            .run(&connection)
+            .await
+            .expect("Failed to migrate the database");
+
+        (container, connection)
+    }
+
+    #[tokio::test]
+    async fn test_postgres_api_key_add_get_and_delete() {
+        let (_container, pool) = setup_and_connect_db_container().await;
+        let store = PostgresApiKeyStore::new(pool);
+        let record = ApiKeyRecord {
+            key_hash: ApiKeyHash::new("hash".to_string()),
+            email: Email::try_from(Secret::from("admin@example.com".to_string())).unwrap(),
+            scopes: vec!["admin".to_string()],
+        };
+
+        store.add_key(record.clone()).await.unwrap();
+        assert_eq!(
+            store.add_key(record.clone()).await,
+            Err(ApiKeyStoreError::KeyAlreadyExists)
+        );
+        assert_eq!(store.get_key(&record.key_hash).await, Ok(record.clone()));
+
+        store.delete_key(&record.key_hash).await.unwrap();
+        assert_eq!(
+            store.get_key(&record.key_hash).await,
+            Err(ApiKeyStoreError::KeyNotFound)
+        );
+        assert_eq!(
+            store.delete_key(&record.key_hash).await,
+            Err(ApiKeyStoreError::KeyNotFound)
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use rand::Rng;
+use sqlx::PgPool;
+use tempered_core::{RateLimitStore, RateLimitStoreError};
+
+// On average one in this many hits also prunes expired windows
+const PRUNE_ONE_IN: u32 = 100;
+
+/// Rate limit counters in Postgres, for deployments without Redis
+#[derive(Clone)]
+pub struct PostgresRateLimitStore {
+    pool: PgPool,
+}
+
+impl PostgresRateLimitStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Remove counters whose window has ended
+    ///
+    /// # Returns
+    /// The number of removed counters
+    #[tracing::instrument(name = "Pruning expired rate limits", skip(self))]
+    pub async fn prune_expired(&self) -> Result<u64, RateLimitStoreError> {
+        let result = sqlx::query!(
+            r#"
+                DELETE FROM rate_limits
+                WHERE expires_at <= NOW()
+            "#
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RateLimitStoreError::UnexpectedError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for PostgresRateLimitStore {
+    #[tracing::instrument(name = "Recording rate limit hit in PostgreSQL", skip_all)]
+    async fn hit(&self, key: &str, window: Duration) -> Result<u64, RateLimitStoreError> {
+        // An expired window is restarted in place, so stale rows never inflate the count
+        let row = sqlx::query!(
+            r#"
+                INSERT INTO rate_limits (key, count, expires_at)
+                VALUES ($1, 1, NOW() + make_interval(secs => $2))
+                ON CONFLICT (key) DO UPDATE SET
+                    count = CASE
+                        WHEN rate_limits.expires_at <= NOW() THEN 1
+                        ELSE rate_limits.count + 1
+                    END,
+                    expires_at = CASE
+                        WHEN rate_limits.expires_at <= NOW() THEN EXCLUDED.expires_at
+                        ELSE rate_limits.expires_at
+                    END
+                RETURNING count
+            "#,
+            key,
+            window.as_secs_f64()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RateLimitStoreError::UnexpectedError(e.to_string()))?;
+
+        // Keys that are never hit again would otherwise stay forever
+        if rand::rng().random_ratio(1, PRUNE_ONE_IN)
+            && let Err(e) = self.prune_expired().await
+        {
+            tracing::warn!(error = %e, "Failed to prune expired rate limits");
+        }
+
+        Ok(row.count as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::postgres_user_store::get_postgres_pool;
+    use testcontainers_modules::{
+        postgres,
+        testcontainers::{ContainerAsync, runners::AsyncRunner},
+    };
+
+    async fn setup_and_connect_db_container() -> (ContainerAsync<postgres::Postgres>, PgPool) {
+        let container = postgres::Postgres::default()
+            .start()
+            .await
+            .expect("Failed to start container");
+
+        let db_port = container
+            .get_host_port_ipv4(5432)
+            .await
+            .expect("Failed to get the mapped port of the container");
+
+        let host = container
+            .get_host()
+            .await
+            .expect("Failed to get the container host address");
+
+        let db_url = format!("postgres://postgres:postgres@{}:{}", host, db_port);
+
+        let connection = get_postgres_pool(&db_url, 5)
+            .await
+            .expect("Failed to connect to database");
+
+        sqlx::migrate!()
+            .run(&connection)
+            .await
+            .expect("Failed to migrate the database");
+
+        (container, connection)
+    }
+
+    #[tokio::test]
+    async fn test_postgres_rate_limit_hits_are_counted_per_window() {
+        let (_container, pool) = setup_and_connect_db_container().await;
+        let store = PostgresRateLimitStore::new(pool);
+        let window = Duration::from_millis(500);
+
+        assert_eq!(store.hit("a", window).await, Ok(1));
+        assert_eq!(store.hit("a", window).await, Ok(2));
+        assert_eq!(store.hit("b", window).await, Ok(1));
+
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert_eq!(store.hit("a", window).await, Ok(1));
+        assert_eq!(store.prune_expired().await, Ok(1));
+    }
+}
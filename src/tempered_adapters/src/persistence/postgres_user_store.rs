@@ -1,52 +1,148 @@
+use super::PasswordHashingPool;
 use argon2::{
     Algorithm, Argon2, Params, PasswordHash, PasswordVerifier, Version,
     password_hash::{PasswordHasher, SaltString, rand_core},
 };
+use chrono::{DateTime, Utc};
 use secrecy::{ExposeSecret, Secret};
 use sqlx::{PgPool, Pool, Postgres, postgres::PgPoolOptions};
-use tempered_core::{Email, Password, User, UserStore, UserStoreError, ValidatedUser};
+use tempered_core::{
+    Email, Identifier, MAX_EMAIL_SEARCH_LIMIT, PASSWORD_HISTORY_LENGTH, Password, ProbeableStore,
+    StoreProbe, User, UserId, UserStore, UserStoreError, Username, ValidatedUser,
+};
+
+/// How `delete_user` removes a user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeletionMode {
+    /// Remove the row
+    #[default]
+    Hard,
+    /// Keep the row and set `deleted_at`. Soft-deleted users are treated as not found,
+    /// and their email stays taken until the row is purged with `purge_deleted`.
+    Soft,
+}
 
 #[derive(Clone)]
 pub struct PostgresUserStore {
     pool: sqlx::PgPool,
+    deletion_mode: DeletionMode,
+    hashing_pool: Option<PasswordHashingPool>,
 }
 
 impl PostgresUserStore {
     pub fn new(pool: Pool<Postgres>) -> Self {
-        PostgresUserStore { pool }
+        Self::with_deletion_mode(pool, DeletionMode::Hard)
     }
-}
 
-#[async_trait::async_trait]
-impl UserStore for PostgresUserStore {
-    #[tracing::instrument(name = "Adding user to PostgreSQL", skip_all)]
-    async fn add_user(&self, user: User) -> Result<(), UserStoreError> {
-        let password = user.password().clone();
-        let password_hash = compute_password_hash(password)
+    pub fn with_deletion_mode(pool: Pool<Postgres>, deletion_mode: DeletionMode) -> Self {
+        PostgresUserStore {
+            pool,
+            deletion_mode,
+            hashing_pool: None,
+        }
+    }
+
+    /// Hash and verify passwords on `hashing_pool` instead of tokio's shared blocking pool
+    pub fn with_hashing_pool(mut self, hashing_pool: PasswordHashingPool) -> Self {
+        self.hashing_pool = Some(hashing_pool);
+        self
+    }
+
+    /// Permanently remove users that were soft-deleted before `before`
+    ///
+    /// # Arguments
+    /// * `before` - Only rows with `deleted_at` earlier than this are removed
+    ///
+    /// # Returns
+    /// The number of purged users
+    #[tracing::instrument(name = "Purging soft-deleted users", skip(self))]
+    pub async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<u64, UserStoreError> {
+        let query = sqlx::query!(
+            r#"
+                DELETE FROM users
+                WHERE deleted_at IS NOT NULL AND deleted_at < $1
+            "#,
+            before
+        );
+
+        let result = query
+            .execute(&self.pool)
             .await
             .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
 
+        Ok(result.rows_affected())
+    }
+
+    async fn insert_user(
+        &self,
+        id: &UserId,
+        email: &Email,
+        password_hash: Secret<String>,
+        requires_2fa: bool,
+    ) -> Result<(), UserStoreError> {
         let query = sqlx::query!(
             r#"
-                INSERT INTO users (email, password_hash, requires_2fa)
-                VALUES ($1, $2, $3)
+                INSERT INTO users (id, email, password_hash, requires_2fa)
+                VALUES ($1, $2, $3, $4)
             "#,
-            user.email().as_ref().expose_secret(),
+            id.as_uuid(),
+            email.as_ref().expose_secret(),
             password_hash.expose_secret(),
-            user.requires_2fa()
+            requires_2fa
         );
 
         query.execute(&self.pool).await.map_err(|e| {
-            if let Some(db_err) = e.as_database_error() {
-                if db_err.constraint().is_some() {
-                    return UserStoreError::UserAlreadyExists;
-                }
+            if let Some(db_err) = e.as_database_error()
+                && db_err.constraint().is_some()
+            {
+                return UserStoreError::UserAlreadyExists;
             }
             UserStoreError::UnexpectedError(e.to_string())
         })?;
 
         Ok(())
     }
+}
+
+#[async_trait::async_trait]
+impl ProbeableStore for PostgresUserStore {
+    async fn probe(&self) -> StoreProbe {
+        let start = std::time::Instant::now();
+        let result = sqlx::query("SELECT 1").execute(&self.pool).await;
+        if let Err(e) = &result {
+            tracing::warn!("Postgres probe failed: {e}");
+        }
+        StoreProbe {
+            healthy: result.is_ok(),
+            latency: start.elapsed(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl UserStore for PostgresUserStore {
+    #[tracing::instrument(name = "Adding user to PostgreSQL", skip_all)]
+    async fn add_user(&self, user: User) -> Result<(), UserStoreError> {
+        let password = user.password().clone();
+        let password_hash = compute_password_hash(password, self.hashing_pool.as_ref())
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+
+        self.insert_user(user.id(), user.email(), password_hash, user.requires_2fa())
+            .await
+    }
+
+    #[tracing::instrument(name = "Importing pre-hashed user to PostgreSQL", skip_all)]
+    async fn add_user_with_hash(
+        &self,
+        email: Email,
+        password_hash: Secret<String>,
+        requires_2fa: bool,
+    ) -> Result<(), UserStoreError> {
+        validate_password_hash(&password_hash)?;
+        self.insert_user(&UserId::new(), &email, password_hash, requires_2fa)
+            .await
+    }
 
     #[tracing::instrument(name = "Set new password", skip_all)]
     async fn set_new_password(
@@ -54,32 +150,141 @@ impl UserStore for PostgresUserStore {
         email: &Email,
         new_password: Password,
     ) -> Result<(), UserStoreError> {
-        let password_hash = compute_password_hash(new_password)
+        let password_hash = compute_password_hash(new_password, self.hashing_pool.as_ref())
             .await
             .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
 
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+
+        // Lock the row so concurrent changes record every replaced hash
+        let current = sqlx::query!(
+            r#"
+                SELECT id, password_hash
+                FROM users
+                WHERE email = $1 AND deleted_at IS NULL
+                FOR UPDATE
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+
+        if let Some(current) = current {
+            sqlx::query!(
+                r#"
+                    INSERT INTO password_history (user_id, password_hash)
+                    VALUES ($1, $2)
+                "#,
+                current.id,
+                current.password_hash
+            )
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+
+            sqlx::query!(
+                r#"
+                    DELETE FROM password_history
+                    WHERE user_id = $1 AND id NOT IN (
+                        SELECT id
+                        FROM password_history
+                        WHERE user_id = $1
+                        ORDER BY changed_at DESC, id DESC
+                        LIMIT $2
+                    )
+                "#,
+                current.id,
+                PASSWORD_HISTORY_LENGTH as i64
+            )
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+        }
+
         let query = sqlx::query!(
             r#"
                 UPDATE users
                 SET password_hash = $1
-                WHERE email = $2
+                WHERE email = $2 AND deleted_at IS NULL
             "#,
             password_hash.expose_secret(),
             email.as_ref().expose_secret()
         );
 
-        query.execute(&self.pool).await.map_err(|e| {
-            if let Some(db_err) = e.as_database_error() {
-                if db_err.constraint().is_some() {
-                    return UserStoreError::UserAlreadyExists;
-                }
+        query.execute(&mut *transaction).await.map_err(|e| {
+            if let Some(db_err) = e.as_database_error()
+                && db_err.constraint().is_some()
+            {
+                return UserStoreError::UserAlreadyExists;
             }
             UserStoreError::UnexpectedError(e.to_string())
         })?;
 
+        transaction
+            .commit()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+
         Ok(())
     }
 
+    #[tracing::instrument(name = "Checking password history in PostgreSQL", skip_all)]
+    async fn is_password_reused(
+        &self,
+        email: &Email,
+        candidate: &Password,
+    ) -> Result<bool, UserStoreError> {
+        let current = sqlx::query!(
+            r#"
+                SELECT id, password_hash
+                FROM users
+                WHERE email = $1 AND deleted_at IS NULL
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        let history = sqlx::query!(
+            r#"
+                SELECT password_hash
+                FROM password_history
+                WHERE user_id = $1
+                ORDER BY changed_at DESC, id DESC
+                LIMIT $2
+            "#,
+            current.id,
+            PASSWORD_HISTORY_LENGTH as i64
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+
+        let hashes = std::iter::once(current.password_hash)
+            .chain(history.into_iter().map(|row| row.password_hash));
+        for hash in hashes {
+            if verify_password_hash(
+                Secret::from(hash),
+                candidate.clone(),
+                self.hashing_pool.as_ref(),
+            )
+            .await
+            .is_ok()
+            {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
     #[tracing::instrument(name = "Validating user credentials in PostgreSQL", skip_all)]
     async fn authenticate_user(
         &self,
@@ -90,7 +295,7 @@ impl UserStore for PostgresUserStore {
             r#"
                 SELECT email, password_hash, requires_2fa
                 FROM users
-                WHERE email = $1
+                WHERE email = $1 AND deleted_at IS NULL
             "#,
             email.as_ref().expose_secret()
         );
@@ -104,9 +309,13 @@ impl UserStore for PostgresUserStore {
             return Err(UserStoreError::UserNotFound);
         };
 
-        verify_password_hash(Secret::from(row.password_hash), password.clone())
-            .await
-            .map_err(|_| UserStoreError::IncorrectPassword)?;
+        verify_password_hash(
+            Secret::from(row.password_hash),
+            password.clone(),
+            self.hashing_pool.as_ref(),
+        )
+        .await
+        .map_err(|_| UserStoreError::IncorrectPassword)?;
 
         let email = Email::try_from(Secret::from(row.email))
             .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
@@ -117,9 +326,9 @@ impl UserStore for PostgresUserStore {
     async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
         let query = sqlx::query!(
             r#"
-                SELECT email, password_hash, requires_2fa
+                SELECT id, email, password_hash, requires_2fa
                 FROM users
-                WHERE email = $1
+                WHERE email = $1 AND deleted_at IS NULL
             "#,
             email.as_ref().expose_secret()
         );
@@ -140,23 +349,199 @@ impl UserStore for PostgresUserStore {
         )
         .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
 
-        Ok(user)
+        Ok(user.with_id(UserId::from(row.id)))
     }
 
-    #[tracing::instrument(name = "Delete user from user store", skip_all)]
-    async fn delete_user(&self, user: &Email) -> Result<(), UserStoreError> {
+    #[tracing::instrument(name = "Retrieving user by id from PostgreSQL", skip_all)]
+    async fn get_user_by_id(&self, id: &UserId) -> Result<User, UserStoreError> {
         let query = sqlx::query!(
             r#"
-                DELETE FROM users
-                WHERE email = $1
+                SELECT id, email, password_hash, requires_2fa
+                FROM users
+                WHERE id = $1 AND deleted_at IS NULL
             "#,
-            user.as_ref().expose_secret()
+            id.as_uuid()
         );
 
-        let result = query
-            .execute(&self.pool)
+        let row = query
+            .fetch_optional(&self.pool)
             .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?
+            .ok_or(UserStoreError::UserNotFound)?;
+
+        let user = User::parse(
+            Secret::from(row.email),
+            Secret::from(row.password_hash),
+            row.requires_2fa,
+        )
+        .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+
+        Ok(user.with_id(UserId::from(row.id)))
+    }
+
+    #[tracing::instrument(name = "Delete user from user store", skip_all)]
+    async fn delete_user(&self, user: &Email) -> Result<(), UserStoreError> {
+        let result = match self.deletion_mode {
+            DeletionMode::Hard => {
+                sqlx::query!(
+                    r#"
+                        DELETE FROM users
+                        WHERE email = $1
+                    "#,
+                    user.as_ref().expose_secret()
+                )
+                .execute(&self.pool)
+                .await
+            }
+            DeletionMode::Soft => {
+                sqlx::query!(
+                    r#"
+                        UPDATE users
+                        SET deleted_at = NOW()
+                        WHERE email = $1 AND deleted_at IS NULL
+                    "#,
+                    user.as_ref().expose_secret()
+                )
+                .execute(&self.pool)
+                .await
+            }
+        }
+        .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Updating 2FA setting in PostgreSQL", skip_all)]
+    async fn set_requires_2fa(
+        &self,
+        email: &Email,
+        requires_2fa: bool,
+    ) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+                UPDATE users
+                SET requires_2fa = $1
+                WHERE email = $2 AND deleted_at IS NULL
+            "#,
+            requires_2fa,
+            email.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Searching users by email prefix in PostgreSQL", skip_all)]
+    async fn search_by_email_prefix(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<Email>, UserStoreError> {
+        if prefix.is_empty() {
+            return Err(UserStoreError::InvalidSearch(
+                "email prefix must not be empty".to_string(),
+            ));
+        }
+
+        let limit = limit.min(MAX_EMAIL_SEARCH_LIMIT) as i64;
+
+        let rows = sqlx::query!(
+            r#"
+                SELECT email
+                FROM users
+                WHERE email LIKE $1 || '%' ESCAPE '\' AND deleted_at IS NULL
+                ORDER BY email
+                LIMIT $2
+            "#,
+            escape_like_pattern(prefix),
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                Email::try_from(Secret::from(row.email))
+                    .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(
+        name = "Validating user credentials by identifier in PostgreSQL",
+        skip_all
+    )]
+    async fn authenticate_user_by_identifier(
+        &self,
+        identifier: &Identifier,
+        password: &Password,
+    ) -> Result<ValidatedUser, UserStoreError> {
+        let username = match identifier {
+            Identifier::Email(email) => return self.authenticate_user(email, password).await,
+            Identifier::Username(username) => username,
+        };
+
+        let row = sqlx::query!(
+            r#"
+                SELECT email, password_hash, requires_2fa
+                FROM users
+                WHERE lower(username) = $1 AND deleted_at IS NULL
+            "#,
+            username.as_ref()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UserNotFound)?;
+
+        let Some(row) = row else {
+            return Err(UserStoreError::UserNotFound);
+        };
+
+        verify_password_hash(
+            Secret::from(row.password_hash),
+            password.clone(),
+            self.hashing_pool.as_ref(),
+        )
+        .await
+        .map_err(|_| UserStoreError::IncorrectPassword)?;
+
+        let email = Email::try_from(Secret::from(row.email))
             .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))?;
+        Ok(ValidatedUser::new(email, row.requires_2fa))
+    }
+
+    #[tracing::instrument(name = "Setting username in PostgreSQL", skip_all)]
+    async fn set_username(&self, email: &Email, username: &Username) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+                UPDATE users
+                SET username = $1
+                WHERE email = $2 AND deleted_at IS NULL
+            "#,
+            username.as_ref(),
+            email.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            if let Some(db_err) = e.as_database_error()
+                && db_err.is_unique_violation()
+            {
+                return UserStoreError::UserAlreadyExists;
+            }
+            UserStoreError::UnexpectedError(e.to_string())
+        })?;
 
         if result.rows_affected() == 0 {
             return Err(UserStoreError::UserNotFound);
@@ -166,13 +551,38 @@ impl UserStore for PostgresUserStore {
     }
 }
 
+/// Escape the LIKE wildcards in user input so it only ever matches literally
+fn escape_like_pattern(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Check that an imported hash is a PHC string this store can verify against.
+fn validate_password_hash(password_hash: &Secret<String>) -> Result<(), UserStoreError> {
+    let parsed = PasswordHash::new(password_hash.expose_secret())
+        .map_err(|e| UserStoreError::InvalidPasswordHash(e.to_string()))?;
+
+    Algorithm::try_from(parsed.algorithm).map_err(|_| {
+        UserStoreError::InvalidPasswordHash(format!(
+            "unsupported algorithm '{}', expected an Argon2 hash",
+            parsed.algorithm
+        ))
+    })?;
+
+    Ok(())
+}
+
 #[tracing::instrument(name = "Verify password hash", skip_all)]
 async fn verify_password_hash(
     expected_password_hash: Secret<String>,
     password_candidate: Password,
+    hashing_pool: Option<&PasswordHashingPool>,
 ) -> Result<(), String> {
     let current_span: tracing::Span = tracing::Span::current();
-    let result = tokio::task::spawn_blocking(move || {
+
+    run_blocking(hashing_pool, move || {
         current_span.in_scope(|| {
             let expected_password_hash: PasswordHash<'_> =
                 PasswordHash::new(expected_password_hash.expose_secret())
@@ -190,17 +600,17 @@ async fn verify_password_hash(
             .map_err(|e| e.to_string())
         })
     })
-    .await
-    .map_err(|e| e.to_string())?;
-
-    result
+    .await?
 }
 
 #[tracing::instrument(name = "Computing password hash", skip_all)]
-async fn compute_password_hash(password: Password) -> Result<Secret<String>, String> {
+async fn compute_password_hash(
+    password: Password,
+    hashing_pool: Option<&PasswordHashingPool>,
+) -> Result<Secret<String>, String> {
     let current_span: tracing::Span = tracing::Span::current();
 
-    let result = tokio::task::spawn_blocking(move || {
+    run_blocking(hashing_pool, move || {
         current_span.in_scope(move || {
             let salt: SaltString = SaltString::generate(rand_core::OsRng);
             let hasher = Argon2::new(
@@ -214,10 +624,21 @@ async fn compute_password_hash(password: Password) -> Result<Secret<String>, Str
                 .map_err(|e| e.to_string())
         })
     })
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?
+}
 
-    result
+/// Run `f` on `hashing_pool` if one is set, otherwise on tokio's blocking pool
+async fn run_blocking<T, F>(hashing_pool: Option<&PasswordHashingPool>, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match hashing_pool {
+        Some(hashing_pool) => hashing_pool.run(f).await,
+        None => tokio::task::spawn_blocking(f)
+            .await
+            .map_err(|e| e.to_string()),
+    }
 }
 
 /// Create a PostgreSQL connection pool
@@ -228,10 +649,23 @@ async fn compute_password_hash(password: Password) -> Result<Secret<String>, Str
 /// # Returns
 /// Result containing the PgPool or an error
 pub async fn get_postgres_pool(url: &str, max_connections: u32) -> Result<PgPool, sqlx::Error> {
-    PgPoolOptions::new()
-        .max_connections(max_connections)
-        .connect(url)
-        .await
+    get_postgres_pool_with_options(url, PgPoolOptions::new().max_connections(max_connections)).await
+}
+
+/// Create a PostgreSQL connection pool with custom pool `options`, e.g.
+/// [`PostgresConfig::pool_options`](crate::config::settings::PostgresConfig::pool_options)
+///
+/// # Arguments
+/// * `url` - Database connection URL
+/// * `options` - Pool sizing and timeouts
+///
+/// # Returns
+/// Result containing the PgPool or an error
+pub async fn get_postgres_pool_with_options(
+    url: &str,
+    options: PgPoolOptions,
+) -> Result<PgPool, sqlx::Error> {
+    options.connect(url).await
 }
 
 #[cfg(test)]
@@ -322,6 +756,67 @@ mod tests {
         assert_eq!(result, Err(UserStoreError::UserAlreadyExists));
     }
 
+    #[tokio::test]
+    async fn test_get_user_by_id() {
+        let (_container, pool) = setup_and_connect_db_container().await;
+        let store = PostgresUserStore::new(pool);
+        let user = create_test_user();
+        store.add_user(user.clone()).await.unwrap();
+
+        let stored_user = store.get_user(user.email()).await.unwrap();
+        assert_eq!(stored_user.id(), user.id());
+
+        let stored_user = store.get_user_by_id(user.id()).await;
+        assert_eq!(stored_user, Ok(user));
+
+        let result = store.get_user_by_id(&UserId::new()).await;
+        assert_eq!(result, Err(UserStoreError::UserNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_add_user_with_hash_can_authenticate() {
+        let (_container, pool) = setup_and_connect_db_container().await;
+        let store = PostgresUserStore::new(pool);
+        let user = create_test_user();
+        let email = user.email().clone();
+        let password_hash = compute_password_hash(user.password().clone(), None)
+            .await
+            .unwrap();
+
+        store
+            .add_user_with_hash(email.clone(), password_hash, false)
+            .await
+            .unwrap();
+
+        let result = store.authenticate_user(&email, user.password()).await;
+        assert_eq!(result, Ok(ValidatedUser::No2Fa(email)));
+    }
+
+    #[test]
+    fn test_validate_password_hash() {
+        let argon2 = Secret::from(
+            "$argon2id$v=19$m=15000,t=2,p=1$c2FsdHNhbHRzYWx0$2dH2n1kkn5uxw6lYAFJbqILiTWdYFTLgBb8AiXqWQJA"
+                .to_string(),
+        );
+        let bcrypt = Secret::from(
+            "$2b$12$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW".to_string(),
+        );
+        let pbkdf2 = Secret::from(
+            "$pbkdf2-sha256$i=10000,l=32$c2FsdHNhbHRzYWx0$YaBHd0lPA2Bi3uFy5X5gXr9fYfGgGnCYpQzCdZ8dQzc"
+                .to_string(),
+        );
+
+        assert!(validate_password_hash(&argon2).is_ok());
+        assert!(matches!(
+            validate_password_hash(&bcrypt),
+            Err(UserStoreError::InvalidPasswordHash(_))
+        ));
+        assert!(matches!(
+            validate_password_hash(&pbkdf2),
+            Err(UserStoreError::InvalidPasswordHash(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_authenticate_user_success() {
         let (_container, pool) = setup_and_connect_db_container().await;
@@ -470,10 +965,67 @@ mod tests {
         assert_eq!(result, Err(UserStoreError::UserNotFound));
     }
 
+    #[tokio::test]
+    async fn test_soft_delete_hides_user() {
+        let (_container, pool) = setup_and_connect_db_container().await;
+        let store = PostgresUserStore::with_deletion_mode(pool, DeletionMode::Soft);
+        let user = create_test_user();
+        let email = user.email().clone();
+
+        store.add_user(user.clone()).await.unwrap();
+        store.delete_user(&email).await.unwrap();
+
+        assert_eq!(
+            store.get_user(&email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            store.authenticate_user(&email, user.password()).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            store.delete_user(&email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        // The row is kept, so the email can't be reused until it is purged
+        assert_eq!(
+            store.add_user(user).await,
+            Err(UserStoreError::UserAlreadyExists)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_purge_deleted() {
+        let (_container, pool) = setup_and_connect_db_container().await;
+        let store = PostgresUserStore::with_deletion_mode(pool, DeletionMode::Soft);
+        let deleted_user = create_test_user();
+        let active_user = create_test_user_with_2fa();
+
+        store.add_user(deleted_user.clone()).await.unwrap();
+        store.add_user(active_user.clone()).await.unwrap();
+        store.delete_user(deleted_user.email()).await.unwrap();
+
+        let purged = store
+            .purge_deleted(Utc::now() - chrono::Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(purged, 0);
+
+        let purged = store
+            .purge_deleted(Utc::now() + chrono::Duration::seconds(1))
+            .await
+            .unwrap();
+        assert_eq!(purged, 1);
+
+        // The email is free again and active users are untouched
+        store.add_user(deleted_user).await.unwrap();
+        assert_eq!(store.get_user(active_user.email()).await, Ok(active_user));
+    }
+
     #[tokio::test]
     async fn test_compute_password_hash() {
         let password = Password::try_from(Secret::from("testpassword123".to_owned())).unwrap();
-        let hash_result = compute_password_hash(password.clone()).await;
+        let hash_result = compute_password_hash(password.clone(), None).await;
 
         assert!(hash_result.is_ok());
         let hash = hash_result.unwrap();
@@ -484,9 +1036,9 @@ mod tests {
     #[tokio::test]
     async fn test_verify_password_hash_success() {
         let password = Password::try_from(Secret::from("testpassword123".to_owned())).unwrap();
-        let hash = compute_password_hash(password.clone()).await.unwrap();
+        let hash = compute_password_hash(password.clone(), None).await.unwrap();
 
-        let result = verify_password_hash(hash, password).await;
+        let result = verify_password_hash(hash, password, None).await;
         assert!(result.is_ok());
     }
 
@@ -494,32 +1046,139 @@ mod tests {
     async fn test_verify_password_hash_failure() {
         let password = Password::try_from(Secret::from("testpassword123".to_owned())).unwrap();
         let wrong_password = Password::try_from(Secret::from("wrongpassword".to_owned())).unwrap();
-        let hash = compute_password_hash(password).await.unwrap();
+        let hash = compute_password_hash(password, None).await.unwrap();
 
-        let result = verify_password_hash(hash, wrong_password).await;
+        let result = verify_password_hash(hash, wrong_password, None).await;
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_set_requires_2fa() {
+        let (_container, pool) = setup_and_connect_db_container().await;
+        let store = PostgresUserStore::new(pool.clone());
+        let user = create_test_user();
+        store.add_user(user.clone()).await.unwrap();
+
+        store.set_requires_2fa(user.email(), true).await.unwrap();
+        assert!(store.get_user(user.email()).await.unwrap().requires_2fa());
+
+        store.set_requires_2fa(user.email(), false).await.unwrap();
+        assert!(!store.get_user(user.email()).await.unwrap().requires_2fa());
+    }
+
+    #[tokio::test]
+    async fn test_set_requires_2fa_for_missing_user() {
+        let (_container, pool) = setup_and_connect_db_container().await;
+        let store = PostgresUserStore::new(pool.clone());
+        let user = create_test_user();
+
+        let result = store.set_requires_2fa(user.email(), true).await;
+        assert_eq!(result, Err(UserStoreError::UserNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_search_by_email_prefix() {
+        let (_container, pool) = setup_and_connect_db_container().await;
+        let store = PostgresUserStore::new(pool.clone());
+        for email in ["alice@example.com", "alicia@example.com", "bob@example.com"] {
+            let user = User::parse(
+                Secret::from(email.to_owned()),
+                Secret::from("password123".to_owned()),
+                false,
+            )
+            .unwrap();
+            store.add_user(user).await.unwrap();
+        }
+
+        let emails = store.search_by_email_prefix("ali", 10).await.unwrap();
+        let emails: Vec<_> = emails
+            .iter()
+            .map(|email| email.as_ref().expose_secret().as_str())
+            .collect();
+        assert_eq!(emails, ["alice@example.com", "alicia@example.com"]);
+
+        let emails = store.search_by_email_prefix("ali", 1).await.unwrap();
+        assert_eq!(emails.len(), 1);
+
+        // Wildcards in the prefix only match literally
+        let emails = store.search_by_email_prefix("%", 10).await.unwrap();
+        assert!(emails.is_empty());
+
+        let result = store.search_by_email_prefix("", 10).await;
+        assert!(matches!(result, Err(UserStoreError::InvalidSearch(_))));
+    }
+
+    #[test]
+    fn test_escape_like_pattern() {
+        assert_eq!(escape_like_pattern("a_b%c\\d"), "a\\_b\\%c\\\\d");
+        assert_eq!(escape_like_pattern("alice"), "alice");
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_user_by_username() {
+        let (_container, pool) = setup_and_connect_db_container().await;
+        let store = PostgresUserStore::new(pool.clone());
+        let user = create_test_user();
+        let username = Username::try_from("test_user".to_owned()).unwrap();
+        store.add_user(user.clone()).await.unwrap();
+        store.set_username(user.email(), &username).await.unwrap();
+
+        let validated = store
+            .authenticate_user_by_identifier(&Identifier::Username(username), user.password())
+            .await
+            .unwrap();
+        assert_eq!(validated.email(), user.email());
+
+        let unknown = Identifier::Username(Username::try_from("nobody".to_owned()).unwrap());
+        let result = store
+            .authenticate_user_by_identifier(&unknown, user.password())
+            .await;
+        assert_eq!(result, Err(UserStoreError::UserNotFound));
+    }
+
     #[tokio::test]
     async fn test_verify_password_hash_invalid_hash() {
         let invalid_hash = Secret::from("invalid_hash_format".to_owned());
         let password = Password::try_from(Secret::from("testpassword123".to_owned())).unwrap();
 
-        let result = verify_password_hash(invalid_hash, password).await;
+        let result = verify_password_hash(invalid_hash, password, None).await;
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_password_hash_on_hashing_pool() {
+        let hashing_pool = PasswordHashingPool::new(1);
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let hash = compute_password_hash(password.clone(), Some(&hashing_pool))
+            .await
+            .unwrap();
+        assert!(
+            verify_password_hash(hash, password, Some(&hashing_pool))
+                .await
+                .is_ok()
+        );
+    }
+
     #[tokio::test]
     async fn test_compute_password_hash_deterministic_salt() {
         let password = Password::try_from(Secret::from("testpassword123".to_owned())).unwrap();
-        let hash1 = compute_password_hash(password.clone()).await.unwrap();
-        let hash2 = compute_password_hash(password.clone()).await.unwrap();
+        let hash1 = compute_password_hash(password.clone(), None).await.unwrap();
+        let hash2 = compute_password_hash(password.clone(), None).await.unwrap();
 
         // Hashes should be different due to random salt
         assert_ne!(hash1.expose_secret(), hash2.expose_secret());
 
         // But both should verify successfully
-        assert!(verify_password_hash(hash1, password.clone()).await.is_ok());
-        assert!(verify_password_hash(hash2, password.clone()).await.is_ok());
+        assert!(
+            verify_password_hash(hash1, password.clone(), None)
+                .await
+                .is_ok()
+        );
+        assert!(
+            verify_password_hash(hash2, password.clone(), None)
+                .await
+                .is_ok()
+        );
     }
 }
@@ -1,42 +1,271 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use redis::{Commands, Connection};
-use tempered_core::{BannedTokenStore, BannedTokenStoreError};
+use chrono::Utc;
+use redis::{Client, Commands, Connection, RedisError, RedisResult};
+use serde::Deserialize;
+use tempered_core::{BannedTokenStore, BannedTokenStoreError, ProbeableStore, StoreProbe, UserId};
 use tokio::sync::RwLock;
 
+// Reconnects attempted per call before giving up on a broken connection
+const MAX_RECONNECT_ATTEMPTS: usize = 3;
+
+// How long a counted number of bans is reported before the keys are scanned again
+const BANNED_COUNT_CACHE_TTL: Duration = Duration::from_secs(30);
+
 #[derive(Clone)]
 pub struct RedisBannedTokenStore {
     conn: Arc<RwLock<Connection>>,
+    client: Option<Client>,
     token_ttl: u64,
+    banned_count: Arc<Mutex<Option<(Instant, usize)>>>,
 }
 
 impl RedisBannedTokenStore {
+    /// `token_ttl` is used for tokens whose expiry can't be read from the token itself
     pub fn new(conn: Arc<RwLock<Connection>>, token_ttl: u64) -> Self {
-        Self { conn, token_ttl }
+        Self {
+            conn,
+            client: None,
+            token_ttl,
+            banned_count: Arc::default(),
+        }
+    }
+
+    /// Like [`RedisBannedTokenStore::new`], but replaces `conn` with a new connection
+    /// from `client` when it breaks. Stores sharing `conn` get the new connection too.
+    pub fn with_client(conn: Arc<RwLock<Connection>>, client: Client, token_ttl: u64) -> Self {
+        Self {
+            conn,
+            client: Some(client),
+            token_ttl,
+            banned_count: Arc::default(),
+        }
+    }
+
+    async fn ban_token_for(&self, token: &str, ttl: u64) -> Result<(), BannedTokenStoreError> {
+        let key = get_key(token);
+        self.run(|conn| conn.set_ex(&key, true, ttl)).await
+    }
+
+    /// Run `command`, reconnecting and retrying up to `MAX_RECONNECT_ATTEMPTS` times
+    /// if the connection turns out to be broken and a client is available
+    async fn run<T, F>(&self, command: F) -> Result<T, BannedTokenStoreError>
+    where
+        F: Fn(&mut Connection) -> RedisResult<T>,
+    {
+        let mut conn = self.conn.write().await;
+        let mut result = command(&mut conn);
+
+        if let Some(client) = &self.client {
+            for _ in 0..MAX_RECONNECT_ATTEMPTS {
+                match &result {
+                    Err(e) if is_broken_connection(e) => {}
+                    _ => break,
+                }
+
+                tracing::warn!("Redis connection lost, reconnecting");
+                result = client.get_connection().and_then(|new_conn| {
+                    *conn = new_conn;
+                    command(&mut conn)
+                });
+            }
+        }
+
+        result.map_err(|e| BannedTokenStoreError::DatabaseError(e.to_string()))
+    }
+}
+
+fn is_broken_connection(error: &RedisError) -> bool {
+    error.is_connection_dropped() || error.is_connection_refusal() || error.is_io_error()
+}
+
+#[async_trait::async_trait]
+impl ProbeableStore for RedisBannedTokenStore {
+    async fn probe(&self) -> StoreProbe {
+        let start = std::time::Instant::now();
+        let result = self
+            .run(|conn| redis::cmd("PING").query::<String>(conn))
+            .await;
+        if let Err(e) = &result {
+            tracing::warn!("Redis probe failed: {e}");
+        }
+        StoreProbe {
+            healthy: result.is_ok(),
+            latency: start.elapsed(),
+        }
     }
 }
 
 #[async_trait::async_trait]
 impl BannedTokenStore for RedisBannedTokenStore {
     async fn ban_token(&self, token: String) -> Result<(), BannedTokenStoreError> {
-        let key = get_key(&token);
+        // Keep the ban only as long as the token itself would be accepted
+        let ttl = match ban_expiry(&token) {
+            Some(expires_at) => ttl_until(expires_at, Utc::now().timestamp()),
+            None => self.token_ttl,
+        };
 
-        let mut conn = self.conn.write().await;
-        conn.set_ex(key, true, self.token_ttl)
-            .map_err(|e| BannedTokenStoreError::DatabaseError(e.to_string()))
+        self.ban_token_for(&token, ttl).await
+    }
+
+    async fn ban_token_until(
+        &self,
+        token: String,
+        expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        let ttl = ttl_until(expires_at, Utc::now().timestamp());
+        self.ban_token_for(&token, ttl).await
     }
 
     async fn contains_token(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
         let key = get_key(token);
-        let mut conn = self.conn.write().await;
-        conn.exists(&key)
-            .map_err(|e| BannedTokenStoreError::DatabaseError(e.to_string()))
+        self.run(|conn| conn.exists(&key)).await
+    }
+
+    async fn ban_token_if_absent(
+        &self,
+        token: String,
+        expires_at: u64,
+    ) -> Result<bool, BannedTokenStoreError> {
+        let key = get_key(&token);
+        let ttl = ttl_until(expires_at, Utc::now().timestamp());
+
+        // SET NX replies nil when the key exists, so only one caller gets OK
+        let reply: Option<String> = self
+            .run(|conn| {
+                redis::cmd("SET")
+                    .arg(&key)
+                    .arg(true)
+                    .arg("NX")
+                    .arg("EX")
+                    .arg(ttl)
+                    .query(conn)
+            })
+            .await?;
+        Ok(reply.is_some())
+    }
+
+    async fn banned_count(&self) -> Result<usize, BannedTokenStoreError> {
+        // Scanning walks the whole keyspace, so reuse a recent count
+        if let Some((counted_at, count)) = *self.banned_count.lock().unwrap()
+            && counted_at.elapsed() < BANNED_COUNT_CACHE_TTL
+        {
+            return Ok(count);
+        }
+
+        // DBSIZE would include the 2FA codes, so count the ban keys only.
+        // Expired bans are dropped by Redis and never counted.
+        let pattern = format!("{BANNED_TOKEN_KEY_PREFIX}*");
+
+        let count = self
+            .run(|conn| {
+                conn.scan_match::<_, String>(&pattern)
+                    .map(|keys| keys.count())
+            })
+            .await?;
+        *self.banned_count.lock().unwrap() = Some((Instant::now(), count));
+        Ok(count)
+    }
+
+    async fn revoke_user_tokens(
+        &self,
+        user: &UserId,
+        revoked_at: u64,
+        expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        let key = get_user_key(user);
+        let ttl = ttl_until(expires_at, Utc::now().timestamp());
+        self.run(|conn| conn.set_ex(&key, revoked_at, ttl)).await
+    }
+
+    async fn user_tokens_revoked_at(
+        &self,
+        user: &UserId,
+    ) -> Result<Option<u64>, BannedTokenStoreError> {
+        let key = get_user_key(user);
+        self.run(|conn| conn.get(&key)).await
     }
 }
 
 // We are using a key prefix to prevent collisions and organize data!
 const BANNED_TOKEN_KEY_PREFIX: &str = "banned_token:";
+const REVOKED_USER_KEY_PREFIX: &str = "revoked_user:";
+
+fn get_user_key(user: &UserId) -> String {
+    format!("{}{}", REVOKED_USER_KEY_PREFIX, user)
+}
 
 fn get_key(token: &str) -> String {
     format!("{}{}", BANNED_TOKEN_KEY_PREFIX, token)
 }
+
+#[derive(Deserialize)]
+struct ExpiryClaim {
+    exp: u64,
+}
+
+// Read the `exp` claim without verifying the signature. Only used to size the
+// ban entry, never to decide whether a token is valid.
+fn token_expiry(token: &str) -> Option<u64> {
+    jsonwebtoken::dangerous::insecure_decode::<ExpiryClaim>(token)
+        .ok()
+        .map(|data| data.claims.exp)
+}
+
+// Tokens are still accepted for the validation leeway after `exp`, so the ban
+// has to outlive it by as much
+fn ban_expiry(token: &str) -> Option<u64> {
+    token_expiry(token).map(|exp| exp + jsonwebtoken::Validation::default().leeway)
+}
+
+// Seconds until `expires_at`, at least 1 since Redis rejects a zero expiry
+fn ttl_until(expires_at: u64, now: i64) -> u64 {
+    let now = u64::try_from(now).unwrap_or_default();
+    expires_at.saturating_sub(now).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ttl_until_future_expiry() {
+        assert_eq!(ttl_until(1_000, 400), 600);
+    }
+
+    #[test]
+    fn test_ttl_until_past_expiry() {
+        assert_eq!(ttl_until(400, 1_000), 1);
+    }
+
+    #[test]
+    fn test_token_expiry_of_jwt() {
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({ "sub": "test@example.com", "exp": 1_234 }),
+            &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+
+        assert_eq!(token_expiry(&token), Some(1_234));
+    }
+
+    #[test]
+    fn test_ban_expiry_covers_validation_leeway() {
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({ "sub": "test@example.com", "exp": 1_234 }),
+            &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+
+        let leeway = jsonwebtoken::Validation::default().leeway;
+        assert_eq!(ban_expiry(&token), Some(1_234 + leeway));
+    }
+
+    #[test]
+    fn test_token_expiry_of_non_jwt() {
+        assert_eq!(token_expiry("not-a-jwt"), None);
+    }
+}
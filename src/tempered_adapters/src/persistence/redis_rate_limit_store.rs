@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use redis::Connection;
+use tempered_core::{RateLimitStore, RateLimitStoreError};
+use tokio::sync::RwLock;
+
+#[derive(Clone)]
+pub struct RedisRateLimitStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisRateLimitStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    async fn hit(&self, key: &str, window: Duration) -> Result<u64, RateLimitStoreError> {
+        // Redis rejects a zero expiry
+        let window_millis = window.as_millis().max(1) as u64;
+
+        redis::Script::new(HIT_SCRIPT)
+            .key(get_key(key))
+            .arg(window_millis)
+            .invoke(&mut *self.conn.write().await)
+            .map_err(|e| RateLimitStoreError::UnexpectedError(e.to_string()))
+    }
+}
+
+/// Increments the counter and starts the window on the first hit, in a single step
+const HIT_SCRIPT: &str = r#"
+local count = redis.call('INCR', KEYS[1])
+if count == 1 then
+    redis.call('PEXPIRE', KEYS[1], ARGV[1])
+end
+return count
+"#;
+
+const RATE_LIMIT_KEY_PREFIX: &str = "rate_limit:";
+
+fn get_key(key: &str) -> String {
+    format!("{}{}", RATE_LIMIT_KEY_PREFIX, key)
+}
@@ -5,14 +5,103 @@ use secrecy::ExposeSecret;
 use tempered_core::{Email, TwoFaAttemptId, TwoFaCode, TwoFaCodeStore, TwoFaCodeStoreError};
 use tokio::sync::RwLock;
 
+use super::two_fa_code_hasher::TwoFaCodeHasher;
+
 #[derive(Clone)]
 pub struct RedisTwoFaCodeStore {
     client: Arc<RwLock<redis::Connection>>,
+    ttl_in_seconds: u64,
+    max_attempts: Option<usize>,
+    hasher: TwoFaCodeHasher,
+    expired_attempt_grace_in_seconds: u64,
 }
 
 impl RedisTwoFaCodeStore {
     pub fn new(client: Arc<RwLock<redis::Connection>>) -> Self {
-        Self { client }
+        Self::with_ttl(client, TEN_MINUTES_IN_SECONDS)
+    }
+
+    /// Keep stored codes for `ttl_in_seconds` instead of the default ten minutes
+    pub fn with_ttl(client: Arc<RwLock<redis::Connection>>, ttl_in_seconds: u64) -> Self {
+        Self {
+            client,
+            ttl_in_seconds,
+            max_attempts: None,
+            hasher: TwoFaCodeHasher::plaintext(),
+            expired_attempt_grace_in_seconds: 0,
+        }
+    }
+
+    /// Keep at most `max_attempts` pending attempts per user, evicting the oldest
+    /// when a new one is stored. `None` keeps every attempt until it expires.
+    pub fn with_max_attempts(mut self, max_attempts: Option<usize>) -> Self {
+        self.max_attempts = max_attempts.map(|max| max.max(1));
+        self
+    }
+
+    /// Store codes in the form given by `hasher` instead of as they are
+    pub fn with_code_hasher(mut self, hasher: TwoFaCodeHasher) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Remember issued attempts for `grace_in_seconds` after their code expires, so
+    /// they are reported as `AttemptExpired` rather than unknown. Zero disables it.
+    pub fn with_expired_attempt_grace(mut self, grace_in_seconds: u64) -> Self {
+        self.expired_attempt_grace_in_seconds = grace_in_seconds;
+        self
+    }
+
+    /// Error for an attempt key that doesn't exist: the attempt expired within the
+    /// grace period, the user has other attempts, or none at all
+    fn missing_attempt_error(
+        &self,
+        conn: &mut redis::Connection,
+        user_id: &Email,
+        login_attempt_id: &TwoFaAttemptId,
+    ) -> TwoFaCodeStoreError {
+        if self.expired_attempt_grace_in_seconds > 0 {
+            match conn.exists::<_, bool>(issued_key(user_id, &login_attempt_id.to_string())) {
+                Ok(true) => return TwoFaCodeStoreError::AttemptExpired,
+                Ok(false) => {}
+                Err(e) => return TwoFaCodeStoreError::UnexpectedError(e.to_string()),
+            }
+        }
+        match conn.exists::<_, bool>(get_index_key(user_id)) {
+            Ok(true) => TwoFaCodeStoreError::InvalidAttemptId,
+            Ok(false) => TwoFaCodeStoreError::UserNotFound,
+            Err(e) => TwoFaCodeStoreError::UnexpectedError(e.to_string()),
+        }
+    }
+
+    /// Entry stored for an attempt whose code hasn't been used yet
+    fn unused_entry(
+        &self,
+        conn: &mut redis::Connection,
+        user_id: &Email,
+        login_attempt_id: &TwoFaAttemptId,
+    ) -> Result<String, TwoFaCodeStoreError> {
+        let stored: Option<String> = conn
+            .get(get_key(user_id, login_attempt_id))
+            .map_err(|e| TwoFaCodeStoreError::UnexpectedError(e.to_string()))?;
+        let Some(stored) = stored else {
+            return Err(self.missing_attempt_error(conn, user_id, login_attempt_id));
+        };
+
+        if stored.starts_with(USED_MARKER_PREFIX) {
+            return Err(TwoFaCodeStoreError::CodeAlreadyUsed);
+        }
+        Ok(stored)
+    }
+
+    /// Entry stored for an attempt, in the same format for plaintext and hashed codes
+    fn entry(
+        &self,
+        login_attempt_id: &TwoFaAttemptId,
+        two_fa_code: &TwoFaCode,
+    ) -> Result<String, TwoFaCodeStoreError> {
+        serde_json::to_string(&(login_attempt_id, self.hasher.digest(two_fa_code)))
+            .map_err(|e| TwoFaCodeStoreError::UnexpectedError(e.to_string()))
     }
 }
 
@@ -24,15 +113,51 @@ impl TwoFaCodeStore for RedisTwoFaCodeStore {
         login_attempt_id: TwoFaAttemptId,
         two_fa_code: TwoFaCode,
     ) -> Result<(), TwoFaCodeStoreError> {
-        let key = get_key(&user_id);
+        let key = get_key(&user_id, &login_attempt_id);
+        let index_key = get_index_key(&user_id);
+
+        let value = self.entry(&login_attempt_id, &two_fa_code)?;
 
-        let value = serde_json::to_string(&(login_attempt_id, two_fa_code))
+        // One key per attempt, so parallel logins don't replace each other's code
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .set_ex(key, value, self.ttl_in_seconds)
+            .ignore()
+            .rpush(&index_key, login_attempt_id.to_string())
+            .ignore()
+            .expire(&index_key, self.ttl_in_seconds as i64)
+            .ignore();
+        if self.expired_attempt_grace_in_seconds > 0 {
+            pipe.set_ex(
+                issued_key(&user_id, &login_attempt_id.to_string()),
+                1,
+                self.ttl_in_seconds + self.expired_attempt_grace_in_seconds,
+            )
+            .ignore();
+        }
+        // Read the ids beyond the cap before trimming them off the index
+        if let Some(max_attempts) = self.max_attempts {
+            let max_attempts = max_attempts as isize;
+            pipe.lrange(&index_key, 0, -max_attempts - 1)
+                .ltrim(&index_key, -max_attempts, -1)
+                .ignore();
+        }
+
+        let mut conn = self.client.write().await;
+        let evicted: Vec<Vec<String>> = pipe
+            .query(&mut *conn)
             .map_err(|e| TwoFaCodeStoreError::UnexpectedError(e.to_string()))?;
 
-        self.client
-            .write()
-            .await
-            .set_ex(key, value, TEN_MINUTES_IN_SECONDS)
+        // Evicted attempts were replaced rather than expired
+        let evicted_keys: Vec<String> = evicted
+            .into_iter()
+            .flatten()
+            .flat_map(|id| [attempt_key(&user_id, &id), issued_key(&user_id, &id)])
+            .collect();
+        if evicted_keys.is_empty() {
+            return Ok(());
+        }
+        conn.del::<_, ()>(evicted_keys)
             .map_err(|e| TwoFaCodeStoreError::UnexpectedError(e.to_string()))
     }
 
@@ -42,13 +167,11 @@ impl TwoFaCodeStore for RedisTwoFaCodeStore {
         login_attempt_id: &TwoFaAttemptId,
         two_fa_code: &TwoFaCode,
     ) -> Result<(), TwoFaCodeStoreError> {
-        let (stored_login_attempt_id, stored_two_fa_code) =
-            self.get_login_attempt_id_and_two_fa_code(user_id).await?;
+        let mut conn = self.client.write().await;
+        let stored = self.unused_entry(&mut conn, user_id, login_attempt_id)?;
 
-        if stored_login_attempt_id != *login_attempt_id {
-            return Err(TwoFaCodeStoreError::InvalidAttemptId);
-        }
-        if stored_two_fa_code != *two_fa_code {
+        let (_, stored_two_fa_code) = parse_entry(&stored)?;
+        if !self.hasher.matches(two_fa_code, &stored_two_fa_code) {
             return Err(TwoFaCodeStoreError::Invalid2FACode);
         }
 
@@ -59,36 +182,160 @@ impl TwoFaCodeStore for RedisTwoFaCodeStore {
         &self,
         user_id: &Email,
     ) -> Result<(TwoFaAttemptId, TwoFaCode), TwoFaCodeStoreError> {
-        let key = get_key(&user_id);
+        let mut conn = self.client.write().await;
+
+        let latest: Option<String> = conn
+            .lindex(get_index_key(user_id), -1)
+            .map_err(|e| TwoFaCodeStoreError::UnexpectedError(e.to_string()))?;
+        let latest = latest
+            .and_then(|id| TwoFaAttemptId::parse(&id).ok())
+            .ok_or(TwoFaCodeStoreError::UserNotFound)?;
 
-        let json_value: String = self
-            .client
-            .write()
-            .await
-            .get(key)
+        let json_value: String = conn
+            .get(get_key(user_id, &latest))
             .map_err(|_| TwoFaCodeStoreError::UserNotFound)?;
 
-        let (login_attempt_id, two_fa_code): (TwoFaAttemptId, TwoFaCode) =
-            serde_json::from_str(&json_value)
-                .map_err(|e| TwoFaCodeStoreError::UnexpectedError(e.to_string()))?;
+        // A consumed code leaves a marker behind until the entry expires
+        if json_value.starts_with(USED_MARKER_PREFIX) {
+            return Err(TwoFaCodeStoreError::UserNotFound);
+        }
+        if !self.hasher.is_plaintext() {
+            return Err(TwoFaCodeStoreError::UnexpectedError(
+                "2FA codes are stored hashed".to_owned(),
+            ));
+        }
 
-        Ok((login_attempt_id, two_fa_code))
+        let (login_attempt_id, code) = parse_entry(&json_value)?;
+        let code = TwoFaCode::parse(code)
+            .map_err(|e| TwoFaCodeStoreError::UnexpectedError(e.to_string()))?;
+        Ok((login_attempt_id, code))
     }
 
     async fn delete(&self, user_id: &Email) -> Result<(), TwoFaCodeStoreError> {
-        let key = get_key(&user_id);
+        let index_key = get_index_key(user_id);
+        let mut conn = self.client.write().await;
+
+        let attempt_ids: Vec<String> = conn
+            .lrange(&index_key, 0, -1)
+            .map_err(|_| TwoFaCodeStoreError::UserNotFound)?;
 
-        self.client
-            .write()
-            .await
-            .del(key)
+        let mut pipe = redis::pipe();
+        pipe.atomic().del(&index_key).ignore();
+        for id in attempt_ids {
+            pipe.del(attempt_key(user_id, &id))
+                .ignore()
+                .del(issued_key(user_id, &id))
+                .ignore();
+        }
+        pipe.query::<()>(&mut *conn)
             .map_err(|_| TwoFaCodeStoreError::UserNotFound)
     }
+
+    async fn consume(
+        &self,
+        user_id: &Email,
+        login_attempt_id: &TwoFaAttemptId,
+        two_fa_code: &TwoFaCode,
+    ) -> Result<(), TwoFaCodeStoreError> {
+        let mut conn = self.client.write().await;
+        let stored = self.unused_entry(&mut conn, user_id, login_attempt_id)?;
+
+        // Compared here rather than in Redis, where string comparison isn't constant time
+        let (_, stored_two_fa_code) = parse_entry(&stored)?;
+        if !self.hasher.matches(two_fa_code, &stored_two_fa_code) {
+            return Err(TwoFaCodeStoreError::Invalid2FACode);
+        }
+
+        mark_used(&mut conn, user_id, login_attempt_id, &stored)
+    }
+
+    async fn discard_attempt(
+        &self,
+        user_id: &Email,
+        login_attempt_id: &TwoFaAttemptId,
+    ) -> Result<(), TwoFaCodeStoreError> {
+        let mut conn = self.client.write().await;
+        let stored = self.unused_entry(&mut conn, user_id, login_attempt_id)?;
+        mark_used(&mut conn, user_id, login_attempt_id, &stored)
+    }
+}
+
+/// Replace the `stored` entry of an attempt with a used marker, unless a concurrent
+/// verification replaced it first
+fn mark_used(
+    conn: &mut redis::Connection,
+    user_id: &Email,
+    login_attempt_id: &TwoFaAttemptId,
+    stored: &str,
+) -> Result<(), TwoFaCodeStoreError> {
+    let marked: bool = redis::Script::new(MARK_USED_SCRIPT)
+        .key(get_key(user_id, login_attempt_id))
+        .key(get_index_key(user_id))
+        .arg(stored)
+        .arg(format!("{USED_MARKER_PREFIX}{login_attempt_id}"))
+        .arg(login_attempt_id.to_string())
+        .invoke(conn)
+        .map_err(|e| TwoFaCodeStoreError::UnexpectedError(e.to_string()))?;
+
+    if marked {
+        Ok(())
+    } else {
+        Err(TwoFaCodeStoreError::CodeAlreadyUsed)
+    }
 }
 
+fn parse_entry(json_value: &str) -> Result<(TwoFaAttemptId, String), TwoFaCodeStoreError> {
+    serde_json::from_str(json_value)
+        .map_err(|e| TwoFaCodeStoreError::UnexpectedError(e.to_string()))
+}
+
+/// Replaces the entry with a used marker if it is still the one read before, so only
+/// one of several concurrent verifications can succeed, and drops the attempt id from
+/// the user's index (`KEYS[2]`). The entry was already checked against the code, so
+/// this compares the entry with itself and reveals nothing about the code.
+const MARK_USED_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) ~= ARGV[1] then
+    return 0
+end
+redis.call('SET', KEYS[1], ARGV[2], 'KEEPTTL')
+redis.call('LREM', KEYS[2], 0, ARGV[3])
+return 1
+"#;
+const USED_MARKER_PREFIX: &str = "used:";
+
 const TEN_MINUTES_IN_SECONDS: u64 = 600;
 const TWO_FA_CODE_PREFIX: &str = "two_fa_code:";
+const TWO_FA_ATTEMPTS_PREFIX: &str = "two_fa_attempts:";
+const TWO_FA_ISSUED_PREFIX: &str = "two_fa_issued:";
+
+fn get_key(email: &Email, login_attempt_id: &TwoFaAttemptId) -> String {
+    attempt_key(email, &login_attempt_id.to_string())
+}
+
+fn attempt_key(email: &Email, login_attempt_id: &str) -> String {
+    format!(
+        "{}{}:{}",
+        TWO_FA_CODE_PREFIX,
+        email.as_ref().expose_secret(),
+        login_attempt_id
+    )
+}
+
+/// Marker outliving an attempt's code by the expired attempt grace period
+fn issued_key(email: &Email, login_attempt_id: &str) -> String {
+    format!(
+        "{}{}:{}",
+        TWO_FA_ISSUED_PREFIX,
+        email.as_ref().expose_secret(),
+        login_attempt_id
+    )
+}
 
-fn get_key(email: &Email) -> String {
-    format!("{}{}", TWO_FA_CODE_PREFIX, email.as_ref().expose_secret())
+/// List of the user's attempt ids, oldest first
+fn get_index_key(email: &Email) -> String {
+    format!(
+        "{}{}",
+        TWO_FA_ATTEMPTS_PREFIX,
+        email.as_ref().expose_secret()
+    )
 }
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tempered_core::TwoFaCode;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// How a 2FA code store writes codes. By default codes are stored as they are, with a
+/// key only their HMAC-SHA256 is stored, so a leaked store doesn't expose pending codes.
+#[derive(Clone, Default)]
+pub struct TwoFaCodeHasher {
+    key: Option<Arc<[u8]>>,
+}
+
+impl TwoFaCodeHasher {
+    /// Store codes as keyed hashes
+    pub fn new(key: &[u8]) -> Self {
+        Self {
+            key: Some(Arc::from(key)),
+        }
+    }
+
+    /// Store codes as they are
+    pub fn plaintext() -> Self {
+        Self::default()
+    }
+
+    /// Whether stored codes can be read back
+    pub fn is_plaintext(&self) -> bool {
+        self.key.is_none()
+    }
+
+    /// The form in which `code` is stored
+    pub fn digest(&self, code: &TwoFaCode) -> String {
+        match self.mac(code) {
+            Some(mac) => hex::encode(mac.finalize().into_bytes()),
+            None => code.as_str().to_owned(),
+        }
+    }
+
+    /// Whether `stored` is the digest of `code`, compared in constant time
+    pub fn matches(&self, code: &TwoFaCode, stored: &str) -> bool {
+        match self.mac(code) {
+            Some(mac) => hex::decode(stored).is_ok_and(|tag| mac.verify_slice(&tag).is_ok()),
+            None => constant_time_eq(code.as_bytes(), stored.as_bytes()),
+        }
+    }
+
+    fn mac(&self, code: &TwoFaCode) -> Option<HmacSha256> {
+        let key = self.key.as_ref()?;
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(code.as_bytes());
+        Some(mac)
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashed_codes_match_only_their_code() {
+        let hasher = TwoFaCodeHasher::new(b"secret");
+        let code = TwoFaCode::parse("123456".to_string()).unwrap();
+        let other = TwoFaCode::parse("654321".to_string()).unwrap();
+
+        let stored = hasher.digest(&code);
+        assert_ne!(stored, "123456");
+        assert!(hasher.matches(&code, &stored));
+        assert!(!hasher.matches(&other, &stored));
+        assert!(!TwoFaCodeHasher::new(b"other").matches(&code, &stored));
+    }
+
+    #[test]
+    fn test_plaintext_codes_are_stored_as_is() {
+        let hasher = TwoFaCodeHasher::plaintext();
+        let code = TwoFaCode::parse("123456".to_string()).unwrap();
+
+        assert_eq!(hasher.digest(&code), "123456");
+        assert!(hasher.matches(&code, "123456"));
+        assert!(!hasher.matches(&code, "12345"));
+    }
+}
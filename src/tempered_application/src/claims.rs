@@ -0,0 +1,347 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize, ser::SerializeMap};
+use tempered_core::UserId;
+
+/// Operation an elevated token was minted for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ElevationScope {
+    ChangePassword,
+    DeleteAccount,
+    TwoFaSettings,
+}
+
+/// Kind of token, stamped at issuance so one kind can't be presented as the other
+/// when the access and elevated secrets are shared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenType {
+    Access,
+    Elevated,
+}
+
+/// Additional claims embedded in a token next to the registered ones
+pub type ExtraClaims = serde_json::Map<String, serde_json::Value>;
+
+/// Claim naming who a token was issued on behalf of (RFC 8693), `{"act": {"sub": ...}}`
+pub const ACTOR_CLAIM: &str = "act";
+
+/// JWT claims carried by auth and elevated auth tokens
+#[derive(Debug, Deserialize)]
+pub struct Claims {
+    /// Id of the user the token was issued for, their email on tokens issued before
+    /// the id was introduced
+    pub sub: Secret<String>,
+    pub exp: usize,
+    /// Issue time, unset on tokens issued before the claim was introduced
+    #[serde(default)]
+    pub iat: Option<usize>,
+    /// Unique token id bans are keyed by, unset on tokens issued before the claim
+    /// was introduced
+    #[serde(default)]
+    pub jti: Option<String>,
+    /// Unset on tokens issued before the claim was introduced, which are access tokens
+    #[serde(default)]
+    pub token_type: Option<TokenType>,
+    /// Only set on elevated tokens restricted to a single operation
+    #[serde(default)]
+    pub scope: Option<ElevationScope>,
+    /// Any other claims, e.g. a tenant id added by a custom issuer
+    #[serde(flatten)]
+    pub extra: ExtraClaims,
+}
+
+impl Claims {
+    /// The subject claim as a [`UserId`], `None` on tokens whose subject is still the
+    /// user's email
+    pub fn user_id(&self) -> Option<UserId> {
+        UserId::parse(self.sub.expose_secret()).ok()
+    }
+
+    /// Time left until the token expires as seen from `now`, zero once it has expired
+    ///
+    /// Taking the current time as an argument lets callers use their own clock.
+    pub fn remaining_ttl(&self, now: DateTime<Utc>) -> Duration {
+        let remaining = (self.exp as i64).saturating_sub(now.timestamp());
+        Duration::from_secs(remaining.max(0) as u64)
+    }
+
+    /// Whether the token is of `token_type`. Tokens without the claim are only
+    /// accepted as access tokens, so they can't stand in for an elevated token.
+    pub fn is_token_type(&self, token_type: TokenType) -> bool {
+        self.token_type.unwrap_or(TokenType::Access) == token_type
+    }
+
+    /// Whether the token was issued at or before `revoked_at` (unix timestamp in
+    /// seconds). Tokens without an issue time are treated as revoked.
+    pub fn is_revoked_by(&self, revoked_at: u64) -> bool {
+        self.iat.is_none_or(|iat| iat as u64 <= revoked_at)
+    }
+
+    /// Key `token` is banned under: its `jti`, or the encoded token itself when it
+    /// has none
+    pub fn ban_key(&self, token: &str) -> String {
+        self.jti.clone().unwrap_or_else(|| token.to_owned())
+    }
+
+    /// Unix timestamp until which a ban of the token has to be kept: its expiry plus
+    /// the leeway tokens are still accepted for after it
+    pub fn ban_expires_at(&self) -> u64 {
+        ban_expires_at(self.exp as u64)
+    }
+
+    /// Who the token was issued on behalf of, if it was issued by impersonation
+    pub fn impersonated_by(&self) -> Option<&str> {
+        self.extra.get(ACTOR_CLAIM)?.get("sub")?.as_str()
+    }
+
+    /// Whether the token was minted for `scope`. Unscoped tokens allow no operation.
+    pub fn allows(&self, scope: ElevationScope) -> bool {
+        self.scope == Some(scope)
+    }
+}
+
+/// Key an encoded token is banned under, see [`Claims::ban_key`]
+///
+/// The signature is not verified, so only use this on tokens being banned, never
+/// to authenticate a request.
+pub fn token_ban_key(token: &str) -> String {
+    token_ban_entry(token).0
+}
+
+/// Key an encoded token is banned under and, if it can be read, until when the ban
+/// has to be kept, see [`Claims::ban_key`] and [`Claims::ban_expires_at`]
+///
+/// The signature is not verified, so only use this on tokens being banned, never
+/// to authenticate a request.
+pub fn token_ban_entry(token: &str) -> (String, Option<u64>) {
+    #[derive(Deserialize)]
+    struct TokenId {
+        #[serde(default)]
+        jti: Option<String>,
+        #[serde(default)]
+        exp: Option<u64>,
+    }
+
+    let claims = jsonwebtoken::dangerous::insecure_decode::<TokenId>(token)
+        .ok()
+        .map(|data| data.claims);
+    let expires_at = claims
+        .as_ref()
+        .and_then(|claims| claims.exp)
+        .map(ban_expires_at);
+    let key = claims
+        .and_then(|claims| claims.jti)
+        .unwrap_or_else(|| token.to_owned());
+    (key, expires_at)
+}
+
+fn ban_expires_at(exp: u64) -> u64 {
+    exp + jsonwebtoken::Validation::default().leeway
+}
+
+impl Serialize for Claims {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_map(None)?;
+        state.serialize_entry("sub", &self.sub.expose_secret())?;
+        state.serialize_entry("exp", &self.exp)?;
+        if let Some(iat) = &self.iat {
+            state.serialize_entry("iat", iat)?;
+        }
+        if let Some(jti) = &self.jti {
+            state.serialize_entry("jti", jti)?;
+        }
+        if let Some(token_type) = &self.token_type {
+            state.serialize_entry("token_type", token_type)?;
+        }
+        if let Some(scope) = &self.scope {
+            state.serialize_entry("scope", scope)?;
+        }
+        for (key, value) in &self.extra {
+            state.serialize_entry(key, value)?;
+        }
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_id_from_subject() {
+        let user_id = UserId::new();
+        let mut claims = Claims {
+            sub: Secret::from(user_id.to_string()),
+            exp: 0,
+            iat: None,
+            jti: None,
+            token_type: None,
+            scope: None,
+            extra: ExtraClaims::new(),
+        };
+        assert_eq!(claims.user_id(), Some(user_id));
+
+        let json = serde_json::to_value(&claims).unwrap();
+        assert_eq!(json["sub"], user_id.to_string());
+        assert!(json.get("email").is_none());
+
+        // Tokens issued before the subject was the user id carry the email instead
+        claims.sub = Secret::from("test@example.com".to_string());
+        assert_eq!(claims.user_id(), None);
+    }
+
+    #[test]
+    fn test_scope_is_only_serialized_when_set() {
+        let mut claims = Claims {
+            sub: Secret::from("test@example.com".to_string()),
+            exp: 0,
+            iat: None,
+            jti: None,
+            token_type: None,
+            scope: None,
+            extra: ExtraClaims::new(),
+        };
+        let json = serde_json::to_value(&claims).unwrap();
+        assert!(json.get("scope").is_none());
+
+        claims.scope = Some(ElevationScope::DeleteAccount);
+        let json = serde_json::to_value(&claims).unwrap();
+        assert_eq!(json["scope"], "delete_account");
+    }
+
+    #[test]
+    fn test_allows() {
+        let mut claims = Claims {
+            sub: Secret::from("test@example.com".to_string()),
+            exp: 0,
+            iat: None,
+            jti: None,
+            token_type: None,
+            scope: None,
+            extra: ExtraClaims::new(),
+        };
+        assert!(!claims.allows(ElevationScope::ChangePassword));
+        assert!(!claims.allows(ElevationScope::DeleteAccount));
+
+        claims.scope = Some(ElevationScope::ChangePassword);
+        assert!(claims.allows(ElevationScope::ChangePassword));
+        assert!(!claims.allows(ElevationScope::DeleteAccount));
+    }
+
+    #[test]
+    fn test_is_token_type() {
+        let mut claims = Claims {
+            sub: Secret::from("test@example.com".to_string()),
+            exp: 0,
+            iat: None,
+            jti: None,
+            token_type: None,
+            scope: None,
+            extra: ExtraClaims::new(),
+        };
+        assert!(claims.is_token_type(TokenType::Access));
+        assert!(!claims.is_token_type(TokenType::Elevated));
+
+        claims.token_type = Some(TokenType::Elevated);
+        assert!(!claims.is_token_type(TokenType::Access));
+        assert!(claims.is_token_type(TokenType::Elevated));
+        assert_eq!(
+            serde_json::to_value(&claims).unwrap()["token_type"],
+            "elevated"
+        );
+    }
+
+    #[test]
+    fn test_impersonated_by() {
+        let mut claims = Claims {
+            sub: Secret::from("test@example.com".to_string()),
+            exp: 0,
+            iat: None,
+            jti: None,
+            token_type: None,
+            scope: None,
+            extra: ExtraClaims::new(),
+        };
+        assert_eq!(claims.impersonated_by(), None);
+
+        claims.extra.insert(
+            ACTOR_CLAIM.to_owned(),
+            serde_json::json!({ "sub": "support@example.com" }),
+        );
+        assert_eq!(claims.impersonated_by(), Some("support@example.com"));
+    }
+
+    #[test]
+    fn test_remaining_ttl() {
+        let now = DateTime::from_timestamp(1_000, 0).unwrap();
+        let mut claims = Claims {
+            sub: Secret::from("test@example.com".to_string()),
+            exp: 1_600,
+            iat: None,
+            jti: None,
+            token_type: None,
+            scope: None,
+            extra: ExtraClaims::new(),
+        };
+        assert_eq!(claims.remaining_ttl(now), Duration::from_secs(600));
+
+        claims.exp = 900;
+        assert_eq!(claims.remaining_ttl(now), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_ban_key() {
+        let mut claims = Claims {
+            sub: Secret::from("test@example.com".to_string()),
+            exp: 0,
+            iat: None,
+            jti: None,
+            token_type: None,
+            scope: None,
+            extra: ExtraClaims::new(),
+        };
+        let secret = jsonwebtoken::EncodingKey::from_secret(b"test-secret");
+        let header = jsonwebtoken::Header::default();
+
+        let token = jsonwebtoken::encode(&header, &claims, &secret).unwrap();
+        assert_eq!(claims.ban_key(&token), token);
+        assert_eq!(token_ban_key(&token), token);
+
+        claims.jti = Some("token-id".to_owned());
+        let token = jsonwebtoken::encode(&header, &claims, &secret).unwrap();
+        assert_eq!(claims.ban_key(&token), "token-id");
+        assert_eq!(token_ban_key(&token), "token-id");
+        assert_eq!(token_ban_key("not-a-jwt"), "not-a-jwt");
+    }
+
+    #[test]
+    fn test_ban_expires_after_the_leeway() {
+        let claims = Claims {
+            sub: Secret::from("test@example.com".to_string()),
+            exp: 1_600,
+            iat: None,
+            jti: Some("token-id".to_owned()),
+            token_type: None,
+            scope: None,
+            extra: ExtraClaims::new(),
+        };
+        let leeway = jsonwebtoken::Validation::default().leeway;
+        assert_eq!(claims.ban_expires_at(), 1_600 + leeway);
+
+        let secret = jsonwebtoken::EncodingKey::from_secret(b"test-secret");
+        let token =
+            jsonwebtoken::encode(&jsonwebtoken::Header::default(), &claims, &secret).unwrap();
+        assert_eq!(
+            token_ban_entry(&token),
+            ("token-id".to_owned(), Some(1_600 + leeway))
+        );
+        assert_eq!(token_ban_entry("not-a-jwt"), ("not-a-jwt".to_owned(), None));
+    }
+}
@@ -1,3 +1,15 @@
+pub mod claims;
+pub mod password_strength;
+pub mod token_issuer;
+pub mod two_fa_token;
 pub mod use_cases;
 
+pub use claims::{
+    ACTOR_CLAIM, Claims, ElevationScope, ExtraClaims, TokenType, token_ban_entry, token_ban_key,
+};
+pub use password_strength::{DEFAULT_MIN_PASSWORD_SCORE, PasswordStrengthPolicy, WeakPassword};
+pub use token_issuer::{
+    ClaimsEnricher, LocalJwtIssuer, NoopClaimsEnricher, TokenIssuer, TokenIssuerError,
+};
+pub use two_fa_token::{TwoFaTokenClaims, TwoFaTokenError, TwoFaTokenSigner};
 pub use use_cases::*;
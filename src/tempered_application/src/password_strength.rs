@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use secrecy::ExposeSecret;
+use tempered_core::{Email, Password, PasswordStrengthEstimator};
+
+/// Zxcvbn score new passwords need by default, "safely unguessable"
+pub const DEFAULT_MIN_PASSWORD_SCORE: u8 = 3;
+
+/// A password rejected by a [`PasswordStrengthPolicy`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Password is too weak")]
+pub struct WeakPassword {
+    pub score: u8,
+    pub suggestions: Vec<String>,
+}
+
+/// Minimum strength new passwords must have, as judged by a pluggable estimator
+#[derive(Clone)]
+pub struct PasswordStrengthPolicy {
+    estimator: Arc<dyn PasswordStrengthEstimator>,
+    min_score: u8,
+}
+
+impl PasswordStrengthPolicy {
+    /// Reject passwords `estimator` scores below `min_score`
+    pub fn new(estimator: impl PasswordStrengthEstimator + 'static, min_score: u8) -> Self {
+        Self {
+            estimator: Arc::new(estimator),
+            min_score,
+        }
+    }
+
+    /// Check `password` for `email`, whose address and local part count against it
+    pub fn check(&self, password: &Password, email: &Email) -> Result<(), WeakPassword> {
+        let email = email.as_ref().expose_secret();
+        let local_part = email.split('@').next().unwrap_or_default();
+
+        let strength = self.estimator.estimate(password, &[email, local_part]);
+        if strength.score >= self.min_score {
+            return Ok(());
+        }
+        Err(WeakPassword {
+            score: strength.score,
+            suggestions: strength.suggestions,
+        })
+    }
+}
+
+impl std::fmt::Debug for PasswordStrengthPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasswordStrengthPolicy")
+            .field("min_score", &self.min_score)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::Secret;
+    use tempered_core::PasswordStrength;
+
+    use super::*;
+
+    // Scores by length and penalizes passwords containing a user input
+    struct LengthEstimator;
+
+    impl PasswordStrengthEstimator for LengthEstimator {
+        fn estimate(&self, password: &Password, user_inputs: &[&str]) -> PasswordStrength {
+            let password = password.as_ref().expose_secret();
+            if user_inputs.iter().any(|input| password.contains(input)) {
+                return PasswordStrength {
+                    score: 0,
+                    suggestions: vec!["Avoid your email address".to_owned()],
+                };
+            }
+            PasswordStrength {
+                score: (password.len() / 4).min(4) as u8,
+                suggestions: vec!["Add another word or two".to_owned()],
+            }
+        }
+    }
+
+    fn password(value: &str) -> Password {
+        Password::try_from(Secret::from(value.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_check() {
+        let policy = PasswordStrengthPolicy::new(LengthEstimator, DEFAULT_MIN_PASSWORD_SCORE);
+        let email = Email::try_from(Secret::from("alice@example.com".to_string())).unwrap();
+
+        assert!(
+            policy
+                .check(&password("correct horse battery"), &email)
+                .is_ok()
+        );
+        assert_eq!(
+            policy.check(&password("Password1!"), &email),
+            Err(WeakPassword {
+                score: 2,
+                suggestions: vec!["Add another word or two".to_owned()],
+            })
+        );
+        assert_eq!(
+            policy
+                .check(&password("alice-horse-battery"), &email)
+                .unwrap_err()
+                .score,
+            0
+        );
+    }
+}
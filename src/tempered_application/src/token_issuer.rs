@@ -0,0 +1,171 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use jsonwebtoken::{DecodingKey, EncodingKey, Validation, decode, encode};
+use secrecy::Secret;
+use tempered_core::{Email, UserId};
+use uuid::Uuid;
+
+use crate::claims::{Claims, ElevationScope, ExtraClaims, TokenType};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TokenIssuerError {
+    #[error("Token error: {0}")]
+    TokenError(#[from] jsonwebtoken::errors::Error),
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+/// Port trait for issuing and verifying auth tokens
+///
+/// Implement this to sign tokens with an external signer (HSM, KMS) instead of
+/// a locally held secret.
+#[async_trait]
+pub trait TokenIssuer: Send + Sync {
+    /// Issue a token of `token_type` for the user `user_id` that expires after
+    /// `ttl_seconds`, with `user_id` as its subject
+    async fn issue(
+        &self,
+        user_id: &UserId,
+        ttl_seconds: i64,
+        token_type: TokenType,
+        scope: Option<ElevationScope>,
+        extra_claims: ExtraClaims,
+    ) -> Result<String, TokenIssuerError>;
+
+    /// Verify the token's signature and expiry and return its claims
+    async fn verify(&self, token: &str) -> Result<Claims, TokenIssuerError>;
+}
+
+/// Port trait for deriving extra claims for a user when a token is issued,
+/// e.g. a tenant id or feature flags looked up in a database
+#[async_trait]
+pub trait ClaimsEnricher: Send + Sync {
+    async fn enrich(&self, email: &Email) -> ExtraClaims;
+}
+
+/// Claims enricher that adds nothing, used when no enricher is configured
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopClaimsEnricher;
+
+#[async_trait]
+impl ClaimsEnricher for NoopClaimsEnricher {
+    async fn enrich(&self, _email: &Email) -> ExtraClaims {
+        ExtraClaims::new()
+    }
+}
+
+/// Default issuer, signs HS256 JWTs with a shared secret
+#[derive(Clone)]
+pub struct LocalJwtIssuer {
+    secret: Vec<u8>,
+}
+
+impl LocalJwtIssuer {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            secret: secret.to_vec(),
+        }
+    }
+}
+
+#[async_trait]
+impl TokenIssuer for LocalJwtIssuer {
+    async fn issue(
+        &self,
+        user_id: &UserId,
+        ttl_seconds: i64,
+        token_type: TokenType,
+        scope: Option<ElevationScope>,
+        extra_claims: ExtraClaims,
+    ) -> Result<String, TokenIssuerError> {
+        let delta = chrono::Duration::try_seconds(ttl_seconds).ok_or_else(|| {
+            TokenIssuerError::UnexpectedError("Failed to create auth token duration".to_owned())
+        })?;
+
+        let now = Utc::now();
+        let exp = now
+            .checked_add_signed(delta)
+            .ok_or_else(|| TokenIssuerError::UnexpectedError("Duration out of range".to_owned()))?
+            .timestamp();
+
+        let exp: usize = exp.try_into().map_err(|_| {
+            TokenIssuerError::UnexpectedError("Failed to cast i64 to usize".to_owned())
+        })?;
+
+        let claims = Claims {
+            sub: Secret::from(user_id.to_string()),
+            exp,
+            iat: usize::try_from(now.timestamp()).ok(),
+            jti: Some(Uuid::new_v4().to_string()),
+            token_type: Some(token_type),
+            scope,
+            extra: extra_claims,
+        };
+
+        Ok(encode(
+            &jsonwebtoken::Header::default(),
+            &claims,
+            &EncodingKey::from_secret(&self.secret),
+        )?)
+    }
+
+    async fn verify(&self, token: &str) -> Result<Claims, TokenIssuerError> {
+        Ok(decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(&self.secret),
+            &Validation::default(),
+        )?
+        .claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_issue_and_verify_round_trip() {
+        let issuer = LocalJwtIssuer::new(b"test-secret");
+        let mut extra = ExtraClaims::new();
+        extra.insert("tenant".to_owned(), "acme".into());
+
+        let user_id = UserId::new();
+        let token = issuer
+            .issue(
+                &user_id,
+                600,
+                TokenType::Elevated,
+                Some(ElevationScope::DeleteAccount),
+                extra,
+            )
+            .await
+            .unwrap();
+        let claims = issuer.verify(&token).await.unwrap();
+
+        assert_eq!(claims.sub.expose_secret(), &user_id.to_string());
+        assert_eq!(claims.user_id(), Some(user_id));
+        assert!(claims.extra.get("email").is_none());
+        assert_eq!(claims.token_type, Some(TokenType::Elevated));
+        assert_eq!(claims.scope, Some(ElevationScope::DeleteAccount));
+        assert_eq!(claims.extra["tenant"], "acme");
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_other_secret_fails() {
+        let token = LocalJwtIssuer::new(b"test-secret")
+            .issue(
+                &UserId::new(),
+                600,
+                TokenType::Access,
+                None,
+                ExtraClaims::new(),
+            )
+            .await
+            .unwrap();
+
+        let result = LocalJwtIssuer::new(b"another-secret").verify(&token).await;
+        assert!(matches!(result, Err(TokenIssuerError::TokenError(_))));
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use jsonwebtoken::{DecodingKey, EncodingKey, Validation, decode, encode};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize, ser::SerializeStruct};
+use sha2::Sha256;
+use tempered_core::{Email, TwoFaCode};
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Keeps 2FA attempt tokens from ever validating as auth tokens signed with the same secret
+const KEY_DERIVATION_CONTEXT: &[u8] = b"tempered:two-fa-attempt-token";
+
+#[derive(Debug, thiserror::Error)]
+pub enum TwoFaTokenError {
+    #[error("Invalid 2FA token: {0}")]
+    InvalidToken(#[from] jsonwebtoken::errors::Error),
+    #[error("2FA token was issued for another user")]
+    EmailMismatch,
+    #[error("Invalid 2FA code")]
+    InvalidCode,
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+/// Claims of a stateless 2FA attempt token
+///
+/// The token carries an HMAC of the emailed code instead of the code itself,
+/// so it can be handed to the client without a server-side lookup.
+#[derive(Debug, Deserialize)]
+pub struct TwoFaTokenClaims {
+    pub sub: Secret<String>,
+    pub code_hash: String,
+    pub exp: usize,
+}
+
+impl TwoFaTokenClaims {
+    /// Last second at which the token still validates, including the decoding leeway
+    pub fn valid_until(&self) -> u64 {
+        self.exp as u64 + Validation::default().leeway
+    }
+}
+
+impl Serialize for TwoFaTokenClaims {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("TwoFaTokenClaims", 3)?;
+        state.serialize_field("sub", &self.sub.expose_secret())?;
+        state.serialize_field("code_hash", &self.code_hash)?;
+        state.serialize_field("exp", &self.exp)?;
+        state.end()
+    }
+}
+
+/// Issues and verifies signed, short-lived 2FA attempt tokens
+pub struct TwoFaTokenSigner {
+    key: Vec<u8>,
+    time_to_live_in_seconds: i64,
+}
+
+impl TwoFaTokenSigner {
+    /// Create a signer whose key is derived from `secret`
+    ///
+    /// # Arguments
+    /// * `secret` - Base secret, typically the JWT secret
+    /// * `time_to_live_in_seconds` - Lifetime of issued tokens
+    pub fn new(secret: &[u8], time_to_live_in_seconds: i64) -> Self {
+        let key = HmacSha256::new_from_slice(secret)
+            .expect("HMAC accepts keys of any length")
+            .chain_update(KEY_DERIVATION_CONTEXT)
+            .finalize()
+            .into_bytes()
+            .to_vec();
+
+        Self {
+            key,
+            time_to_live_in_seconds,
+        }
+    }
+
+    /// Issue a token binding `email` to the hash of `code`
+    pub fn issue(&self, email: &Email, code: &TwoFaCode) -> Result<String, TwoFaTokenError> {
+        let exp = Utc::now()
+            .checked_add_signed(chrono::Duration::seconds(self.time_to_live_in_seconds))
+            .ok_or_else(|| TwoFaTokenError::UnexpectedError("Duration out of range".to_owned()))?
+            .timestamp();
+
+        let exp: usize = exp.try_into().map_err(|_| {
+            TwoFaTokenError::UnexpectedError("Failed to cast i64 to usize".to_owned())
+        })?;
+
+        let claims = TwoFaTokenClaims {
+            sub: Clone::clone(email.as_ref()),
+            code_hash: hex::encode(self.code_mac(code).finalize().into_bytes()),
+            exp,
+        };
+
+        Ok(encode(
+            &jsonwebtoken::Header::default(),
+            &claims,
+            &EncodingKey::from_secret(&self.key),
+        )?)
+    }
+
+    /// Verify that `token` is valid, belongs to `email` and was issued for `code`
+    pub fn verify(
+        &self,
+        token: &str,
+        email: &Email,
+        code: &TwoFaCode,
+    ) -> Result<TwoFaTokenClaims, TwoFaTokenError> {
+        let claims = self.decode(token, email)?;
+        self.check_code(&claims, code)?;
+        Ok(claims)
+    }
+
+    /// Verify that `token` is valid and belongs to `email`, without checking a code
+    pub fn decode(&self, token: &str, email: &Email) -> Result<TwoFaTokenClaims, TwoFaTokenError> {
+        let claims = decode::<TwoFaTokenClaims>(
+            token,
+            &DecodingKey::from_secret(&self.key),
+            &Validation::default(),
+        )?
+        .claims;
+
+        if claims.sub.expose_secret() != email.as_ref().expose_secret() {
+            return Err(TwoFaTokenError::EmailMismatch);
+        }
+
+        Ok(claims)
+    }
+
+    /// Check that `claims` were issued for `code`
+    pub fn check_code(
+        &self,
+        claims: &TwoFaTokenClaims,
+        code: &TwoFaCode,
+    ) -> Result<(), TwoFaTokenError> {
+        let expected = hex::decode(&claims.code_hash).map_err(|_| TwoFaTokenError::InvalidCode)?;
+
+        // Constant time comparison
+        self.code_mac(code)
+            .verify_slice(&expected)
+            .map_err(|_| TwoFaTokenError::InvalidCode)
+    }
+
+    fn code_mac(&self, code: &TwoFaCode) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.key)
+            .expect("HMAC accepts keys of any length")
+            .chain_update(code.as_bytes())
+    }
+}
@@ -0,0 +1,228 @@
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+use tempered_core::{ApiKey, ApiKeyHash, ApiKeyRecord, ApiKeyStore, ApiKeyStoreError, Email};
+
+/// Error types for the API key use cases
+#[derive(Debug, thiserror::Error)]
+pub enum ApiKeyError {
+    #[error("API key store error: {0}")]
+    ApiKeyStoreError(#[from] ApiKeyStoreError),
+    #[error("Invalid API key")]
+    InvalidApiKey,
+}
+
+/// Hash an API key for storage and lookup
+///
+/// API keys are long random strings, so a fast unsalted hash is sufficient,
+/// unlike passwords.
+pub fn hash_api_key(key: &ApiKey) -> ApiKeyHash {
+    ApiKeyHash::new(hex::encode(Sha256::digest(key.expose_secret().as_bytes())))
+}
+
+/// Issue API key use case - creates a new key for a user
+pub struct IssueApiKeyUseCase<K>
+where
+    K: ApiKeyStore,
+{
+    api_key_store: K,
+}
+
+impl<K> IssueApiKeyUseCase<K>
+where
+    K: ApiKeyStore,
+{
+    pub fn new(api_key_store: K) -> Self {
+        Self { api_key_store }
+    }
+
+    /// Execute the issue API key use case
+    ///
+    /// # Arguments
+    /// * `email` - Owner of the new key
+    /// * `scopes` - Scopes granted to the key
+    ///
+    /// # Returns
+    /// The plaintext key, which is not stored and can't be retrieved again, and its record
+    #[tracing::instrument(name = "IssueApiKeyUseCase::execute", skip(self))]
+    pub async fn execute(
+        &self,
+        email: Email,
+        scopes: Vec<String>,
+    ) -> Result<(ApiKey, ApiKeyRecord), ApiKeyError> {
+        let key = ApiKey::new();
+        let record = ApiKeyRecord {
+            key_hash: hash_api_key(&key),
+            email,
+            scopes,
+        };
+
+        self.api_key_store.add_key(record.clone()).await?;
+
+        Ok((key, record))
+    }
+}
+
+/// Validate API key use case - looks up a presented key by its hash
+pub struct ValidateApiKeyUseCase<K>
+where
+    K: ApiKeyStore,
+{
+    api_key_store: K,
+}
+
+impl<K> ValidateApiKeyUseCase<K>
+where
+    K: ApiKeyStore,
+{
+    pub fn new(api_key_store: K) -> Self {
+        Self { api_key_store }
+    }
+
+    /// Execute the validate API key use case
+    ///
+    /// # Arguments
+    /// * `key` - The plaintext key presented by the client
+    ///
+    /// # Returns
+    /// The key's record, or ApiKeyError::InvalidApiKey if it is unknown or revoked
+    #[tracing::instrument(name = "ValidateApiKeyUseCase::execute", skip_all)]
+    pub async fn execute(&self, key: &ApiKey) -> Result<ApiKeyRecord, ApiKeyError> {
+        match self.api_key_store.get_key(&hash_api_key(key)).await {
+            Ok(record) => Ok(record),
+            Err(ApiKeyStoreError::KeyNotFound) => Err(ApiKeyError::InvalidApiKey),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Revoke API key use case - deletes a key so it can no longer be used
+pub struct RevokeApiKeyUseCase<K>
+where
+    K: ApiKeyStore,
+{
+    api_key_store: K,
+}
+
+impl<K> RevokeApiKeyUseCase<K>
+where
+    K: ApiKeyStore,
+{
+    pub fn new(api_key_store: K) -> Self {
+        Self { api_key_store }
+    }
+
+    /// Execute the revoke API key use case
+    ///
+    /// # Arguments
+    /// * `key_hash` - Hash of the key to revoke, as returned when it was issued
+    ///
+    /// # Returns
+    /// Ok(()) on success, or ApiKeyError::InvalidApiKey if the key doesn't exist
+    #[tracing::instrument(name = "RevokeApiKeyUseCase::execute", skip(self))]
+    pub async fn execute(&self, key_hash: &ApiKeyHash) -> Result<(), ApiKeyError> {
+        match self.api_key_store.delete_key(key_hash).await {
+            Ok(()) => Ok(()),
+            Err(ApiKeyStoreError::KeyNotFound) => Err(ApiKeyError::InvalidApiKey),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::RwLock;
+
+    use super::*;
+    use secrecy::Secret;
+    use std::{collections::HashMap, sync::Arc};
+
+    #[derive(Clone, Default)]
+    struct MockApiKeyStore {
+        keys: Arc<RwLock<HashMap<ApiKeyHash, ApiKeyRecord>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ApiKeyStore for MockApiKeyStore {
+        async fn add_key(&self, record: ApiKeyRecord) -> Result<(), ApiKeyStoreError> {
+            self.keys
+                .write()
+                .await
+                .insert(record.key_hash.clone(), record);
+            Ok(())
+        }
+
+        async fn get_key(&self, key_hash: &ApiKeyHash) -> Result<ApiKeyRecord, ApiKeyStoreError> {
+            self.keys
+                .read()
+                .await
+                .get(key_hash)
+                .cloned()
+                .ok_or(ApiKeyStoreError::KeyNotFound)
+        }
+
+        async fn delete_key(&self, key_hash: &ApiKeyHash) -> Result<(), ApiKeyStoreError> {
+            self.keys
+                .write()
+                .await
+                .remove(key_hash)
+                .map(|_| ())
+                .ok_or(ApiKeyStoreError::KeyNotFound)
+        }
+    }
+
+    fn email() -> Email {
+        Email::try_from(Secret::from("service@example.com".to_string())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_issued_key_is_stored_hashed_and_validates() {
+        let store = MockApiKeyStore::default();
+
+        let (key, record) = IssueApiKeyUseCase::new(store.clone())
+            .execute(email(), vec!["read".to_string()])
+            .await
+            .unwrap();
+
+        let stored = store.keys.read().await;
+        assert!(
+            !stored
+                .keys()
+                .any(|hash| hash.as_str() == key.expose_secret())
+        );
+        drop(stored);
+
+        let validated = ValidateApiKeyUseCase::new(store)
+            .execute(&key)
+            .await
+            .unwrap();
+        assert_eq!(validated, record);
+        assert!(validated.has_scope("read"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_key_is_invalid() {
+        let result = ValidateApiKeyUseCase::new(MockApiKeyStore::default())
+            .execute(&ApiKey::new())
+            .await;
+
+        assert!(matches!(result, Err(ApiKeyError::InvalidApiKey)));
+    }
+
+    #[tokio::test]
+    async fn test_revoked_key_is_invalid() {
+        let store = MockApiKeyStore::default();
+
+        let (key, record) = IssueApiKeyUseCase::new(store.clone())
+            .execute(email(), vec![])
+            .await
+            .unwrap();
+
+        RevokeApiKeyUseCase::new(store.clone())
+            .execute(&record.key_hash)
+            .await
+            .unwrap();
+
+        let result = ValidateApiKeyUseCase::new(store).execute(&key).await;
+        assert!(matches!(result, Err(ApiKeyError::InvalidApiKey)));
+    }
+}
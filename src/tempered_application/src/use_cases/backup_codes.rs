@@ -0,0 +1,140 @@
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+use tempered_core::{BackupCode, BackupCodeHash, BackupCodeStore, BackupCodeStoreError, Email};
+
+/// Number of backup codes generated per user
+pub const BACKUP_CODE_COUNT: usize = 10;
+
+/// Error types for the backup code use cases
+#[derive(Debug, thiserror::Error)]
+pub enum BackupCodeError {
+    #[error("Backup code store error: {0}")]
+    BackupCodeStoreError(#[from] BackupCodeStoreError),
+}
+
+/// Hash a backup code for storage and lookup
+///
+/// Codes are only accepted during a pending 2FA login and rate limited with the
+/// 2FA codes, so a fast unsalted hash is sufficient, unlike passwords.
+pub fn hash_backup_code(code: &BackupCode) -> BackupCodeHash {
+    BackupCodeHash::new(hex::encode(Sha256::digest(code.expose_secret().as_bytes())))
+}
+
+/// Generate backup codes use case - replaces a user's backup codes with a fresh set
+pub struct GenerateBackupCodesUseCase<C>
+where
+    C: BackupCodeStore,
+{
+    backup_code_store: C,
+}
+
+impl<C> GenerateBackupCodesUseCase<C>
+where
+    C: BackupCodeStore,
+{
+    pub fn new(backup_code_store: C) -> Self {
+        Self { backup_code_store }
+    }
+
+    /// Execute the generate backup codes use case
+    ///
+    /// # Arguments
+    /// * `email` - Owner of the codes
+    ///
+    /// # Returns
+    /// The plaintext codes, which are not stored and can't be retrieved again.
+    /// Any codes generated earlier stop working.
+    #[tracing::instrument(name = "GenerateBackupCodesUseCase::execute", skip(self))]
+    pub async fn execute(&self, email: Email) -> Result<Vec<BackupCode>, BackupCodeError> {
+        let codes: Vec<BackupCode> = (0..BACKUP_CODE_COUNT).map(|_| BackupCode::new()).collect();
+
+        self.backup_code_store
+            .replace_codes(&email, codes.iter().map(hash_backup_code).collect())
+            .await?;
+
+        Ok(codes)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use secrecy::Secret;
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+    };
+    use tokio::sync::RwLock;
+
+    #[derive(Clone, Default)]
+    pub(crate) struct MockBackupCodeStore {
+        codes: Arc<RwLock<HashMap<Email, HashSet<BackupCodeHash>>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl BackupCodeStore for MockBackupCodeStore {
+        async fn replace_codes(
+            &self,
+            user: &Email,
+            code_hashes: Vec<BackupCodeHash>,
+        ) -> Result<(), BackupCodeStoreError> {
+            self.codes
+                .write()
+                .await
+                .insert(user.clone(), code_hashes.into_iter().collect());
+            Ok(())
+        }
+
+        async fn consume_code(
+            &self,
+            user: &Email,
+            code_hash: &BackupCodeHash,
+        ) -> Result<(), BackupCodeStoreError> {
+            let mut codes = self.codes.write().await;
+            if codes
+                .get_mut(user)
+                .is_some_and(|codes| codes.remove(code_hash))
+            {
+                Ok(())
+            } else {
+                Err(BackupCodeStoreError::CodeNotFound)
+            }
+        }
+
+        async fn remaining_codes(&self, user: &Email) -> Result<usize, BackupCodeStoreError> {
+            Ok(self.codes.read().await.get(user).map_or(0, HashSet::len))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_replaces_previous_codes() {
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let store = MockBackupCodeStore::default();
+        let use_case = GenerateBackupCodesUseCase::new(store.clone());
+
+        let first = use_case.execute(email.clone()).await.unwrap();
+        assert_eq!(first.len(), BACKUP_CODE_COUNT);
+        assert_eq!(
+            store.remaining_codes(&email).await.unwrap(),
+            BACKUP_CODE_COUNT
+        );
+
+        let second = use_case.execute(email.clone()).await.unwrap();
+        assert_eq!(
+            store.remaining_codes(&email).await.unwrap(),
+            BACKUP_CODE_COUNT
+        );
+        assert_eq!(
+            store
+                .consume_code(&email, &hash_backup_code(&first[0]))
+                .await,
+            Err(BackupCodeStoreError::CodeNotFound)
+        );
+        assert!(
+            store
+                .consume_code(&email, &hash_backup_code(&second[0]))
+                .await
+                .is_ok()
+        );
+    }
+}
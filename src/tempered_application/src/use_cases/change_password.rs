@@ -1,10 +1,18 @@
 use tempered_core::{Email, Password, UserStore, UserStoreError};
 
+use crate::password_strength::{PasswordStrengthPolicy, WeakPassword};
+
 /// Error types for change password use case
 #[derive(Debug, thiserror::Error)]
 pub enum ChangePasswordError {
     #[error("User store error: {0}")]
     UserStoreError(#[from] UserStoreError),
+    #[error("New password must differ from recently used passwords")]
+    PasswordReused,
+    #[error("Current password is incorrect")]
+    IncorrectCurrentPassword,
+    #[error(transparent)]
+    WeakPassword(#[from] WeakPassword),
 }
 
 /// Change password use case - updates user's password
@@ -13,6 +21,7 @@ where
     U: UserStore,
 {
     user_store: U,
+    password_strength_policy: Option<PasswordStrengthPolicy>,
 }
 
 impl<U> ChangePasswordUseCase<U>
@@ -20,11 +29,24 @@ where
     U: UserStore,
 {
     pub fn new(user_store: U) -> Self {
-        Self { user_store }
+        Self {
+            user_store,
+            password_strength_policy: None,
+        }
+    }
+
+    /// Reject new passwords `policy` considers too weak. `None` only enforces the
+    /// minimum length.
+    pub fn with_password_strength_policy(mut self, policy: Option<PasswordStrengthPolicy>) -> Self {
+        self.password_strength_policy = policy;
+        self
     }
 
     /// Execute the change password use case
     ///
+    /// Passwords still in the user's password history are rejected, as are passwords
+    /// the strength policy considers too weak.
+    ///
     /// # Arguments
     /// * `email` - User's email address (from elevated auth token)
     /// * `new_password` - The new password to set
@@ -37,12 +59,60 @@ where
         email: Email,
         new_password: Password,
     ) -> Result<(), ChangePasswordError> {
+        if let Some(policy) = &self.password_strength_policy {
+            policy.check(&new_password, &email)?;
+        }
+        if self
+            .user_store
+            .is_password_reused(&email, &new_password)
+            .await?
+        {
+            return Err(ChangePasswordError::PasswordReused);
+        }
+
         self.user_store
             .set_new_password(&email, new_password)
             .await?;
 
         Ok(())
     }
+
+    /// Execute the change password use case after verifying the user's current password
+    ///
+    /// Guards against a stolen elevated token being enough to take over the account.
+    ///
+    /// # Arguments
+    /// * `email` - User's email address (from elevated auth token)
+    /// * `current_password` - The password the user has now
+    /// * `new_password` - The new password to set
+    ///
+    /// # Returns
+    /// Ok(()) on success, or ChangePasswordError::IncorrectCurrentPassword if
+    /// `current_password` doesn't match
+    #[tracing::instrument(
+        name = "ChangePasswordUseCase::execute_with_current_password",
+        skip(self, current_password, new_password)
+    )]
+    pub async fn execute_with_current_password(
+        &self,
+        email: Email,
+        current_password: Password,
+        new_password: Password,
+    ) -> Result<(), ChangePasswordError> {
+        match self
+            .user_store
+            .authenticate_user(&email, &current_password)
+            .await
+        {
+            Ok(_) => {}
+            Err(UserStoreError::IncorrectPassword) => {
+                return Err(ChangePasswordError::IncorrectCurrentPassword);
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        self.execute(email, new_password).await
+    }
 }
 
 #[cfg(test)]
@@ -82,10 +152,15 @@ mod tests {
 
         async fn authenticate_user(
             &self,
-            _email: &Email,
-            _password: &Password,
+            email: &Email,
+            password: &Password,
         ) -> Result<ValidatedUser, UserStoreError> {
-            unimplemented!()
+            let users = self.users.read().await;
+            match users.get(email.as_ref().expose_secret()) {
+                Some(stored) if stored == password => Ok(ValidatedUser::No2Fa(email.clone())),
+                Some(_) => Err(UserStoreError::IncorrectPassword),
+                None => Err(UserStoreError::UserNotFound),
+            }
         }
 
         async fn get_user(&self, _email: &Email) -> Result<User, UserStoreError> {
@@ -95,6 +170,18 @@ mod tests {
         async fn delete_user(&self, _email: &Email) -> Result<(), UserStoreError> {
             unimplemented!()
         }
+
+        async fn is_password_reused(
+            &self,
+            email: &Email,
+            candidate: &Password,
+        ) -> Result<bool, UserStoreError> {
+            let users = self.users.read().await;
+            let password = users
+                .get(email.as_ref().expose_secret())
+                .ok_or(UserStoreError::UserNotFound)?;
+            Ok(password == candidate)
+        }
     }
 
     #[tokio::test]
@@ -125,6 +212,61 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_change_password_rejects_reuse() {
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("old_password".to_string())).unwrap();
+
+        let mut users = HashMap::new();
+        users.insert("test@example.com".to_string(), password.clone());
+
+        let use_case = ChangePasswordUseCase::new(MockUserStore {
+            users: Arc::new(RwLock::new(users)),
+        });
+
+        let result = use_case.execute(email, password).await;
+        assert!(matches!(result, Err(ChangePasswordError::PasswordReused)));
+    }
+
+    #[tokio::test]
+    async fn test_change_password_with_current_password() {
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let old_password = Password::try_from(Secret::from("old_password".to_string())).unwrap();
+        let new_password = Password::try_from(Secret::from("new_password".to_string())).unwrap();
+
+        let mut users = HashMap::new();
+        users.insert("test@example.com".to_string(), old_password.clone());
+        let user_store = MockUserStore {
+            users: Arc::new(RwLock::new(users)),
+        };
+        let use_case = ChangePasswordUseCase::new(user_store.clone());
+
+        let result = use_case
+            .execute_with_current_password(
+                email.clone(),
+                new_password.clone(),
+                new_password.clone(),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(ChangePasswordError::IncorrectCurrentPassword)
+        ));
+        assert_eq!(
+            user_store.users.read().await["test@example.com"],
+            old_password
+        );
+
+        let result = use_case
+            .execute_with_current_password(email, old_password, new_password.clone())
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(
+            user_store.users.read().await["test@example.com"],
+            new_password
+        );
+    }
+
     #[tokio::test]
     async fn test_change_password_user_not_found() {
         let user_store = MockUserStore {
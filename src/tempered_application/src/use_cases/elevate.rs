@@ -1,12 +1,24 @@
-use tempered_core::{Email, Password, UserStore, UserStoreError};
+use std::time::Duration;
+
+use secrecy::ExposeSecret;
+use tempered_core::{
+    Email, Password, RateLimitStore, RateLimitStoreError, UserStore, UserStoreError,
+};
 
 /// Error types for elevate use case
 #[derive(Debug, thiserror::Error)]
 pub enum ElevateError {
     #[error("User store error: {0}")]
     UserStoreError(#[from] UserStoreError),
+    #[error("Rate limit store error: {0}")]
+    RateLimitStoreError(#[from] RateLimitStoreError),
+    #[error("Elevation attempted again too soon")]
+    TooManyAttempts,
 }
 
+// Shared with verify password, so alternating the endpoints doesn't allow more guesses
+pub(crate) const ELEVATION_RATE_LIMIT_PREFIX: &str = "elevate:";
+
 /// Elevate use case - grants elevated permissions by re-authenticating
 pub struct ElevateUseCase<U>
 where
@@ -38,13 +50,55 @@ where
 
         Ok(email)
     }
+
+    /// Execute the elevate use case, allowing at most one attempt per `min_interval` per user
+    ///
+    /// Every attempt counts, successful or not, so the endpoint can't be used to
+    /// test passwords in quick succession.
+    ///
+    /// # Arguments
+    /// * `email` - User's email address (from existing auth token)
+    /// * `password` - User's password for re-authentication
+    /// * `rate_limit_store` - Store tracking recent elevation attempts
+    /// * `min_interval` - Minimum time between two attempts for the same email
+    ///
+    /// # Returns
+    /// Ok(Email) on successful re-authentication, `TooManyAttempts` if the previous
+    /// attempt was less than `min_interval` ago, or another ElevateError
+    #[tracing::instrument(
+        name = "ElevateUseCase::execute_with_cooldown",
+        skip(self, password, rate_limit_store)
+    )]
+    pub async fn execute_with_cooldown<R>(
+        &self,
+        email: Email,
+        password: Password,
+        rate_limit_store: &R,
+        min_interval: Duration,
+    ) -> Result<Email, ElevateError>
+    where
+        R: RateLimitStore,
+    {
+        let key = format!(
+            "{ELEVATION_RATE_LIMIT_PREFIX}{}",
+            email.as_ref().expose_secret()
+        );
+        if rate_limit_store.hit(&key, min_interval).await? > 1 {
+            return Err(ElevateError::TooManyAttempts);
+        }
+
+        self.execute(email, password).await
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
     use super::*;
-    use secrecy::{ExposeSecret, Secret};
+    use secrecy::Secret;
     use tempered_core::{User, ValidatedUser};
+    use tokio::sync::RwLock;
 
     #[derive(Clone)]
     struct MockUserStore {
@@ -121,4 +175,45 @@ mod tests {
         let result = use_case.execute(email, password).await;
         assert!(result.is_err());
     }
+
+    #[derive(Default)]
+    struct MockRateLimitStore {
+        hits: Arc<RwLock<HashMap<String, u64>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RateLimitStore for MockRateLimitStore {
+        async fn hit(&self, key: &str, _window: Duration) -> Result<u64, RateLimitStoreError> {
+            let mut hits = self.hits.write().await;
+            let count = hits.entry(key.to_owned()).or_default();
+            *count += 1;
+            Ok(*count)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_elevate_again_within_cooldown() {
+        let user_store = MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+        };
+        let use_case = ElevateUseCase::new(user_store);
+        let rate_limit_store = MockRateLimitStore::default();
+        let interval = Duration::from_secs(30);
+
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let wrong = Password::try_from(Secret::from("wrong_password".to_string())).unwrap();
+        let right = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let result = use_case
+            .execute_with_cooldown(email.clone(), wrong, &rate_limit_store, interval)
+            .await;
+        assert!(matches!(result, Err(ElevateError::UserStoreError(_))));
+
+        // Even the correct password is refused until the interval has passed
+        let result = use_case
+            .execute_with_cooldown(email, right, &rate_limit_store, interval)
+            .await;
+        assert!(matches!(result, Err(ElevateError::TooManyAttempts)));
+    }
 }
@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use tempered_core::{AuditEvent, AuditSink, BannedTokenStore, BannedTokenStoreError, User};
+
+/// Error types for force logout use case
+#[derive(Debug, thiserror::Error)]
+pub enum ForceLogoutError {
+    #[error("Banned token store error: {0}")]
+    BannedTokenStoreError(#[from] BannedTokenStoreError),
+}
+
+/// Force logout use case - revokes every token of a user without one of their tokens
+pub struct ForceLogoutUseCase<B, A>
+where
+    B: BannedTokenStore,
+    A: AuditSink,
+{
+    banned_token_store: B,
+    audit_sink: A,
+    max_token_ttl: Duration,
+}
+
+impl<B, A> ForceLogoutUseCase<B, A>
+where
+    B: BannedTokenStore,
+    A: AuditSink,
+{
+    /// Create a force logout use case
+    ///
+    /// # Arguments
+    /// * `banned_token_store` - Store the revocation is recorded in
+    /// * `audit_sink` - Sink the forced logout is recorded with
+    /// * `max_token_ttl` - Longest lifetime of any token type, the revocation is kept
+    ///   until every token issued before it has expired
+    pub fn new(banned_token_store: B, audit_sink: A, max_token_ttl: Duration) -> Self {
+        Self {
+            banned_token_store,
+            audit_sink,
+            max_token_ttl,
+        }
+    }
+
+    /// Execute the force logout use case
+    ///
+    /// # Arguments
+    /// * `user` - User whose tokens are revoked
+    /// * `actor` - Who forced the logout, for the audit event
+    ///
+    /// # Returns
+    /// Ok(()) on success, or ForceLogoutError
+    #[tracing::instrument(name = "ForceLogoutUseCase::execute", skip(self, user))]
+    pub async fn execute(&self, user: &User, actor: String) -> Result<(), ForceLogoutError> {
+        let revoked_at = u64::try_from(Utc::now().timestamp()).unwrap_or_default();
+        // Tokens are accepted for the validation leeway past their expiry
+        let expires_at = revoked_at
+            .saturating_add(self.max_token_ttl.as_secs())
+            .saturating_add(jsonwebtoken::Validation::default().leeway);
+
+        self.banned_token_store
+            .revoke_user_tokens(user.id(), revoked_at, expires_at)
+            .await?;
+
+        self.audit_sink
+            .record(AuditEvent::ForcedLogout {
+                subject: user.email().clone(),
+                actor,
+            })
+            .await;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use secrecy::Secret;
+    use tempered_core::UserId;
+
+    #[derive(Clone, Default)]
+    struct MockBannedTokenStore {
+        revocations: Arc<Mutex<Vec<(UserId, u64, u64)>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl BannedTokenStore for MockBannedTokenStore {
+        async fn ban_token(&self, _token: String) -> Result<(), BannedTokenStoreError> {
+            unimplemented!()
+        }
+
+        async fn contains_token(&self, _token: &str) -> Result<bool, BannedTokenStoreError> {
+            unimplemented!()
+        }
+
+        async fn revoke_user_tokens(
+            &self,
+            user: &UserId,
+            revoked_at: u64,
+            expires_at: u64,
+        ) -> Result<(), BannedTokenStoreError> {
+            self.revocations
+                .lock()
+                .unwrap()
+                .push((*user, revoked_at, expires_at));
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct MockAuditSink {
+        events: Arc<Mutex<Vec<AuditEvent>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AuditSink for MockAuditSink {
+        async fn record(&self, event: AuditEvent) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_force_logout() {
+        let store = MockBannedTokenStore::default();
+        let audit_sink = MockAuditSink::default();
+        let use_case =
+            ForceLogoutUseCase::new(store.clone(), audit_sink.clone(), Duration::from_secs(600));
+        let user = User::parse(
+            Secret::from("test@example.com".to_string()),
+            Secret::from("password123".to_string()),
+            false,
+        )
+        .unwrap();
+
+        use_case
+            .execute(&user, "support@example.com".to_owned())
+            .await
+            .unwrap();
+
+        let revocations = store.revocations.lock().unwrap();
+        let (user_id, revoked_at, expires_at) = &revocations[0];
+        assert_eq!(user_id, user.id());
+        let leeway = jsonwebtoken::Validation::default().leeway;
+        assert_eq!(expires_at - revoked_at, 600 + leeway);
+
+        assert_eq!(
+            *audit_sink.events.lock().unwrap(),
+            [AuditEvent::ForcedLogout {
+                subject: user.email().clone(),
+                actor: "support@example.com".to_owned(),
+            }]
+        );
+    }
+}
@@ -1,8 +1,21 @@
+use std::{ops::Range, time::Duration};
+
+use rand::Rng;
 use tempered_core::{
-    Email, EmailClient, Password, TwoFaAttemptId, TwoFaCode, TwoFaCodeStore, TwoFaCodeStoreError,
-    UserStore, UserStoreError, ValidatedUser,
+    AttemptIdGenerator, Email, EmailClient, EmailKind, Identifier, Password, TwoFaAttemptId,
+    TwoFaCode, TwoFaCodeStore, TwoFaCodeStoreError, User, UserStore, UserStoreError,
+    UuidAttemptIdGenerator, ValidatedUser,
 };
 
+use crate::two_fa_token::{TwoFaTokenError, TwoFaTokenSigner};
+
+/// Subject of the 2FA code email unless overridden with [`LoginUseCase::with_two_fa_subject`]
+pub const DEFAULT_TWO_FA_SUBJECT: &str = "2FA Code";
+
+/// Upper bound on the delay added to failed logins, so a flood of bad
+/// passwords can't hold requests open for long
+pub const MAX_FAILED_AUTH_DELAY: Duration = Duration::from_secs(2);
+
 /// Response from login use case
 #[derive(Debug, PartialEq)]
 pub enum LoginResponse {
@@ -13,6 +26,31 @@ pub enum LoginResponse {
         email: Email,
         attempt_id: TwoFaAttemptId,
     },
+    /// User requires 2FA, return a signed attempt token (stateless mode)
+    Requires2FaToken { email: Email, token: String },
+}
+
+/// User fields returned by [`LoginUseCase::execute_detailed`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserSummary {
+    pub email: Email,
+    pub requires_2fa: bool,
+}
+
+impl From<User> for UserSummary {
+    fn from(user: User) -> Self {
+        Self {
+            email: user.email,
+            requires_2fa: user.requires_2fa,
+        }
+    }
+}
+
+/// Response from a detailed login, the regular response plus the user's summary
+#[derive(Debug, PartialEq)]
+pub struct DetailedLoginResponse {
+    pub response: LoginResponse,
+    pub user: UserSummary,
 }
 
 /// Error types specific to login use case
@@ -22,6 +60,15 @@ pub enum LoginError {
     UserStoreError(#[from] UserStoreError),
     #[error("2FA code store error: {0}")]
     TwoFaCodeStoreError(#[from] TwoFaCodeStoreError),
+    /// The 2FA code was stored but could not be delivered. The attempt is
+    /// still valid, so the client can request a new code for it.
+    #[error("Failed to deliver 2FA code: {reason}")]
+    TwoFaCodeDeliveryFailed {
+        attempt_id: TwoFaAttemptId,
+        reason: String,
+    },
+    #[error("2FA token error: {0}")]
+    TwoFaTokenError(#[from] TwoFaTokenError),
     #[error("Failed to send email: {0}")]
     EmailError(String),
 }
@@ -36,6 +83,9 @@ where
     user_store: U,
     two_fa_code_store: T,
     email_client: E,
+    attempt_id_generator: Box<dyn AttemptIdGenerator>,
+    two_fa_subject: String,
+    failed_auth_delay: Option<Range<Duration>>,
 }
 
 impl<U, T, E> LoginUseCase<U, T, E>
@@ -49,9 +99,38 @@ where
             user_store,
             two_fa_code_store,
             email_client,
+            attempt_id_generator: Box::new(UuidAttemptIdGenerator),
+            two_fa_subject: DEFAULT_TWO_FA_SUBJECT.to_owned(),
+            failed_auth_delay: None,
         }
     }
 
+    /// Sleep for a random duration within `delay` before returning a failed
+    /// authentication, to slow down online guessing and mask timing differences
+    ///
+    /// Successful logins are not delayed. The range is capped at [`MAX_FAILED_AUTH_DELAY`].
+    pub fn with_failed_auth_delay(mut self, delay: Option<Range<Duration>>) -> Self {
+        self.failed_auth_delay = delay.map(|delay| {
+            delay.start.min(MAX_FAILED_AUTH_DELAY)..delay.end.min(MAX_FAILED_AUTH_DELAY)
+        });
+        self
+    }
+
+    /// Send 2FA codes with `subject` instead of [`DEFAULT_TWO_FA_SUBJECT`]
+    pub fn with_two_fa_subject(mut self, subject: impl Into<String>) -> Self {
+        self.two_fa_subject = subject.into();
+        self
+    }
+
+    /// Use `generator` for the IDs of new 2FA login attempts instead of random UUIDs
+    pub fn with_attempt_id_generator(
+        mut self,
+        generator: impl AttemptIdGenerator + 'static,
+    ) -> Self {
+        self.attempt_id_generator = Box::new(generator);
+        self
+    }
+
     /// Execute the login use case
     ///
     /// # Arguments
@@ -66,8 +145,24 @@ where
         email: Email,
         password: Password,
     ) -> Result<LoginResponse, LoginError> {
-        // Authenticate user credentials
-        let validated_user = self.user_store.authenticate_user(&email, &password).await?;
+        self.execute_with_identifier(email.into(), password).await
+    }
+
+    /// Execute the login use case for a user identified by email or username
+    ///
+    /// # Arguments
+    /// * `identifier` - User's email address or username
+    /// * `password` - User's password
+    ///
+    /// # Returns
+    /// LoginResponse indicating whether user needs 2FA or is authenticated
+    #[tracing::instrument(name = "LoginUseCase::execute_with_identifier", skip(self, password))]
+    pub async fn execute_with_identifier(
+        &self,
+        identifier: Identifier,
+        password: Password,
+    ) -> Result<LoginResponse, LoginError> {
+        let validated_user = self.authenticate(&identifier, &password).await?;
 
         match validated_user {
             ValidatedUser::Requires2Fa(email) => self.handle_2fa_required(email).await,
@@ -75,9 +170,160 @@ where
         }
     }
 
+    /// Execute the login use case and return the user's summary with the response
+    ///
+    /// Authenticates exactly like [`Self::execute`], then loads the user so the
+    /// caller can decide on UX without another `get_user` round-trip.
+    ///
+    /// # Arguments
+    /// * `email` - User's email address
+    /// * `password` - User's password
+    ///
+    /// # Returns
+    /// DetailedLoginResponse with the login response and the user's summary
+    #[tracing::instrument(name = "LoginUseCase::execute_detailed", skip(self, password))]
+    pub async fn execute_detailed(
+        &self,
+        email: Email,
+        password: Password,
+    ) -> Result<DetailedLoginResponse, LoginError> {
+        let response = self.execute(email.clone(), password).await?;
+        let user = self.user_store.get_user(&email).await?;
+
+        Ok(DetailedLoginResponse {
+            response,
+            user: user.into(),
+        })
+    }
+
+    /// Execute the login use case without storing the 2FA code
+    ///
+    /// The code is emailed as usual, but instead of an attempt ID the response
+    /// carries a signed token that [`crate::StatelessVerify2FaUseCase`] checks
+    /// the submitted code against.
+    ///
+    /// # Arguments
+    /// * `email` - User's email address
+    /// * `password` - User's password
+    /// * `signer` - Signer used to issue the 2FA attempt token
+    ///
+    /// # Returns
+    /// LoginResponse indicating whether user needs 2FA or is authenticated
+    #[tracing::instrument(name = "LoginUseCase::execute_stateless", skip(self, password, signer))]
+    pub async fn execute_stateless(
+        &self,
+        email: Email,
+        password: Password,
+        signer: &TwoFaTokenSigner,
+    ) -> Result<LoginResponse, LoginError> {
+        self.execute_stateless_with_identifier(email.into(), password, signer)
+            .await
+    }
+
+    /// Execute the stateless login use case for a user identified by email or username
+    ///
+    /// # Arguments
+    /// * `identifier` - User's email address or username
+    /// * `password` - User's password
+    /// * `signer` - Signer used to issue the 2FA attempt token
+    ///
+    /// # Returns
+    /// LoginResponse indicating whether user needs 2FA or is authenticated
+    #[tracing::instrument(
+        name = "LoginUseCase::execute_stateless_with_identifier",
+        skip(self, password, signer)
+    )]
+    pub async fn execute_stateless_with_identifier(
+        &self,
+        identifier: Identifier,
+        password: Password,
+        signer: &TwoFaTokenSigner,
+    ) -> Result<LoginResponse, LoginError> {
+        let validated_user = self.authenticate(&identifier, &password).await?;
+
+        let email = match validated_user {
+            ValidatedUser::Requires2Fa(email) => email,
+            ValidatedUser::No2Fa(email) => return Ok(LoginResponse::Success(email)),
+        };
+
+        let code = TwoFaCode::new();
+        let token = signer.issue(&email, &code)?;
+
+        // Nothing is stored, so there is no attempt to keep if delivery fails
+        self.email_client
+            .send_email_of_kind(
+                EmailKind::TwoFaCode,
+                &email,
+                &self.two_fa_subject,
+                code.as_str(),
+            )
+            .await
+            .map_err(LoginError::EmailError)?;
+
+        Ok(LoginResponse::Requires2FaToken { email, token })
+    }
+
+    /// Replace a pending 2FA attempt with a new one and send its code, e.g. after the
+    /// code of the first attempt could not be delivered
+    ///
+    /// # Arguments
+    /// * `email` - User's email address
+    /// * `login_attempt_id` - The pending attempt to replace
+    ///
+    /// # Returns
+    /// Ok(TwoFaAttemptId) of the new attempt, TwoFaCodeDeliveryFailed if sending failed
+    /// again, or a TwoFaCodeStoreError if the attempt is unknown, used or expired
+    #[tracing::instrument(name = "LoginUseCase::resend_two_fa_code", skip(self))]
+    pub async fn resend_two_fa_code(
+        &self,
+        email: Email,
+        login_attempt_id: &TwoFaAttemptId,
+    ) -> Result<TwoFaAttemptId, LoginError> {
+        // Discarding first means each attempt ID can be resent only once
+        self.two_fa_code_store
+            .discard_attempt(&email, login_attempt_id)
+            .await?;
+
+        self.send_two_fa_code(&email).await
+    }
+
+    /// Authenticate user credentials, delaying failures if configured
+    async fn authenticate(
+        &self,
+        identifier: &Identifier,
+        password: &Password,
+    ) -> Result<ValidatedUser, LoginError> {
+        let result = self
+            .user_store
+            .authenticate_user_by_identifier(identifier, password)
+            .await;
+
+        if let (
+            Err(UserStoreError::IncorrectPassword | UserStoreError::UserNotFound),
+            Some(delay),
+        ) = (&result, &self.failed_auth_delay)
+        {
+            let delay = if delay.is_empty() {
+                delay.start
+            } else {
+                rand::rng().random_range(delay.clone())
+            };
+            tokio::time::sleep(delay).await;
+        }
+
+        Ok(result?)
+    }
+
     /// Handle 2FA required scenario
     async fn handle_2fa_required(&self, email: Email) -> Result<LoginResponse, LoginError> {
-        let login_attempt_id = TwoFaAttemptId::new();
+        let attempt_id = self.send_two_fa_code(&email).await?;
+
+        Ok(LoginResponse::Requires2Fa { email, attempt_id })
+    }
+
+    /// Store a new 2FA attempt for `email` and send its code
+    async fn send_two_fa_code(&self, email: &Email) -> Result<TwoFaAttemptId, LoginError> {
+        let login_attempt_id = self.attempt_id_generator.generate(email);
         let code = TwoFaCode::new();
 
         // Store the 2FA code
@@ -85,16 +331,25 @@ where
             .store_code(email.clone(), login_attempt_id.clone(), code.clone())
             .await?;
 
-        // Send the 2FA code via email
-        self.email_client
-            .send_email(&email, "2FA Code", code.as_str())
+        // Send the 2FA code via email, keeping the attempt if delivery fails
+        if let Err(e) = self
+            .email_client
+            .send_email_of_kind(
+                EmailKind::TwoFaCode,
+                email,
+                &self.two_fa_subject,
+                code.as_str(),
+            )
             .await
-            .map_err(|e| LoginError::EmailError(e.to_string()))?;
+        {
+            tracing::warn!("2FA code delivery failed: {e}");
+            return Err(LoginError::TwoFaCodeDeliveryFailed {
+                attempt_id: login_attempt_id,
+                reason: e,
+            });
+        }
 
-        Ok(LoginResponse::Requires2Fa {
-            email,
-            attempt_id: login_attempt_id,
-        })
+        Ok(login_attempt_id)
     }
 }
 
@@ -102,6 +357,7 @@ where
 mod tests {
     use super::*;
     use secrecy::{ExposeSecret, Secret};
+    use std::sync::{Arc, Mutex};
 
     // Mock implementations for testing
     #[derive(Clone)]
@@ -139,8 +395,16 @@ mod tests {
             }
         }
 
-        async fn get_user(&self, _email: &Email) -> Result<tempered_core::User, UserStoreError> {
-            unimplemented!()
+        async fn get_user(&self, email: &Email) -> Result<tempered_core::User, UserStoreError> {
+            if email.as_ref().expose_secret() != &self.email {
+                return Err(UserStoreError::UserNotFound);
+            }
+            tempered_core::User::parse(
+                Secret::from(self.email.clone()),
+                Secret::from(self.password.clone()),
+                self.requires_2fa,
+            )
+            .map_err(|e| UserStoreError::UnexpectedError(e.to_string()))
         }
 
         async fn delete_user(&self, _user: &Email) -> Result<(), UserStoreError> {
@@ -183,6 +447,59 @@ mod tests {
         }
     }
 
+    #[derive(Clone, Default)]
+    struct RecordingTwoFaCodeStore {
+        stored_attempt_id: Arc<Mutex<Option<TwoFaAttemptId>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TwoFaCodeStore for RecordingTwoFaCodeStore {
+        async fn store_code(
+            &self,
+            _user_id: Email,
+            login_attempt_id: TwoFaAttemptId,
+            _two_fa_code: TwoFaCode,
+        ) -> Result<(), TwoFaCodeStoreError> {
+            *self.stored_attempt_id.lock().unwrap() = Some(login_attempt_id);
+            Ok(())
+        }
+
+        async fn validate(
+            &self,
+            _user_id: &Email,
+            _login_attempt_id: &TwoFaAttemptId,
+            _two_fa_code: &TwoFaCode,
+        ) -> Result<(), TwoFaCodeStoreError> {
+            unimplemented!()
+        }
+
+        async fn get_login_attempt_id_and_two_fa_code(
+            &self,
+            _user_id: &Email,
+        ) -> Result<(TwoFaAttemptId, TwoFaCode), TwoFaCodeStoreError> {
+            unimplemented!()
+        }
+
+        async fn delete(&self, _user_id: &Email) -> Result<(), TwoFaCodeStoreError> {
+            unimplemented!()
+        }
+    }
+
+    #[derive(Clone)]
+    struct FailingEmailClient;
+
+    #[async_trait::async_trait]
+    impl EmailClient for FailingEmailClient {
+        async fn send_email(
+            &self,
+            _recipient: &Email,
+            _subject: &str,
+            _content: &str,
+        ) -> Result<(), String> {
+            Err("email service unavailable".to_string())
+        }
+    }
+
     #[derive(Clone)]
     struct MockEmailClient;
 
@@ -235,4 +552,198 @@ mod tests {
         let result = use_case.execute(email, password).await;
         assert!(matches!(result, Ok(LoginResponse::Requires2Fa { .. })));
     }
+
+    struct NilAttemptIdGenerator;
+
+    impl AttemptIdGenerator for NilAttemptIdGenerator {
+        fn generate(&self, _email: &Email) -> TwoFaAttemptId {
+            TwoFaAttemptId::from(uuid::Uuid::nil())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_with_custom_attempt_id_generator() {
+        let user_store = MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            requires_2fa: true,
+        };
+
+        let use_case = LoginUseCase::new(user_store, MockTwoFaCodeStore, MockEmailClient)
+            .with_attempt_id_generator(NilAttemptIdGenerator);
+
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let result = use_case.execute(email, password).await;
+        match result {
+            Ok(LoginResponse::Requires2Fa { attempt_id, .. }) => {
+                assert_eq!(attempt_id, TwoFaAttemptId::from(uuid::Uuid::nil()))
+            }
+            other => panic!("Expected Requires2Fa, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_with_email_identifier() {
+        let user_store = MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            requires_2fa: false,
+        };
+
+        let use_case = LoginUseCase::new(user_store, MockTwoFaCodeStore, MockEmailClient);
+
+        let identifier = Identifier::parse(Secret::from("test@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let result = use_case.execute_with_identifier(identifier, password).await;
+        assert!(matches!(result, Ok(LoginResponse::Success(_))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failed_auth_delay_only_delays_failures() {
+        let user_store = MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            requires_2fa: false,
+        };
+        let use_case = LoginUseCase::new(user_store, MockTwoFaCodeStore, MockEmailClient)
+            .with_failed_auth_delay(Some(Duration::from_millis(500)..Duration::from_millis(600)));
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+
+        let start = tokio::time::Instant::now();
+        let wrong_password = Password::try_from(Secret::from("wrongpassword".to_string())).unwrap();
+        let result = use_case.execute(email.clone(), wrong_password).await;
+        assert!(matches!(
+            result,
+            Err(LoginError::UserStoreError(
+                UserStoreError::IncorrectPassword
+            ))
+        ));
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(500) && elapsed < Duration::from_millis(600));
+
+        let start = tokio::time::Instant::now();
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+        use_case.execute(email, password).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failed_auth_delay_is_capped() {
+        let user_store = MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            requires_2fa: false,
+        };
+        let use_case = LoginUseCase::new(user_store, MockTwoFaCodeStore, MockEmailClient)
+            .with_failed_auth_delay(Some(Duration::from_secs(60)..Duration::from_secs(120)));
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("wrongpassword".to_string())).unwrap();
+
+        let start = tokio::time::Instant::now();
+        assert!(use_case.execute(email, password).await.is_err());
+        assert_eq!(start.elapsed(), MAX_FAILED_AUTH_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_detailed_login_returns_user_summary() {
+        let user_store = MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            requires_2fa: true,
+        };
+
+        let use_case = LoginUseCase::new(user_store, MockTwoFaCodeStore, MockEmailClient);
+
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let result = use_case
+            .execute_detailed(email.clone(), password)
+            .await
+            .unwrap();
+        assert!(matches!(result.response, LoginResponse::Requires2Fa { .. }));
+        assert_eq!(
+            result.user,
+            UserSummary {
+                email,
+                requires_2fa: true
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detailed_login_with_wrong_password() {
+        let user_store = MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            requires_2fa: false,
+        };
+
+        let use_case = LoginUseCase::new(user_store, MockTwoFaCodeStore, MockEmailClient);
+
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("wrongpassword".to_string())).unwrap();
+
+        let result = use_case.execute_detailed(email, password).await;
+        assert!(matches!(
+            result,
+            Err(LoginError::UserStoreError(
+                UserStoreError::IncorrectPassword
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stateless_login_with_2fa_returns_token() {
+        let user_store = MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            requires_2fa: true,
+        };
+        let two_fa_store = RecordingTwoFaCodeStore::default();
+        let signer = TwoFaTokenSigner::new(b"secret", 600);
+
+        let use_case = LoginUseCase::new(user_store, two_fa_store.clone(), MockEmailClient);
+
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let result = use_case.execute_stateless(email, password, &signer).await;
+
+        assert!(matches!(result, Ok(LoginResponse::Requires2FaToken { .. })));
+        assert!(two_fa_store.stored_attempt_id.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_login_with_2fa_keeps_attempt_when_email_fails() {
+        let user_store = MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            requires_2fa: true,
+        };
+        let two_fa_store = RecordingTwoFaCodeStore::default();
+
+        let use_case = LoginUseCase::new(user_store, two_fa_store.clone(), FailingEmailClient);
+
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let result = use_case.execute(email, password).await;
+        let stored_attempt_id = two_fa_store
+            .stored_attempt_id
+            .lock()
+            .unwrap()
+            .clone()
+            .expect("2FA code should be stored before sending the email");
+
+        match result {
+            Err(LoginError::TwoFaCodeDeliveryFailed { attempt_id, .. }) => {
+                assert_eq!(attempt_id, stored_attempt_id)
+            }
+            other => panic!("Expected TwoFaCodeDeliveryFailed, got {other:?}"),
+        }
+    }
 }
@@ -1,5 +1,7 @@
 use tempered_core::{BannedTokenStore, BannedTokenStoreError};
 
+use crate::claims::token_ban_entry;
+
 /// Error types for logout use case
 #[derive(Debug, thiserror::Error)]
 pub enum LogoutError {
@@ -38,15 +40,29 @@ where
         elevated_token: Option<String>,
     ) -> Result<(), LogoutError> {
         // Ban the main token
-        self.banned_token_store.ban_token(token).await?;
+        self.ban(&token).await?;
 
         // Ban elevated token if present
         if let Some(elevated) = elevated_token {
-            self.banned_token_store.ban_token(elevated).await?;
+            self.ban(&elevated).await?;
         }
 
         Ok(())
     }
+
+    /// Ban `token` until it expires. A jti key says nothing about the expiry, so it
+    /// is passed explicitly rather than left to the store.
+    async fn ban(&self, token: &str) -> Result<(), LogoutError> {
+        match token_ban_entry(token) {
+            (key, Some(expires_at)) => {
+                self.banned_token_store
+                    .ban_token_until(key, expires_at)
+                    .await?
+            }
+            (key, None) => self.banned_token_store.ban_token(key).await?,
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -68,6 +84,18 @@ mod tests {
             Ok(())
         }
 
+        async fn ban_token_until(
+            &self,
+            token: String,
+            expires_at: u64,
+        ) -> Result<(), BannedTokenStoreError> {
+            self.banned_tokens
+                .write()
+                .await
+                .insert(format!("{token}@{expires_at}"));
+            Ok(())
+        }
+
         async fn contains_token(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
             Ok(self.banned_tokens.read().await.contains(token))
         }
@@ -109,4 +137,28 @@ mod tests {
         assert!(store.contains_token(&token).await.unwrap());
         assert!(store.contains_token(&elevated_token).await.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_logout_bans_jti_until_the_token_expires() {
+        let store = MockBannedTokenStore {
+            banned_tokens: Arc::new(RwLock::new(HashSet::new())),
+        };
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({ "sub": "test@example.com", "exp": 1_600, "jti": "token-id" }),
+            &jsonwebtoken::EncodingKey::from_secret(b"test-secret"),
+        )
+        .unwrap();
+
+        let use_case = LogoutUseCase::new(store.clone());
+        use_case.execute(token, None).await.unwrap();
+
+        let leeway = jsonwebtoken::Validation::default().leeway;
+        assert!(
+            store
+                .contains_token(&format!("token-id@{}", 1_600 + leeway))
+                .await
+                .unwrap()
+        );
+    }
 }
@@ -1,16 +1,39 @@
+pub mod api_key;
+pub mod backup_codes;
 pub mod change_password;
 pub mod delete_account;
 pub mod elevate;
+pub mod force_logout;
 pub mod login;
 pub mod logout;
+pub mod profile;
 pub mod signup;
+pub mod two_fa_settings;
 pub mod verify_2fa;
+pub mod verify_password;
+pub mod verify_token;
 
 // Re-export for convenience
+pub use api_key::{
+    ApiKeyError, IssueApiKeyUseCase, RevokeApiKeyUseCase, ValidateApiKeyUseCase, hash_api_key,
+};
+pub use backup_codes::{
+    BACKUP_CODE_COUNT, BackupCodeError, GenerateBackupCodesUseCase, hash_backup_code,
+};
 pub use change_password::{ChangePasswordError, ChangePasswordUseCase};
 pub use delete_account::{DeleteAccountError, DeleteAccountUseCase};
 pub use elevate::{ElevateError, ElevateUseCase};
-pub use login::{LoginError, LoginResponse, LoginUseCase};
+pub use force_logout::{ForceLogoutError, ForceLogoutUseCase};
+pub use login::{
+    DEFAULT_TWO_FA_SUBJECT, DetailedLoginResponse, LoginError, LoginResponse, LoginUseCase,
+    MAX_FAILED_AUTH_DELAY, UserSummary,
+};
 pub use logout::{LogoutError, LogoutUseCase};
-pub use signup::SignupUseCase;
-pub use verify_2fa::{Verify2FaError, Verify2FaUseCase};
+pub use profile::{GetProfileError, GetProfileUseCase};
+pub use signup::{SignupError, SignupOutcome, SignupUseCase};
+pub use two_fa_settings::{TwoFaSettingsError, UpdateTwoFaSettingsUseCase};
+pub use verify_2fa::{
+    StatelessVerify2FaUseCase, Verify2FaError, Verify2FaRateLimit, Verify2FaUseCase,
+};
+pub use verify_password::{VerifyPasswordError, VerifyPasswordUseCase};
+pub use verify_token::{VerifyTokenError, VerifyTokenUseCase};
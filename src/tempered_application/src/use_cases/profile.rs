@@ -0,0 +1,123 @@
+use tempered_core::{Email, UserStore, UserStoreError};
+
+use super::login::UserSummary;
+
+/// Error types for the profile use case
+#[derive(Debug, thiserror::Error)]
+pub enum GetProfileError {
+    /// The token is valid but its user no longer exists, e.g. after account deletion
+    #[error("User no longer exists")]
+    UserNotFound,
+    #[error("User store error: {0}")]
+    UserStoreError(UserStoreError),
+}
+
+impl From<UserStoreError> for GetProfileError {
+    fn from(error: UserStoreError) -> Self {
+        match error {
+            UserStoreError::UserNotFound => GetProfileError::UserNotFound,
+            e => GetProfileError::UserStoreError(e),
+        }
+    }
+}
+
+/// Profile use case - loads the public fields of an authenticated user
+pub struct GetProfileUseCase<U>
+where
+    U: UserStore,
+{
+    user_store: U,
+}
+
+impl<U> GetProfileUseCase<U>
+where
+    U: UserStore,
+{
+    pub fn new(user_store: U) -> Self {
+        Self { user_store }
+    }
+
+    /// Execute the profile use case
+    ///
+    /// # Arguments
+    /// * `email` - User's email address (from a validated auth token)
+    ///
+    /// # Returns
+    /// The user's profile without any credentials, or GetProfileError
+    #[tracing::instrument(name = "GetProfileUseCase::execute", skip(self))]
+    pub async fn execute(&self, email: Email) -> Result<UserSummary, GetProfileError> {
+        let user = self.user_store.get_user(&email).await?;
+        Ok(user.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::Secret;
+    use tempered_core::{Password, User, ValidatedUser};
+
+    struct MockUserStore {
+        user: Option<User>,
+    }
+
+    #[async_trait::async_trait]
+    impl UserStore for MockUserStore {
+        async fn add_user(&self, _user: User) -> Result<(), UserStoreError> {
+            unimplemented!()
+        }
+
+        async fn set_new_password(
+            &self,
+            _email: &Email,
+            _new_password: Password,
+        ) -> Result<(), UserStoreError> {
+            unimplemented!()
+        }
+
+        async fn authenticate_user(
+            &self,
+            _email: &Email,
+            _password: &Password,
+        ) -> Result<ValidatedUser, UserStoreError> {
+            unimplemented!()
+        }
+
+        async fn get_user(&self, _email: &Email) -> Result<User, UserStoreError> {
+            self.user.clone().ok_or(UserStoreError::UserNotFound)
+        }
+
+        async fn delete_user(&self, _email: &Email) -> Result<(), UserStoreError> {
+            unimplemented!()
+        }
+    }
+
+    fn email() -> Email {
+        Email::try_from(Secret::from("test@example.com".to_string())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_profile() {
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+        let use_case = GetProfileUseCase::new(MockUserStore {
+            user: Some(User::new(email(), password, true)),
+        });
+
+        let profile = use_case.execute(email()).await.unwrap();
+        assert_eq!(
+            profile,
+            UserSummary {
+                email: email(),
+                requires_2fa: true
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_profile_of_deleted_user() {
+        let use_case = GetProfileUseCase::new(MockUserStore { user: None });
+
+        let result = use_case.execute(email()).await;
+        assert!(matches!(result, Err(GetProfileError::UserNotFound)));
+    }
+}
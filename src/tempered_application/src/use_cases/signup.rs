@@ -1,11 +1,42 @@
-use tempered_core::{Email, Password, User, UserStore, UserStoreError};
+use tempered_core::{
+    Email, EmailDomainPolicy, NoopRegistrationHook, Password, RegistrationHook, User, UserStore,
+    UserStoreError, Username,
+};
+
+use crate::password_strength::{PasswordStrengthPolicy, WeakPassword};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SignupError {
+    #[error("User store error: {0}")]
+    UserStoreError(#[from] UserStoreError),
+    #[error("Registration hook failed: {0}")]
+    RegistrationHookFailed(String),
+    #[error("disposable email not allowed")]
+    EmailDomainNotAllowed,
+    #[error("Username is already taken")]
+    UsernameTaken,
+    #[error(transparent)]
+    WeakPassword(#[from] WeakPassword),
+}
+
+/// Outcome of a successful signup
+#[derive(Debug, PartialEq)]
+pub enum SignupOutcome {
+    /// User was created and can log in right away
+    Created(Email),
+}
 
 /// Signup use case - handles user registration
-pub struct SignupUseCase<U>
+pub struct SignupUseCase<U, H = NoopRegistrationHook>
 where
     U: UserStore,
+    H: RegistrationHook,
 {
     user_store: U,
+    registration_hook: H,
+    rollback_on_hook_failure: bool,
+    email_domain_policy: EmailDomainPolicy,
+    password_strength_policy: Option<PasswordStrengthPolicy>,
 }
 
 impl<U> SignupUseCase<U>
@@ -13,7 +44,47 @@ where
     U: UserStore,
 {
     pub fn new(user_store: U) -> Self {
-        Self { user_store }
+        Self::with_registration_hook(user_store, NoopRegistrationHook, false)
+    }
+}
+
+impl<U, H> SignupUseCase<U, H>
+where
+    U: UserStore,
+    H: RegistrationHook,
+{
+    /// Create a signup use case that runs `registration_hook` after the user is persisted
+    ///
+    /// # Arguments
+    /// * `user_store` - Store the new user is added to
+    /// * `registration_hook` - Custom logic to run after registration
+    /// * `rollback_on_hook_failure` - Delete the new user again if the hook fails,
+    ///   otherwise the failure is only logged
+    pub fn with_registration_hook(
+        user_store: U,
+        registration_hook: H,
+        rollback_on_hook_failure: bool,
+    ) -> Self {
+        Self {
+            user_store,
+            registration_hook,
+            rollback_on_hook_failure,
+            email_domain_policy: EmailDomainPolicy::default(),
+            password_strength_policy: None,
+        }
+    }
+
+    /// Reject signups whose email domain `policy` doesn't allow
+    pub fn with_email_domain_policy(mut self, policy: EmailDomainPolicy) -> Self {
+        self.email_domain_policy = policy;
+        self
+    }
+
+    /// Reject passwords `policy` considers too weak. `None` only enforces the
+    /// minimum length.
+    pub fn with_password_strength_policy(mut self, policy: Option<PasswordStrengthPolicy>) -> Self {
+        self.password_strength_policy = policy;
+        self
     }
 
     /// Execute the signup use case
@@ -24,17 +95,76 @@ where
     /// * `requires_2fa` - Whether user requires 2FA
     ///
     /// # Returns
-    /// Ok(()) on success, or UserStoreError if user already exists or other error occurs
+    /// SignupOutcome of the created user, or SignupError if the email domain is not allowed, the password
+    /// is too weak, the user already exists, the store fails, or the registration hook fails with rollback enabled
     #[tracing::instrument(name = "SignupUseCase::execute", skip(self, password))]
     pub async fn execute(
         &self,
         email: Email,
         password: Password,
         requires_2fa: bool,
-    ) -> Result<(), UserStoreError> {
-        let user = User::new(email, password, requires_2fa);
+    ) -> Result<SignupOutcome, SignupError> {
+        self.execute_with_username(email, password, requires_2fa, None)
+            .await
+    }
 
-        self.user_store.add_user(user).await
+    /// Execute the signup use case, giving the new user `username` if one is set
+    ///
+    /// # Arguments
+    /// * `email` - Validated email address
+    /// * `password` - Validated password
+    /// * `requires_2fa` - Whether user requires 2FA
+    /// * `username` - Username to log in with instead of the email
+    ///
+    /// # Returns
+    /// Like [`SignupUseCase::execute`], or SignupError::UsernameTaken if another user
+    /// has the username, in which case the new user is removed again
+    #[tracing::instrument(name = "SignupUseCase::execute_with_username", skip(self, password))]
+    pub async fn execute_with_username(
+        &self,
+        email: Email,
+        password: Password,
+        requires_2fa: bool,
+        username: Option<Username>,
+    ) -> Result<SignupOutcome, SignupError> {
+        if !self.email_domain_policy.allows(&email) {
+            return Err(SignupError::EmailDomainNotAllowed);
+        }
+        if let Some(policy) = &self.password_strength_policy {
+            policy.check(&password, &email)?;
+        }
+
+        let user = User::new(email.clone(), password, requires_2fa);
+
+        self.user_store.add_user(user).await?;
+
+        // The store enforces uniqueness, so a taken username is only found out now
+        if let Some(username) = &username
+            && let Err(e) = self.user_store.set_username(&email, username).await
+        {
+            if let Err(delete_error) = self.user_store.delete_user(&email).await {
+                tracing::error!("Failed to roll back registration: {delete_error}");
+            }
+            return Err(match e {
+                UserStoreError::UserAlreadyExists => SignupError::UsernameTaken,
+                e => e.into(),
+            });
+        }
+
+        if let Err(e) = self.registration_hook.after_register(&email).await {
+            if !self.rollback_on_hook_failure {
+                tracing::warn!("Registration hook failed: {e}");
+                return Ok(SignupOutcome::Created(email));
+            }
+
+            tracing::error!("Registration hook failed, rolling back registration: {e}");
+            if let Err(delete_error) = self.user_store.delete_user(&email).await {
+                tracing::error!("Failed to roll back registration: {delete_error}");
+            }
+            return Err(SignupError::RegistrationHookFailed(e));
+        }
+
+        Ok(SignupOutcome::Created(email))
     }
 }
 
@@ -43,12 +173,14 @@ mod tests {
     use super::*;
     use secrecy::{ExposeSecret, Secret};
     use std::sync::Arc;
+    use tempered_core::PasswordStrength;
     use tokio::sync::RwLock;
 
     // Mock user store for testing
     #[derive(Clone)]
     struct MockUserStore {
         users: Arc<RwLock<std::collections::HashMap<String, User>>>,
+        usernames: Arc<RwLock<std::collections::HashSet<Username>>>,
     }
 
     #[async_trait::async_trait]
@@ -83,8 +215,23 @@ mod tests {
             unimplemented!()
         }
 
-        async fn delete_user(&self, _user: &Email) -> Result<(), UserStoreError> {
-            unimplemented!()
+        async fn delete_user(&self, user: &Email) -> Result<(), UserStoreError> {
+            let mut users = self.users.write().await;
+            users
+                .remove(user.as_ref().expose_secret())
+                .ok_or(UserStoreError::UserNotFound)?;
+            Ok(())
+        }
+
+        async fn set_username(
+            &self,
+            _email: &Email,
+            username: &Username,
+        ) -> Result<(), UserStoreError> {
+            if !self.usernames.write().await.insert(username.clone()) {
+                return Err(UserStoreError::UserAlreadyExists);
+            }
+            Ok(())
         }
     }
 
@@ -92,14 +239,15 @@ mod tests {
     async fn test_signup_success() {
         let user_store = MockUserStore {
             users: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            usernames: Arc::default(),
         };
         let use_case = SignupUseCase::new(user_store);
 
         let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
         let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
 
-        let result = use_case.execute(email, password, false).await;
-        assert!(result.is_ok());
+        let result = use_case.execute(email.clone(), password, false).await;
+        assert_eq!(result.unwrap(), SignupOutcome::Created(email));
     }
 
     #[tokio::test]
@@ -112,10 +260,165 @@ mod tests {
 
         let user_store = MockUserStore {
             users: Arc::new(RwLock::new(initial_users)),
+            usernames: Arc::default(),
         };
         let use_case = SignupUseCase::new(user_store);
 
         let result = use_case.execute(email, password, false).await;
-        assert!(matches!(result, Err(UserStoreError::UserAlreadyExists)));
+        assert!(matches!(
+            result,
+            Err(SignupError::UserStoreError(
+                UserStoreError::UserAlreadyExists
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_signup_rejects_denied_email_domain() {
+        let user_store = MockUserStore {
+            users: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            usernames: Arc::default(),
+        };
+        let use_case = SignupUseCase::new(user_store.clone())
+            .with_email_domain_policy(EmailDomainPolicy::new([], ["mailinator.com".to_string()]));
+
+        let email = Email::try_from(Secret::from("test@mailinator.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let result = use_case.execute(email, password, false).await;
+        assert!(matches!(result, Err(SignupError::EmailDomainNotAllowed)));
+        assert!(user_store.users.read().await.is_empty());
+    }
+
+    struct ScoreEstimator(u8);
+
+    impl tempered_core::PasswordStrengthEstimator for ScoreEstimator {
+        fn estimate(&self, _password: &Password, _user_inputs: &[&str]) -> PasswordStrength {
+            PasswordStrength {
+                score: self.0,
+                suggestions: vec!["Add another word or two".to_string()],
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signup_rejects_weak_password() {
+        let user_store = MockUserStore {
+            users: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            usernames: Arc::default(),
+        };
+        let use_case = SignupUseCase::new(user_store.clone())
+            .with_password_strength_policy(Some(PasswordStrengthPolicy::new(ScoreEstimator(1), 3)));
+
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("Password1!".to_string())).unwrap();
+
+        let result = use_case.execute(email, password, false).await;
+        match result {
+            Err(SignupError::WeakPassword(weak)) => {
+                assert_eq!(weak.score, 1);
+                assert_eq!(weak.suggestions, ["Add another word or two"]);
+            }
+            other => panic!("expected WeakPassword, got {other:?}"),
+        }
+        assert!(user_store.users.read().await.is_empty());
+    }
+
+    struct FailingRegistrationHook;
+
+    #[async_trait::async_trait]
+    impl RegistrationHook for FailingRegistrationHook {
+        async fn after_register(&self, _email: &Email) -> Result<(), String> {
+            Err("profile service unavailable".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signup_keeps_user_when_hook_fails() {
+        let user_store = MockUserStore {
+            users: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            usernames: Arc::default(),
+        };
+        let use_case = SignupUseCase::with_registration_hook(
+            user_store.clone(),
+            FailingRegistrationHook,
+            false,
+        );
+
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let result = use_case.execute(email, password, false).await;
+        assert!(result.is_ok());
+        assert!(
+            user_store
+                .users
+                .read()
+                .await
+                .contains_key("test@example.com")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_signup_rolls_back_when_hook_fails() {
+        let user_store = MockUserStore {
+            users: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            usernames: Arc::default(),
+        };
+        let use_case = SignupUseCase::with_registration_hook(
+            user_store.clone(),
+            FailingRegistrationHook,
+            true,
+        );
+
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let result = use_case.execute(email, password, false).await;
+        assert!(matches!(
+            result,
+            Err(SignupError::RegistrationHookFailed(_))
+        ));
+        assert!(user_store.users.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_signup_rejects_username_taken_in_another_case() {
+        let user_store = MockUserStore {
+            users: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            usernames: Arc::default(),
+        };
+        let use_case = SignupUseCase::new(user_store.clone());
+
+        let first = Email::try_from(Secret::from("first@example.com".to_string())).unwrap();
+        let second = Email::try_from(Secret::from("second@example.com".to_string())).unwrap();
+        let password = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let result = use_case
+            .execute_with_username(
+                first.clone(),
+                password.clone(),
+                false,
+                Some(Username::try_from("Bob_1".to_string()).unwrap()),
+            )
+            .await;
+        assert_eq!(result.unwrap(), SignupOutcome::Created(first));
+
+        let result = use_case
+            .execute_with_username(
+                second,
+                password,
+                false,
+                Some(Username::try_from("BOB_1".to_string()).unwrap()),
+            )
+            .await;
+        assert!(matches!(result, Err(SignupError::UsernameTaken)));
+        assert!(
+            !user_store
+                .users
+                .read()
+                .await
+                .contains_key("second@example.com")
+        );
     }
 }
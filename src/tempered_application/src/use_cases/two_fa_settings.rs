@@ -0,0 +1,230 @@
+use tempered_core::{Email, TwoFaCodeStore, TwoFaCodeStoreError, UserStore, UserStoreError};
+
+/// Error types for the 2FA settings use case
+#[derive(Debug, thiserror::Error)]
+pub enum TwoFaSettingsError {
+    #[error("User store error: {0}")]
+    UserStoreError(#[from] UserStoreError),
+    #[error("2FA code store error: {0}")]
+    TwoFaCodeStoreError(#[from] TwoFaCodeStoreError),
+}
+
+/// 2FA settings use case - enables or disables 2FA for a user
+pub struct UpdateTwoFaSettingsUseCase<U, T>
+where
+    U: UserStore,
+    T: TwoFaCodeStore,
+{
+    user_store: U,
+    two_fa_code_store: T,
+}
+
+impl<U, T> UpdateTwoFaSettingsUseCase<U, T>
+where
+    U: UserStore,
+    T: TwoFaCodeStore,
+{
+    pub fn new(user_store: U, two_fa_code_store: T) -> Self {
+        Self {
+            user_store,
+            two_fa_code_store,
+        }
+    }
+
+    /// Execute the 2FA settings use case
+    ///
+    /// Disabling 2FA also discards any pending 2FA code for the user.
+    ///
+    /// # Arguments
+    /// * `email` - User's email address (from elevated auth token)
+    /// * `requires_2fa` - Whether logins should require a 2FA code
+    ///
+    /// # Returns
+    /// Ok(()) on success, or TwoFaSettingsError
+    #[tracing::instrument(name = "UpdateTwoFaSettingsUseCase::execute", skip(self))]
+    pub async fn execute(
+        &self,
+        email: Email,
+        requires_2fa: bool,
+    ) -> Result<(), TwoFaSettingsError> {
+        self.user_store
+            .set_requires_2fa(&email, requires_2fa)
+            .await?;
+
+        if !requires_2fa {
+            match self.two_fa_code_store.delete(&email).await {
+                // No login attempt was pending
+                Ok(()) | Err(TwoFaCodeStoreError::UserNotFound) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+    };
+
+    use super::*;
+    use secrecy::{ExposeSecret, Secret};
+    use tempered_core::{Password, TwoFaAttemptId, TwoFaCode, User, ValidatedUser};
+    use tokio::sync::RwLock;
+
+    #[derive(Clone, Default)]
+    struct MockUserStore {
+        users: Arc<RwLock<HashMap<String, bool>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl UserStore for MockUserStore {
+        async fn add_user(&self, _user: User) -> Result<(), UserStoreError> {
+            unimplemented!()
+        }
+
+        async fn set_new_password(
+            &self,
+            _email: &Email,
+            _new_password: Password,
+        ) -> Result<(), UserStoreError> {
+            unimplemented!()
+        }
+
+        async fn authenticate_user(
+            &self,
+            _email: &Email,
+            _password: &Password,
+        ) -> Result<ValidatedUser, UserStoreError> {
+            unimplemented!()
+        }
+
+        async fn get_user(&self, _email: &Email) -> Result<User, UserStoreError> {
+            unimplemented!()
+        }
+
+        async fn delete_user(&self, _user: &Email) -> Result<(), UserStoreError> {
+            unimplemented!()
+        }
+
+        async fn set_requires_2fa(
+            &self,
+            email: &Email,
+            requires_2fa: bool,
+        ) -> Result<(), UserStoreError> {
+            let mut users = self.users.write().await;
+            let setting = users
+                .get_mut(email.as_ref().expose_secret())
+                .ok_or(UserStoreError::UserNotFound)?;
+            *setting = requires_2fa;
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct MockTwoFaCodeStore {
+        pending: Arc<RwLock<HashSet<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TwoFaCodeStore for MockTwoFaCodeStore {
+        async fn store_code(
+            &self,
+            _user_id: Email,
+            _login_attempt_id: TwoFaAttemptId,
+            _two_fa_code: TwoFaCode,
+        ) -> Result<(), TwoFaCodeStoreError> {
+            unimplemented!()
+        }
+
+        async fn validate(
+            &self,
+            _user_id: &Email,
+            _login_attempt_id: &TwoFaAttemptId,
+            _two_fa_code: &TwoFaCode,
+        ) -> Result<(), TwoFaCodeStoreError> {
+            unimplemented!()
+        }
+
+        async fn get_login_attempt_id_and_two_fa_code(
+            &self,
+            _user_id: &Email,
+        ) -> Result<(TwoFaAttemptId, TwoFaCode), TwoFaCodeStoreError> {
+            unimplemented!()
+        }
+
+        async fn delete(&self, user_id: &Email) -> Result<(), TwoFaCodeStoreError> {
+            if self
+                .pending
+                .write()
+                .await
+                .remove(user_id.as_ref().expose_secret())
+            {
+                Ok(())
+            } else {
+                Err(TwoFaCodeStoreError::UserNotFound)
+            }
+        }
+    }
+
+    fn email() -> Email {
+        Email::try_from(Secret::from("test@example.com".to_string())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_disable_2fa_discards_pending_code() {
+        let user_store = MockUserStore::default();
+        user_store
+            .users
+            .write()
+            .await
+            .insert("test@example.com".to_string(), true);
+        let two_fa_store = MockTwoFaCodeStore::default();
+        two_fa_store
+            .pending
+            .write()
+            .await
+            .insert("test@example.com".to_string());
+
+        let use_case = UpdateTwoFaSettingsUseCase::new(user_store.clone(), two_fa_store.clone());
+        use_case.execute(email(), false).await.unwrap();
+
+        assert!(!user_store.users.read().await["test@example.com"]);
+        assert!(two_fa_store.pending.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_enable_2fa() {
+        let user_store = MockUserStore::default();
+        user_store
+            .users
+            .write()
+            .await
+            .insert("test@example.com".to_string(), false);
+
+        let use_case =
+            UpdateTwoFaSettingsUseCase::new(user_store.clone(), MockTwoFaCodeStore::default());
+        use_case.execute(email(), true).await.unwrap();
+
+        assert!(user_store.users.read().await["test@example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_update_missing_user() {
+        let use_case = UpdateTwoFaSettingsUseCase::new(
+            MockUserStore::default(),
+            MockTwoFaCodeStore::default(),
+        );
+
+        let result = use_case.execute(email(), true).await;
+        assert!(matches!(
+            result,
+            Err(TwoFaSettingsError::UserStoreError(
+                UserStoreError::UserNotFound
+            ))
+        ));
+    }
+}
@@ -1,7 +1,15 @@
+use std::{net::IpAddr, time::Duration};
+
+use secrecy::ExposeSecret;
 use tempered_core::{
-    Email, TwoFaAttemptId, TwoFaCode, TwoFaCodeStore, TwoFaCodeStoreError, TwoFaError,
+    BackupCode, BackupCodeStore, BackupCodeStoreError, BannedTokenStore, BannedTokenStoreError,
+    Email, RateLimitStore, RateLimitStoreError, TwoFaAttemptId, TwoFaCode, TwoFaCodeStore,
+    TwoFaCodeStoreError, TwoFaError,
 };
 
+use crate::two_fa_token::{TwoFaTokenError, TwoFaTokenSigner};
+use crate::use_cases::backup_codes::hash_backup_code;
+
 /// Error types for verify 2FA use case
 #[derive(Debug, thiserror::Error)]
 pub enum Verify2FaError {
@@ -13,6 +21,83 @@ pub enum Verify2FaError {
     InvalidLoginAttemptId,
     #[error("Invalid 2FA code")]
     InvalidTwoFaCode,
+    #[error("2FA code has already been used")]
+    CodeAlreadyUsed,
+    #[error("2FA attempt has expired")]
+    AttemptExpired,
+    #[error("Banned token store error: {0}")]
+    BannedTokenStoreError(#[from] BannedTokenStoreError),
+    #[error("2FA token error: {0}")]
+    TwoFaTokenError(String),
+    #[error("Rate limit store error: {0}")]
+    RateLimitStoreError(#[from] RateLimitStoreError),
+    #[error("Too many 2FA verification attempts")]
+    TooManyAttempts,
+    #[error("Backup code store error: {0}")]
+    BackupCodeStoreError(#[from] BackupCodeStoreError),
+}
+
+const VERIFY_2FA_RATE_LIMIT_PREFIX: &str = "verify_2fa:";
+const STATELESS_2FA_FAILURES_PREFIX: &str = "stateless_2fa_failures:";
+
+/// Limit on 2FA verification attempts per window, counted across all login attempts
+/// so guesses can't be spread over many fresh logins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Verify2FaRateLimit {
+    pub max_attempts: u64,
+    pub window: Duration,
+}
+
+impl Verify2FaRateLimit {
+    /// Count a verification attempt for `email` and, if known, the client's `ip`
+    ///
+    /// Every attempt counts, successful or not.
+    ///
+    /// # Returns
+    /// Ok(()) if the attempt is allowed, `TooManyAttempts` once either key exceeds
+    /// `max_attempts` within the window
+    pub async fn check<R>(
+        &self,
+        rate_limit_store: &R,
+        email: &Email,
+        ip: Option<IpAddr>,
+    ) -> Result<(), Verify2FaError>
+    where
+        R: RateLimitStore,
+    {
+        let email_key = format!(
+            "{VERIFY_2FA_RATE_LIMIT_PREFIX}email:{}",
+            email.as_ref().expose_secret()
+        );
+        let ip_key = ip.map(|ip| format!("{VERIFY_2FA_RATE_LIMIT_PREFIX}ip:{ip}"));
+
+        let mut limited = false;
+        for key in std::iter::once(email_key).chain(ip_key) {
+            limited |= rate_limit_store.hit(&key, self.window).await? > self.max_attempts;
+        }
+
+        if limited {
+            return Err(Verify2FaError::TooManyAttempts);
+        }
+        Ok(())
+    }
+}
+
+impl From<TwoFaTokenError> for Verify2FaError {
+    fn from(error: TwoFaTokenError) -> Self {
+        match error {
+            TwoFaTokenError::InvalidToken(e)
+                if *e.kind() == jsonwebtoken::errors::ErrorKind::ExpiredSignature =>
+            {
+                Verify2FaError::AttemptExpired
+            }
+            TwoFaTokenError::InvalidToken(_) | TwoFaTokenError::EmailMismatch => {
+                Verify2FaError::InvalidLoginAttemptId
+            }
+            TwoFaTokenError::InvalidCode => Verify2FaError::InvalidTwoFaCode,
+            TwoFaTokenError::UnexpectedError(e) => Verify2FaError::TwoFaTokenError(e),
+        }
+    }
 }
 
 /// Verify 2FA use case - validates 2FA code and login attempt
@@ -47,24 +132,181 @@ where
         login_attempt_id: TwoFaAttemptId,
         two_fa_code: TwoFaCode,
     ) -> Result<Email, Verify2FaError> {
-        // Get stored attempt ID and code
-        let (stored_attempt_id, stored_two_fa_code) = self
-            .two_fa_code_store
-            .get_login_attempt_id_and_two_fa_code(&email)
-            .await?;
-
-        // Verify attempt ID matches
-        if stored_attempt_id != login_attempt_id {
-            return Err(Verify2FaError::InvalidLoginAttemptId);
-        }
+        // Validate and delete in one step, so concurrent submissions can't both succeed
+        self.two_fa_code_store
+            .consume(&email, &login_attempt_id, &two_fa_code)
+            .await
+            .map_err(|e| match e {
+                TwoFaCodeStoreError::InvalidAttemptId => Verify2FaError::InvalidLoginAttemptId,
+                TwoFaCodeStoreError::Invalid2FACode => Verify2FaError::InvalidTwoFaCode,
+                TwoFaCodeStoreError::CodeAlreadyUsed => Verify2FaError::CodeAlreadyUsed,
+                TwoFaCodeStoreError::AttemptExpired => Verify2FaError::AttemptExpired,
+                e => Verify2FaError::TwoFaCodeStoreError(e),
+            })?;
 
-        // Verify 2FA code matches
-        if stored_two_fa_code != two_fa_code {
-            return Err(Verify2FaError::InvalidTwoFaCode);
+        Ok(email)
+    }
+
+    /// Execute the verify 2FA use case with a backup code in place of the emailed code
+    ///
+    /// The login attempt is used up before the backup code is checked, so a wrong
+    /// backup code ends the attempt and codes can't be guessed against one login.
+    ///
+    /// # Arguments
+    /// * `email` - User's email address
+    /// * `login_attempt_id` - The login attempt ID from login response
+    /// * `backup_code` - One of the user's backup codes, consumed on success
+    /// * `backup_code_store` - Store holding the user's backup codes
+    ///
+    /// # Returns
+    /// Ok(Email) on successful verification, or Verify2FaError
+    #[tracing::instrument(
+        name = "Verify2FaUseCase::execute_with_backup_code",
+        skip(self, backup_code, backup_code_store)
+    )]
+    pub async fn execute_with_backup_code<C>(
+        &self,
+        email: Email,
+        login_attempt_id: TwoFaAttemptId,
+        backup_code: BackupCode,
+        backup_code_store: &C,
+    ) -> Result<Email, Verify2FaError>
+    where
+        C: BackupCodeStore + ?Sized,
+    {
+        self.two_fa_code_store
+            .discard_attempt(&email, &login_attempt_id)
+            .await
+            .map_err(|e| match e {
+                TwoFaCodeStoreError::UserNotFound | TwoFaCodeStoreError::InvalidAttemptId => {
+                    Verify2FaError::InvalidLoginAttemptId
+                }
+                TwoFaCodeStoreError::CodeAlreadyUsed => Verify2FaError::CodeAlreadyUsed,
+                TwoFaCodeStoreError::AttemptExpired => Verify2FaError::AttemptExpired,
+                e => Verify2FaError::TwoFaCodeStoreError(e),
+            })?;
+
+        backup_code_store
+            .consume_code(&email, &hash_backup_code(&backup_code))
+            .await
+            .map_err(|e| match e {
+                BackupCodeStoreError::CodeNotFound => Verify2FaError::InvalidTwoFaCode,
+                e => Verify2FaError::BackupCodeStoreError(e),
+            })?;
+
+        Ok(email)
+    }
+}
+
+/// Verify 2FA use case for stateless mode - checks the code against a signed attempt token
+///
+/// Tokens are single use: a verified token is added to the banned token store.
+/// With [`StatelessVerify2FaUseCase::execute_with_failure_limit`] a token is also
+/// banned after too many wrong codes.
+pub struct StatelessVerify2FaUseCase<B>
+where
+    B: BannedTokenStore,
+{
+    banned_token_store: B,
+}
+
+impl<B> StatelessVerify2FaUseCase<B>
+where
+    B: BannedTokenStore,
+{
+    pub fn new(banned_token_store: B) -> Self {
+        Self { banned_token_store }
+    }
+
+    /// Execute the stateless verify 2FA use case
+    ///
+    /// # Arguments
+    /// * `email` - User's email address
+    /// * `token` - The 2FA attempt token from the login response
+    /// * `two_fa_code` - The 2FA code received via email
+    /// * `signer` - Signer the token was issued with
+    ///
+    /// # Returns
+    /// Ok(Email) on successful verification, or Verify2FaError
+    #[tracing::instrument(
+        name = "StatelessVerify2FaUseCase::execute",
+        skip(self, token, two_fa_code, signer)
+    )]
+    pub async fn execute(
+        &self,
+        email: Email,
+        token: String,
+        two_fa_code: TwoFaCode,
+        signer: &TwoFaTokenSigner,
+    ) -> Result<Email, Verify2FaError> {
+        let claims = signer.verify(&token, &email, &two_fa_code)?;
+        self.redeem(email, token, claims.valid_until()).await
+    }
+
+    /// Execute the stateless verify 2FA use case, banning the token after
+    /// `max_failed_codes` wrong codes
+    ///
+    /// # Arguments
+    /// * `email` - User's email address
+    /// * `token` - The 2FA attempt token from the login response
+    /// * `two_fa_code` - The 2FA code received via email
+    /// * `signer` - Signer the token was issued with
+    /// * `rate_limit_store` - Store counting wrong codes per token
+    /// * `max_failed_codes` - Wrong codes allowed before the token is banned
+    ///
+    /// # Returns
+    /// Ok(Email) on successful verification, or Verify2FaError
+    #[tracing::instrument(
+        name = "StatelessVerify2FaUseCase::execute_with_failure_limit",
+        skip(self, token, two_fa_code, signer, rate_limit_store)
+    )]
+    pub async fn execute_with_failure_limit<R>(
+        &self,
+        email: Email,
+        token: String,
+        two_fa_code: TwoFaCode,
+        signer: &TwoFaTokenSigner,
+        rate_limit_store: &R,
+        max_failed_codes: u64,
+    ) -> Result<Email, Verify2FaError>
+    where
+        R: RateLimitStore,
+    {
+        let claims = signer.decode(&token, &email)?;
+        let valid_until = claims.valid_until();
+
+        if let Err(e) = signer.check_code(&claims, &two_fa_code) {
+            // Count for as long as the token can still be used
+            let now = chrono::Utc::now().timestamp().max(0) as u64;
+            let window = Duration::from_secs(valid_until.saturating_sub(now).max(1));
+            let key = format!("{STATELESS_2FA_FAILURES_PREFIX}{token}");
+
+            if rate_limit_store.hit(&key, window).await? >= max_failed_codes {
+                self.banned_token_store
+                    .ban_token_until(token, valid_until)
+                    .await?;
+            }
+            return Err(e.into());
         }
 
-        // Delete the used code
-        self.two_fa_code_store.delete(&email).await?;
+        self.redeem(email, token, valid_until).await
+    }
+
+    async fn redeem(
+        &self,
+        email: Email,
+        token: String,
+        valid_until: u64,
+    ) -> Result<Email, Verify2FaError> {
+        // Checked and banned in one step so concurrent requests can't both redeem it.
+        // A used token is treated like an unknown attempt.
+        if !self
+            .banned_token_store
+            .ban_token_if_absent(token, valid_until)
+            .await?
+        {
+            return Err(Verify2FaError::InvalidLoginAttemptId);
+        }
 
         Ok(email)
     }
@@ -74,6 +316,33 @@ where
 mod tests {
     use super::*;
     use secrecy::{ExposeSecret, Secret};
+    use std::{collections::HashSet, sync::Arc};
+    use tokio::sync::RwLock;
+
+    #[derive(Clone, Default)]
+    struct MockBannedTokenStore {
+        banned_tokens: Arc<RwLock<HashSet<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl BannedTokenStore for MockBannedTokenStore {
+        async fn ban_token(&self, token: String) -> Result<(), BannedTokenStoreError> {
+            self.banned_tokens.write().await.insert(token);
+            Ok(())
+        }
+
+        async fn contains_token(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
+            Ok(self.banned_tokens.read().await.contains(token))
+        }
+
+        async fn ban_token_if_absent(
+            &self,
+            token: String,
+            _expires_at: u64,
+        ) -> Result<bool, BannedTokenStoreError> {
+            Ok(self.banned_tokens.write().await.insert(token))
+        }
+    }
 
     #[derive(Clone)]
     struct MockTwoFaCodeStore {
@@ -82,6 +351,46 @@ mod tests {
         code: TwoFaCode,
     }
 
+    /// Simulates losing a race: the code is still readable, but already deleted
+    #[derive(Clone)]
+    struct ConsumedTwoFaCodeStore(MockTwoFaCodeStore);
+
+    #[async_trait::async_trait]
+    impl TwoFaCodeStore for ConsumedTwoFaCodeStore {
+        async fn store_code(
+            &self,
+            user_id: Email,
+            login_attempt_id: TwoFaAttemptId,
+            two_fa_code: TwoFaCode,
+        ) -> Result<(), TwoFaCodeStoreError> {
+            self.0
+                .store_code(user_id, login_attempt_id, two_fa_code)
+                .await
+        }
+
+        async fn validate(
+            &self,
+            user_id: &Email,
+            login_attempt_id: &TwoFaAttemptId,
+            two_fa_code: &TwoFaCode,
+        ) -> Result<(), TwoFaCodeStoreError> {
+            self.0
+                .validate(user_id, login_attempt_id, two_fa_code)
+                .await
+        }
+
+        async fn get_login_attempt_id_and_two_fa_code(
+            &self,
+            user_id: &Email,
+        ) -> Result<(TwoFaAttemptId, TwoFaCode), TwoFaCodeStoreError> {
+            self.0.get_login_attempt_id_and_two_fa_code(user_id).await
+        }
+
+        async fn delete(&self, _user_id: &Email) -> Result<(), TwoFaCodeStoreError> {
+            Err(TwoFaCodeStoreError::UserNotFound)
+        }
+    }
+
     #[async_trait::async_trait]
     impl TwoFaCodeStore for MockTwoFaCodeStore {
         async fn store_code(
@@ -155,4 +464,186 @@ mod tests {
 
         assert!(matches!(result, Err(Verify2FaError::InvalidTwoFaCode)));
     }
+
+    #[tokio::test]
+    async fn test_verify_2fa_code_already_used() {
+        let attempt_id = TwoFaAttemptId::new();
+        let code = TwoFaCode::new();
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+
+        let store = ConsumedTwoFaCodeStore(MockTwoFaCodeStore {
+            email: "test@example.com".to_string(),
+            attempt_id: attempt_id.clone(),
+            code: code.clone(),
+        });
+
+        let use_case = Verify2FaUseCase::new(store);
+        let result = use_case.execute(email, attempt_id, code).await;
+
+        assert!(matches!(result, Err(Verify2FaError::CodeAlreadyUsed)));
+    }
+
+    #[tokio::test]
+    async fn test_verify_2fa_with_backup_code_is_single_use() {
+        use crate::use_cases::backup_codes::{
+            GenerateBackupCodesUseCase, tests::MockBackupCodeStore,
+        };
+
+        let attempt_id = TwoFaAttemptId::new();
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let store = MockTwoFaCodeStore {
+            email: "test@example.com".to_string(),
+            attempt_id: attempt_id.clone(),
+            code: TwoFaCode::new(),
+        };
+        let backup_code_store = MockBackupCodeStore::default();
+        let codes = GenerateBackupCodesUseCase::new(backup_code_store.clone())
+            .execute(email.clone())
+            .await
+            .unwrap();
+
+        let use_case = Verify2FaUseCase::new(store);
+        let result = use_case
+            .execute_with_backup_code(
+                email.clone(),
+                TwoFaAttemptId::new(),
+                codes[0].clone(),
+                &backup_code_store,
+            )
+            .await;
+        assert!(matches!(result, Err(Verify2FaError::InvalidLoginAttemptId)));
+
+        let result = use_case
+            .execute_with_backup_code(
+                email.clone(),
+                attempt_id.clone(),
+                codes[0].clone(),
+                &backup_code_store,
+            )
+            .await;
+        assert_eq!(result.unwrap(), email);
+
+        let result = use_case
+            .execute_with_backup_code(email, attempt_id, codes[0].clone(), &backup_code_store)
+            .await;
+        assert!(matches!(result, Err(Verify2FaError::InvalidTwoFaCode)));
+    }
+
+    #[tokio::test]
+    async fn test_stateless_verify_2fa_success_and_single_use() {
+        let signer = TwoFaTokenSigner::new(b"secret", 600);
+        let code = TwoFaCode::new();
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let token = signer.issue(&email, &code).unwrap();
+
+        let use_case = StatelessVerify2FaUseCase::new(MockBannedTokenStore::default());
+
+        let result = use_case
+            .execute(email.clone(), token.clone(), code.clone(), &signer)
+            .await;
+        assert_eq!(result.unwrap(), email);
+
+        let result = use_case.execute(email, token, code, &signer).await;
+        assert!(matches!(result, Err(Verify2FaError::InvalidLoginAttemptId)));
+    }
+
+    #[tokio::test]
+    async fn test_stateless_verify_2fa_invalid_code() {
+        let signer = TwoFaTokenSigner::new(b"secret", 600);
+        let code = TwoFaCode::parse("123456".to_string()).unwrap();
+        let wrong_code = TwoFaCode::parse("654321".to_string()).unwrap();
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let token = signer.issue(&email, &code).unwrap();
+
+        let use_case = StatelessVerify2FaUseCase::new(MockBannedTokenStore::default());
+        let result = use_case.execute(email, token, wrong_code, &signer).await;
+
+        assert!(matches!(result, Err(Verify2FaError::InvalidTwoFaCode)));
+    }
+
+    #[tokio::test]
+    async fn test_stateless_verify_2fa_token_for_other_user() {
+        let signer = TwoFaTokenSigner::new(b"secret", 600);
+        let code = TwoFaCode::new();
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let other = Email::try_from(Secret::from("other@example.com".to_string())).unwrap();
+        let token = signer.issue(&other, &code).unwrap();
+
+        let use_case = StatelessVerify2FaUseCase::new(MockBannedTokenStore::default());
+        let result = use_case.execute(email, token, code, &signer).await;
+
+        assert!(matches!(result, Err(Verify2FaError::InvalidLoginAttemptId)));
+    }
+
+    #[derive(Default)]
+    struct MockRateLimitStore {
+        hits: Arc<RwLock<std::collections::HashMap<String, u64>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RateLimitStore for MockRateLimitStore {
+        async fn hit(&self, key: &str, _window: Duration) -> Result<u64, RateLimitStoreError> {
+            let mut hits = self.hits.write().await;
+            let count = hits.entry(key.to_owned()).or_default();
+            *count += 1;
+            Ok(*count)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stateless_verify_2fa_bans_token_after_failed_codes() {
+        let signer = TwoFaTokenSigner::new(b"secret", 600);
+        let code = TwoFaCode::parse("123456".to_string()).unwrap();
+        let wrong_code = TwoFaCode::parse("654321".to_string()).unwrap();
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let token = signer.issue(&email, &code).unwrap();
+        let rate_limit_store = MockRateLimitStore::default();
+
+        let use_case = StatelessVerify2FaUseCase::new(MockBannedTokenStore::default());
+        for _ in 0..3 {
+            let result = use_case
+                .execute_with_failure_limit(
+                    email.clone(),
+                    token.clone(),
+                    wrong_code.clone(),
+                    &signer,
+                    &rate_limit_store,
+                    3,
+                )
+                .await;
+            assert!(matches!(result, Err(Verify2FaError::InvalidTwoFaCode)));
+        }
+
+        // The right code no longer helps once the token is burned
+        let result = use_case
+            .execute_with_failure_limit(email, token, code, &signer, &rate_limit_store, 3)
+            .await;
+        assert!(matches!(result, Err(Verify2FaError::InvalidLoginAttemptId)));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_counts_email_and_ip() {
+        let limit = Verify2FaRateLimit {
+            max_attempts: 2,
+            window: Duration::from_secs(60),
+        };
+        let store = MockRateLimitStore::default();
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let other = Email::try_from(Secret::from("other@example.com".to_string())).unwrap();
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+
+        assert!(limit.check(&store, &email, Some(ip)).await.is_ok());
+        assert!(limit.check(&store, &email, None).await.is_ok());
+        assert!(matches!(
+            limit.check(&store, &email, None).await,
+            Err(Verify2FaError::TooManyAttempts)
+        ));
+
+        // Same client guessing for another user
+        assert!(limit.check(&store, &other, Some(ip)).await.is_ok());
+        assert!(matches!(
+            limit.check(&store, &other, Some(ip)).await,
+            Err(Verify2FaError::TooManyAttempts)
+        ));
+    }
 }
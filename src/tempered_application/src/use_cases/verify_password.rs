@@ -0,0 +1,237 @@
+use std::time::Duration;
+
+use secrecy::ExposeSecret;
+use tempered_core::{
+    Email, Password, RateLimitStore, RateLimitStoreError, UserStore, UserStoreError,
+};
+
+use super::elevate::ELEVATION_RATE_LIMIT_PREFIX;
+
+/// Error types for verify password use case
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyPasswordError {
+    #[error("User store error: {0}")]
+    UserStoreError(#[from] UserStoreError),
+    #[error("Rate limit store error: {0}")]
+    RateLimitStoreError(#[from] RateLimitStoreError),
+    #[error("Password verified again too soon")]
+    TooManyAttempts,
+}
+
+/// Verify password use case - re-authenticates a user without issuing anything,
+/// e.g. for "re-enter your password" confirmation dialogs
+pub struct VerifyPasswordUseCase<U>
+where
+    U: UserStore,
+{
+    user_store: U,
+}
+
+impl<U> VerifyPasswordUseCase<U>
+where
+    U: UserStore,
+{
+    pub fn new(user_store: U) -> Self {
+        Self { user_store }
+    }
+
+    /// Execute the verify password use case
+    ///
+    /// # Arguments
+    /// * `email` - User's email address (from existing auth token)
+    /// * `password` - Password to check
+    ///
+    /// # Returns
+    /// Ok(()) if the password is correct, or VerifyPasswordError
+    #[tracing::instrument(name = "VerifyPasswordUseCase::execute", skip(self, password))]
+    pub async fn execute(
+        &self,
+        email: Email,
+        password: Password,
+    ) -> Result<(), VerifyPasswordError> {
+        self.user_store.authenticate_user(&email, &password).await?;
+        Ok(())
+    }
+
+    /// Execute the verify password use case, allowing at most one attempt per
+    /// `min_interval` per user
+    ///
+    /// Attempts share the cooldown of [`crate::ElevateUseCase::execute_with_cooldown`],
+    /// so neither endpoint can be used to test passwords in quick succession.
+    ///
+    /// # Arguments
+    /// * `email` - User's email address (from existing auth token)
+    /// * `password` - Password to check
+    /// * `rate_limit_store` - Store tracking recent attempts
+    /// * `min_interval` - Minimum time between two attempts for the same email
+    ///
+    /// # Returns
+    /// Ok(()) if the password is correct, `TooManyAttempts` if the previous attempt
+    /// was less than `min_interval` ago, or another VerifyPasswordError
+    #[tracing::instrument(
+        name = "VerifyPasswordUseCase::execute_with_cooldown",
+        skip(self, password, rate_limit_store)
+    )]
+    pub async fn execute_with_cooldown<R>(
+        &self,
+        email: Email,
+        password: Password,
+        rate_limit_store: &R,
+        min_interval: Duration,
+    ) -> Result<(), VerifyPasswordError>
+    where
+        R: RateLimitStore,
+    {
+        let key = format!(
+            "{ELEVATION_RATE_LIMIT_PREFIX}{}",
+            email.as_ref().expose_secret()
+        );
+        if rate_limit_store.hit(&key, min_interval).await? > 1 {
+            return Err(VerifyPasswordError::TooManyAttempts);
+        }
+
+        self.execute(email, password).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::*;
+    use secrecy::Secret;
+    use tempered_core::{User, ValidatedUser};
+    use tokio::sync::RwLock;
+
+    struct MockUserStore {
+        email: String,
+        password: String,
+    }
+
+    #[async_trait::async_trait]
+    impl UserStore for MockUserStore {
+        async fn add_user(&self, _user: User) -> Result<(), UserStoreError> {
+            unimplemented!()
+        }
+
+        async fn set_new_password(
+            &self,
+            _email: &Email,
+            _new_password: Password,
+        ) -> Result<(), UserStoreError> {
+            unimplemented!()
+        }
+
+        async fn authenticate_user(
+            &self,
+            email: &Email,
+            password: &Password,
+        ) -> Result<ValidatedUser, UserStoreError> {
+            if email.as_ref().expose_secret() != &self.email {
+                return Err(UserStoreError::UserNotFound);
+            }
+            if password.as_ref().expose_secret() != &self.password {
+                return Err(UserStoreError::IncorrectPassword);
+            }
+            Ok(ValidatedUser::No2Fa(email.clone()))
+        }
+
+        async fn get_user(&self, _email: &Email) -> Result<User, UserStoreError> {
+            unimplemented!()
+        }
+
+        async fn delete_user(&self, _email: &Email) -> Result<(), UserStoreError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_password() {
+        let use_case = VerifyPasswordUseCase::new(MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+        });
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+
+        let correct = Password::try_from(Secret::from("password123".to_string())).unwrap();
+        assert!(use_case.execute(email.clone(), correct).await.is_ok());
+
+        let wrong = Password::try_from(Secret::from("password456".to_string())).unwrap();
+        let result = use_case.execute(email, wrong).await;
+        assert!(matches!(
+            result,
+            Err(VerifyPasswordError::UserStoreError(
+                UserStoreError::IncorrectPassword
+            ))
+        ));
+    }
+
+    #[derive(Default)]
+    struct MockRateLimitStore {
+        hits: Arc<RwLock<HashMap<String, u64>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RateLimitStore for MockRateLimitStore {
+        async fn hit(&self, key: &str, _window: Duration) -> Result<u64, RateLimitStoreError> {
+            let mut hits = self.hits.write().await;
+            let count = hits.entry(key.to_owned()).or_default();
+            *count += 1;
+            Ok(*count)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_wrong_passwords_are_throttled() {
+        let use_case = VerifyPasswordUseCase::new(MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+        });
+        let rate_limit_store = MockRateLimitStore::default();
+        let interval = Duration::from_secs(30);
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let wrong = Password::try_from(Secret::from("password456".to_string())).unwrap();
+
+        let result = use_case
+            .execute_with_cooldown(email.clone(), wrong.clone(), &rate_limit_store, interval)
+            .await;
+        assert!(matches!(
+            result,
+            Err(VerifyPasswordError::UserStoreError(
+                UserStoreError::IncorrectPassword
+            ))
+        ));
+
+        let result = use_case
+            .execute_with_cooldown(email, wrong, &rate_limit_store, interval)
+            .await;
+        assert!(matches!(result, Err(VerifyPasswordError::TooManyAttempts)));
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_is_shared_with_elevate() {
+        let user_store = MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+        };
+        let rate_limit_store = MockRateLimitStore::default();
+        let interval = Duration::from_secs(30);
+        let email = Email::try_from(Secret::from("test@example.com".to_string())).unwrap();
+        let wrong = Password::try_from(Secret::from("password456".to_string())).unwrap();
+        let right = Password::try_from(Secret::from("password123".to_string())).unwrap();
+
+        let result = crate::ElevateUseCase::new(user_store)
+            .execute_with_cooldown(email.clone(), wrong, &rate_limit_store, interval)
+            .await;
+        assert!(result.is_err());
+
+        let use_case = VerifyPasswordUseCase::new(MockUserStore {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+        });
+        let result = use_case
+            .execute_with_cooldown(email, right, &rate_limit_store, interval)
+            .await;
+        assert!(matches!(result, Err(VerifyPasswordError::TooManyAttempts)));
+    }
+}
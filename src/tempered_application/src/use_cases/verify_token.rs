@@ -0,0 +1,280 @@
+use jsonwebtoken::{DecodingKey, EncodingKey, Validation, decode, encode};
+use tempered_core::{BannedTokenStore, BannedTokenStoreError};
+
+use crate::{
+    claims::Claims,
+    token_issuer::{TokenIssuer, TokenIssuerError},
+};
+
+/// Error types for verify token use case
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyTokenError {
+    #[error("Token error: {0}")]
+    TokenError(#[from] jsonwebtoken::errors::Error),
+    #[error("Token is banned")]
+    TokenIsBanned,
+    #[error("Token issuer error: {0}")]
+    IssuerError(#[from] TokenIssuerError),
+    #[error("Banned token store error: {0}")]
+    BannedTokenStoreError(#[from] BannedTokenStoreError),
+}
+
+/// Verify token use case - decodes a JWT and checks it against the ban list
+pub struct VerifyTokenUseCase<B>
+where
+    B: BannedTokenStore,
+{
+    banned_token_store: B,
+}
+
+impl<B> VerifyTokenUseCase<B>
+where
+    B: BannedTokenStore,
+{
+    pub fn new(banned_token_store: B) -> Self {
+        Self { banned_token_store }
+    }
+
+    /// Execute the verify token use case
+    ///
+    /// # Arguments
+    /// * `token` - The raw JWT to verify
+    /// * `secret` - Secret the token was signed with
+    ///
+    /// # Returns
+    /// The token's Claims if it is valid and not banned, or VerifyTokenError
+    #[tracing::instrument(name = "VerifyTokenUseCase::execute", skip_all)]
+    pub async fn execute(&self, token: &str, secret: &[u8]) -> Result<Claims, VerifyTokenError> {
+        let claims = decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(secret),
+            &Validation::default(),
+        )
+        .map(|data| data.claims)?;
+
+        // Tokens without a `jti` are banned in their canonical encoded form
+        let ban_key = match &claims.jti {
+            Some(jti) => jti.clone(),
+            None => encode(
+                &jsonwebtoken::Header::default(),
+                &claims,
+                &EncodingKey::from_secret(secret),
+            )?,
+        };
+
+        self.check_not_banned(&ban_key, &claims).await?;
+
+        Ok(claims)
+    }
+
+    /// Execute the verify token use case with a custom [`TokenIssuer`]
+    ///
+    /// # Arguments
+    /// * `token` - The raw JWT to verify
+    /// * `issuer` - Issuer that verifies the token's signature
+    ///
+    /// # Returns
+    /// The token's Claims if it is valid and not banned, or VerifyTokenError
+    #[tracing::instrument(name = "VerifyTokenUseCase::execute_with_issuer", skip_all)]
+    pub async fn execute_with_issuer<I>(
+        &self,
+        token: &str,
+        issuer: &I,
+    ) -> Result<Claims, VerifyTokenError>
+    where
+        I: TokenIssuer,
+    {
+        let claims = issuer.verify(token).await?;
+
+        // The issuer owns the encoding, so a token without a `jti` is checked as presented
+        self.check_not_banned(&claims.ban_key(token), &claims)
+            .await?;
+
+        Ok(claims)
+    }
+
+    /// Reject the token if its ban key is banned, or it was issued before all of its
+    /// subject's tokens were revoked. Revocations are keyed by user id, so they don't
+    /// reach tokens issued before the subject was the user id.
+    async fn check_not_banned(
+        &self,
+        ban_key: &str,
+        claims: &Claims,
+    ) -> Result<(), VerifyTokenError> {
+        if self.banned_token_store.contains_token(ban_key).await? {
+            return Err(VerifyTokenError::TokenIsBanned);
+        }
+
+        let Some(user_id) = claims.user_id() else {
+            return Ok(());
+        };
+        let revoked_at = self
+            .banned_token_store
+            .user_tokens_revoked_at(&user_id)
+            .await?;
+        if revoked_at.is_some_and(|revoked_at| claims.is_revoked_by(revoked_at)) {
+            return Err(VerifyTokenError::TokenIsBanned);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::RwLock;
+
+    use super::*;
+    use secrecy::{ExposeSecret, Secret};
+    use std::{collections::HashSet, sync::Arc};
+    use tempered_core::UserId;
+
+    const SECRET: &[u8] = b"test-secret";
+    const USER_ID: &str = "1b4e28ba-2fa1-11d2-883f-0016d3cca427";
+
+    #[derive(Clone, Default)]
+    struct MockBannedTokenStore {
+        banned_tokens: Arc<RwLock<HashSet<String>>>,
+        revoked_at: Arc<RwLock<Option<u64>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl BannedTokenStore for MockBannedTokenStore {
+        async fn ban_token(&self, token: String) -> Result<(), BannedTokenStoreError> {
+            self.banned_tokens.write().await.insert(token);
+            Ok(())
+        }
+
+        async fn contains_token(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
+            Ok(self.banned_tokens.read().await.contains(token))
+        }
+
+        async fn revoke_user_tokens(
+            &self,
+            _user: &UserId,
+            revoked_at: u64,
+            _expires_at: u64,
+        ) -> Result<(), BannedTokenStoreError> {
+            *self.revoked_at.write().await = Some(revoked_at);
+            Ok(())
+        }
+
+        async fn user_tokens_revoked_at(
+            &self,
+            _user: &UserId,
+        ) -> Result<Option<u64>, BannedTokenStoreError> {
+            Ok(*self.revoked_at.read().await)
+        }
+    }
+
+    fn create_token(secret: &[u8]) -> String {
+        let now = chrono::Utc::now().timestamp() as usize;
+        let claims = Claims {
+            sub: Secret::from(USER_ID.to_string()),
+            exp: now + 600,
+            iat: Some(now),
+            jti: None,
+            token_type: None,
+            scope: None,
+            extra: Default::default(),
+        };
+        encode(
+            &jsonwebtoken::Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_verify_valid_token() {
+        let use_case = VerifyTokenUseCase::new(MockBannedTokenStore::default());
+        let token = create_token(SECRET);
+
+        let claims = use_case.execute(&token, SECRET).await.unwrap();
+        assert_eq!(claims.sub.expose_secret(), USER_ID);
+    }
+
+    #[tokio::test]
+    async fn test_verify_token_with_wrong_secret() {
+        let use_case = VerifyTokenUseCase::new(MockBannedTokenStore::default());
+        let token = create_token(b"another-secret");
+
+        let result = use_case.execute(&token, SECRET).await;
+        assert!(matches!(result, Err(VerifyTokenError::TokenError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_issuer() {
+        let store = MockBannedTokenStore::default();
+        let issuer = crate::LocalJwtIssuer::new(SECRET);
+        let token = create_token(SECRET);
+
+        let use_case = VerifyTokenUseCase::new(store.clone());
+        let claims = use_case.execute_with_issuer(&token, &issuer).await.unwrap();
+        assert_eq!(claims.sub.expose_secret(), USER_ID);
+
+        store.ban_token(token.clone()).await.unwrap();
+        let result = use_case.execute_with_issuer(&token, &issuer).await;
+        assert!(matches!(result, Err(VerifyTokenError::TokenIsBanned)));
+    }
+
+    #[tokio::test]
+    async fn test_verify_banned_token() {
+        let store = MockBannedTokenStore::default();
+        let token = create_token(SECRET);
+        store.ban_token(token.clone()).await.unwrap();
+
+        let use_case = VerifyTokenUseCase::new(store);
+
+        let result = use_case.execute(&token, SECRET).await;
+        assert!(matches!(result, Err(VerifyTokenError::TokenIsBanned)));
+    }
+
+    #[tokio::test]
+    async fn test_verify_token_banned_by_jti() {
+        let store = MockBannedTokenStore::default();
+        let issuer = crate::LocalJwtIssuer::new(SECRET);
+        let token = issuer
+            .issue(
+                &UserId::new(),
+                600,
+                crate::TokenType::Access,
+                None,
+                Default::default(),
+            )
+            .await
+            .unwrap();
+        let use_case = VerifyTokenUseCase::new(store.clone());
+
+        let jti = use_case.execute(&token, SECRET).await.unwrap().jti.unwrap();
+        store.ban_token(crate::token_ban_key(&token)).await.unwrap();
+        assert!(store.banned_tokens.read().await.contains(&jti));
+
+        let result = use_case.execute(&token, SECRET).await;
+        assert!(matches!(result, Err(VerifyTokenError::TokenIsBanned)));
+        let result = use_case.execute_with_issuer(&token, &issuer).await;
+        assert!(matches!(result, Err(VerifyTokenError::TokenIsBanned)));
+    }
+
+    #[tokio::test]
+    async fn test_verify_token_of_revoked_user() {
+        let store = MockBannedTokenStore::default();
+        let token = create_token(SECRET);
+        let use_case = VerifyTokenUseCase::new(store.clone());
+
+        let user_id = UserId::new();
+        let now = chrono::Utc::now().timestamp() as u64;
+        store
+            .revoke_user_tokens(&user_id, now - 60, now)
+            .await
+            .unwrap();
+        assert!(use_case.execute(&token, SECRET).await.is_ok());
+
+        store
+            .revoke_user_tokens(&user_id, now + 1, now)
+            .await
+            .unwrap();
+        let result = use_case.execute(&token, SECRET).await;
+        assert!(matches!(result, Err(VerifyTokenError::TokenIsBanned)));
+    }
+}
@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS api_keys(
+   key_hash TEXT NOT NULL PRIMARY KEY,
+   email TEXT NOT NULL,
+   scopes TEXT[] NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Add up migration script here
+CREATE UNIQUE INDEX IF NOT EXISTS users_username_lower_idx ON users (lower(username));
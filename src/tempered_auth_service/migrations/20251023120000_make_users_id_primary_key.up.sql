@@ -0,0 +1,24 @@
+-- Add up migration script here
+-- Key users by their stable id instead of their email, and point password_history at it.
+ALTER TABLE password_history ADD COLUMN IF NOT EXISTS user_id UUID;
+
+UPDATE password_history
+SET user_id = users.id
+FROM users
+WHERE users.email = password_history.email;
+
+ALTER TABLE password_history ALTER COLUMN user_id SET NOT NULL;
+
+-- Also drops the email foreign key and password_history_email_changed_at_idx
+ALTER TABLE password_history DROP COLUMN IF EXISTS email;
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_pkey;
+ALTER TABLE users ADD CONSTRAINT users_pkey PRIMARY KEY USING INDEX users_id_idx;
+ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email);
+
+ALTER TABLE password_history
+   ADD CONSTRAINT password_history_user_id_fkey
+   FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS password_history_user_id_changed_at_idx
+   ON password_history (user_id, changed_at DESC);
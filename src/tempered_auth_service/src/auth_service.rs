@@ -1,28 +1,64 @@
+use std::{sync::Arc, time::Duration};
+
 use axum::{
-    Router,
-    http::{HeaderValue, Method, request},
-    routing::{delete, post},
+    BoxError, Extension, Json, Router,
+    error_handling::HandleErrorLayer,
+    http::{HeaderValue, Method, StatusCode, request},
+    middleware,
+    routing::{delete, get, post},
 };
 use tempered_adapters::{
+    auth::{ClaimsEnricher, TokenIssuer, TokenMinter, migrate_legacy_cookies},
     config::AllowedOrigins,
     http::routes::{
-        change_password, delete_account, elevate, login, logout, signup, verify_2fa,
-        verify_elevated_token, verify_token,
+        BackupCodes, CasedJson, SharedAttemptIdGenerator, SignupAutoLogin, change_password,
+        delete_account, elevate, force_logout, login, logout, me, metrics, ready,
+        regenerate_backup_codes, resend_2fa_code, session, signup, two_fa_settings, verify_2fa,
+        verify_elevated_token, verify_password, verify_token,
     },
+    persistence::HashMapRateLimitStore,
+};
+use tempered_application::PasswordStrengthPolicy;
+use tempered_core::{
+    ApiKeyStore, AttemptIdGenerator, BackupCodeStore, BannedTokenStore, EmailClient,
+    NoopRegistrationHook, PasswordStrengthEstimator, ProbeableStore, RateLimitStore,
+    RegistrationHook, TwoFaCodeStore, UserStore,
 };
-use tempered_core::{BannedTokenStore, EmailClient, TwoFaCodeStore, UserStore};
 use tokio::net::TcpListener;
+use tower::{ServiceBuilder, limit::GlobalConcurrencyLimitLayer};
 use tower_http::{
     cors::{AllowOrigin, CorsLayer},
     services::{ServeDir, ServeFile},
+    timeout::TimeoutLayer,
     trace::TraceLayer,
 };
 
-use crate::tracing::{make_span_with_request_id, on_request, on_response};
+use crate::{
+    capabilities::{CAPABILITIES_PATH, Capabilities},
+    openapi::{OPENAPI_PATH, RouteSpec, openapi_document},
+    security_headers::SecurityHeadersLayer,
+    tracing::{make_span_with_request_id, on_request, on_response},
+};
+
+/// Default upper bound on how long a single request may take
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Main authentication service that provides all auth-related routes
 pub struct AuthService {
     router: Router,
+    /// Routes that hash or verify passwords, kept apart so they can be concurrency limited
+    password_hashing_router: Router,
+    assets_service: ServeDir<ServeFile>,
+    prefix: Option<String>,
+    request_timeout: Option<Duration>,
+    password_hashing_limit: Option<usize>,
+    /// Metadata of the mounted routes, relative to the prefix
+    routes: Vec<RouteSpec>,
+    serve_openapi: bool,
+    serve_capabilities: bool,
+    readiness_probes: Vec<(String, Arc<dyn ProbeableStore>)>,
+    security_headers: Option<SecurityHeadersLayer>,
+    token_minter: TokenMinter,
 }
 
 impl AuthService {
@@ -49,14 +85,81 @@ impl AuthService {
         B: BannedTokenStore + Clone + 'static,
         T: TwoFaCodeStore + Clone + 'static,
         E: EmailClient + Clone + 'static,
+    {
+        Self::new_with_registration_hook(
+            user_store,
+            banned_token_store,
+            two_fa_code_store,
+            email_client,
+            NoopRegistrationHook,
+            assets_dir,
+        )
+    }
+
+    /// Create a new AuthService that runs `registration_hook` after every successful signup
+    ///
+    /// Whether a failing hook rolls back the registration is controlled by
+    /// `auth.signup.rollback_on_hook_failure`, by default the failure is only logged.
+    pub fn new_with_registration_hook<U, B, T, E, H>(
+        user_store: U,
+        banned_token_store: B,
+        two_fa_code_store: T,
+        email_client: E,
+        registration_hook: H,
+        assets_dir: String,
+    ) -> Self
+    where
+        U: UserStore + Clone + 'static,
+        B: BannedTokenStore + Clone + 'static,
+        T: TwoFaCodeStore + Clone + 'static,
+        E: EmailClient + Clone + 'static,
+        H: RegistrationHook + Clone + 'static,
+    {
+        Self::new_with_rate_limit_store(
+            user_store,
+            banned_token_store,
+            two_fa_code_store,
+            email_client,
+            registration_hook,
+            HashMapRateLimitStore::default(),
+            assets_dir,
+        )
+    }
+
+    /// Create a new AuthService that keeps rate limit counters in `rate_limit_store`
+    ///
+    /// The other constructors keep them in memory, which is only accurate with a
+    /// single instance. Share a store between instances when running several.
+    pub fn new_with_rate_limit_store<U, B, T, E, H, R>(
+        user_store: U,
+        banned_token_store: B,
+        two_fa_code_store: T,
+        email_client: E,
+        registration_hook: H,
+        rate_limit_store: R,
+        assets_dir: String,
+    ) -> Self
+    where
+        U: UserStore + Clone + 'static,
+        B: BannedTokenStore + Clone + 'static,
+        T: TwoFaCodeStore + Clone + 'static,
+        E: EmailClient + Clone + 'static,
+        H: RegistrationHook + Clone + 'static,
+        R: RateLimitStore + Clone + 'static,
     {
         let assets_service =
             ServeDir::new(assets_dir.clone()).fallback(ServeFile::new(assets_dir + "/index.html"));
 
-        let router = Router::new()
-            // Signup only needs user store
-            .route("/signup", post(signup::<U>))
-            .with_state(user_store.clone())
+        let password_hashing_router = Router::new()
+            // Signup needs user store and the registration hook, plus the 2FA store and
+            // email client to start a 2FA login when auto login is enabled
+            .route("/signup", post(signup::<U, H, T, E>))
+            .with_state((
+                user_store.clone(),
+                registration_hook,
+                two_fa_code_store.clone(),
+                email_client.clone(),
+            ))
             // Login needs user store, 2FA store, and email client
             .route("/login", post(login::<U, T, E>))
             .with_state((
@@ -64,30 +167,449 @@ impl AuthService {
                 two_fa_code_store.clone(),
                 email_client.clone(),
             ))
+            // Change password needs user store and banned token store. PUT and PATCH
+            // are accepted for clients that treat the password as a resource.
+            .route(
+                "/change-password",
+                post(change_password::<U, B>)
+                    .put(change_password::<U, B>)
+                    .patch(change_password::<U, B>),
+            )
+            .with_state((user_store.clone(), banned_token_store.clone()))
+            // Verify password needs user store, banned token store, and rate limit store
+            .route("/verify-password", post(verify_password::<U, B, R>))
+            .with_state((
+                user_store.clone(),
+                banned_token_store.clone(),
+                rate_limit_store.clone(),
+            ));
+
+        let router = Router::new()
             // Logout only needs banned token store
             .route("/logout", post(logout::<B>))
             .with_state(banned_token_store.clone())
-            // Verify 2FA only needs 2FA code store
-            .route("/verify-2fa", post(verify_2fa::<T>))
-            .with_state(two_fa_code_store.clone())
+            // Verify 2FA needs user store, 2FA code store, banned token store for stateless
+            // 2FA tokens, and rate limit store
+            .route("/verify-2fa", post(verify_2fa::<U, T, B, R>))
+            .with_state((
+                user_store.clone(),
+                two_fa_code_store.clone(),
+                banned_token_store.clone(),
+                rate_limit_store.clone(),
+            ))
+            // Resend 2FA code needs the login state and rate limit store
+            .route("/resend-2fa-code", post(resend_2fa_code::<U, T, E, R>))
+            .with_state((
+                user_store.clone(),
+                two_fa_code_store.clone(),
+                email_client,
+                rate_limit_store.clone(),
+            ))
             // Verify token only needs banned token store
             .route("/verify-token", post(verify_token::<B>))
             .with_state(banned_token_store.clone())
+            // Session needs user store and banned token store
+            .route("/session", get(session::<U, B>))
+            .with_state((user_store.clone(), banned_token_store.clone()))
+            // Me needs user store and banned token store
+            .route("/me", get(me::<U, B>))
+            .with_state((user_store.clone(), banned_token_store.clone()))
             // Verify elevated token only needs banned token store
             .route("/verify-elevated-token", post(verify_elevated_token::<B>))
             .with_state(banned_token_store.clone())
-            // Elevate needs user store and banned token store
-            .route("/elevate", post(elevate::<U, B>))
-            .with_state((user_store.clone(), banned_token_store.clone()))
-            // Change password needs user store and banned token store
-            .route("/change-password", post(change_password::<U, B>))
-            .with_state((user_store.clone(), banned_token_store.clone()))
+            // Elevate needs user store, banned token store, and rate limit store
+            .route("/elevate", post(elevate::<U, B, R>))
+            .with_state((
+                user_store.clone(),
+                banned_token_store.clone(),
+                rate_limit_store,
+            ))
+            // 2FA settings need user store, 2FA store, and banned token store
+            .route("/2fa/settings", post(two_fa_settings::<U, T, B>))
+            .with_state((
+                user_store.clone(),
+                two_fa_code_store.clone(),
+                banned_token_store.clone(),
+            ))
             // Delete account needs user store and banned token store
             .route("/delete-account", delete(delete_account::<U, B>))
-            .with_state((user_store, banned_token_store))
-            .fallback_service(assets_service);
+            .with_state((user_store, banned_token_store));
+
+        let routes = vec![
+            RouteSpec::new(
+                Method::POST,
+                "/signup",
+                Some("SignupRequest"),
+                Some("SignupResponse"),
+            ),
+            RouteSpec::new(
+                Method::POST,
+                "/login",
+                Some("LoginRequest"),
+                Some("LoginHttpResponse"),
+            ),
+            RouteSpec::new(
+                Method::POST,
+                "/change-password",
+                Some("ChangePasswordRequest"),
+                None,
+            ),
+            RouteSpec::new(
+                Method::PUT,
+                "/change-password",
+                Some("ChangePasswordRequest"),
+                None,
+            ),
+            RouteSpec::new(
+                Method::PATCH,
+                "/change-password",
+                Some("ChangePasswordRequest"),
+                None,
+            ),
+            RouteSpec::new(
+                Method::POST,
+                "/verify-password",
+                Some("VerifyPasswordRequest"),
+                None,
+            ),
+            RouteSpec::new(Method::POST, "/logout", None, None),
+            RouteSpec::new(
+                Method::POST,
+                "/verify-2fa",
+                Some("Verify2FARequest"),
+                Some("CsrfTokenResponse"),
+            ),
+            RouteSpec::new(
+                Method::POST,
+                "/resend-2fa-code",
+                Some("Resend2FACodeRequest"),
+                Some("TwoFactorAuthResponse"),
+            ),
+            RouteSpec::new(
+                Method::POST,
+                "/verify-token",
+                Some("VerifyTokenRequest"),
+                None,
+            ),
+            RouteSpec::new(Method::GET, "/session", None, Some("SessionResponse")),
+            RouteSpec::new(Method::GET, "/me", None, Some("UserProfile")),
+            RouteSpec::new(
+                Method::POST,
+                "/verify-elevated-token",
+                Some("VerifyElevatedTokenRequest"),
+                None,
+            ),
+            RouteSpec::new(Method::POST, "/elevate", Some("ElevateRequest"), None),
+            RouteSpec::new(
+                Method::POST,
+                "/2fa/settings",
+                Some("TwoFaSettingsRequest"),
+                Some("TwoFaSettingsResponse"),
+            ),
+            RouteSpec::new(Method::DELETE, "/delete-account", None, None),
+        ];
 
-        Self { router }
+        Self {
+            router,
+            password_hashing_router,
+            assets_service,
+            prefix: None,
+            request_timeout: Some(DEFAULT_REQUEST_TIMEOUT),
+            password_hashing_limit: None,
+            routes,
+            serve_openapi: false,
+            serve_capabilities: false,
+            readiness_probes: Vec::new(),
+            security_headers: Some(SecurityHeadersLayer::default()),
+            token_minter: TokenMinter::default(),
+        }
+    }
+
+    /// Set how long a request may take before it is aborted with 504 Gateway Timeout
+    ///
+    /// Defaults to [`DEFAULT_REQUEST_TIMEOUT`]. Pass `None` to disable the timeout.
+    pub fn with_request_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Limit how many signup, login, change password and verify password requests are
+    /// handled at once
+    ///
+    /// Each of them runs Argon2, so under load they can exhaust the blocking thread pool.
+    /// Requests over the limit are rejected with 503 Service Unavailable instead of
+    /// queueing. The limit is shared by these routes and independent of the rate
+    /// limits. Disabled by default, `None` disables it again.
+    pub fn with_password_hashing_limit(mut self, max_concurrent_requests: Option<usize>) -> Self {
+        self.password_hashing_limit = max_concurrent_requests;
+        self
+    }
+
+    /// Set the security headers added to every response, including the static assets
+    ///
+    /// Defaults to [`SecurityHeadersLayer::default`]. Pass `None` to add none.
+    pub fn with_security_headers(mut self, security_headers: Option<SecurityHeadersLayer>) -> Self {
+        self.security_headers = security_headers;
+        self
+    }
+
+    /// Serve a minimal OpenAPI document of the mounted routes at `/openapi.json`,
+    /// under the prefix if one is set. Disabled by default.
+    pub fn with_openapi(mut self, enabled: bool) -> Self {
+        self.serve_openapi = enabled;
+        self
+    }
+
+    /// Serve the [`Capabilities`] of this service at `/capabilities`, under the prefix
+    /// if one is set. Disabled by default.
+    pub fn with_capabilities(mut self, enabled: bool) -> Self {
+        self.serve_capabilities = enabled;
+        self
+    }
+
+    /// Sign the tokens issued by login, 2FA verification, signup, session renewal and
+    /// elevation with `issuer` instead of the configured JWT secrets
+    ///
+    /// Tokens are still verified with the configured secrets, so `issuer` has to sign
+    /// with them too, e.g. from a KMS or HSM holding the keys.
+    pub fn with_token_issuer(mut self, issuer: impl TokenIssuer + 'static) -> Self {
+        self.token_minter = self.token_minter.with_issuer(issuer);
+        self
+    }
+
+    /// Add the claims `enricher` derives for a user to every token the routes issue,
+    /// e.g. a tenant id looked up in a database
+    pub fn with_claims_enricher(mut self, enricher: impl ClaimsEnricher + 'static) -> Self {
+        self.token_minter = self.token_minter.with_claims_enricher(enricher);
+        self
+    }
+
+    /// Mount `/admin/force-logout`, which revokes every token of a user
+    ///
+    /// Callers authenticate with an API key from `api_key_store` that has the `admin`
+    /// scope. `user_store` and `banned_token_store` must be the stores the other routes use.
+    pub fn with_admin_routes<U, B, K>(
+        mut self,
+        user_store: U,
+        banned_token_store: B,
+        api_key_store: K,
+    ) -> Self
+    where
+        U: UserStore + Clone + 'static,
+        B: BannedTokenStore + Clone + 'static,
+        K: ApiKeyStore + Clone + 'static,
+    {
+        self.router = std::mem::take(&mut self.router).route(
+            "/admin/force-logout",
+            post(force_logout::<U, B, K>).with_state((
+                user_store,
+                banned_token_store,
+                api_key_store,
+            )),
+        );
+        self.routes.push(RouteSpec::new(
+            Method::POST,
+            "/admin/force-logout",
+            Some("ForceLogoutRequest"),
+            None,
+        ));
+        self
+    }
+
+    /// Log users in on signup when `enabled`, instead of following
+    /// `auth.signup.auto_login`
+    ///
+    /// Users who require 2FA are sent a code and get the 2FA response of `/login`.
+    pub fn with_signup_auto_login(mut self, enabled: bool) -> Self {
+        // Signup is on the password hashing router
+        self.password_hashing_router = std::mem::take(&mut self.password_hashing_router)
+            .layer(Extension(SignupAutoLogin(enabled)));
+        self
+    }
+
+    /// Generate 2FA login attempt IDs with `generator` instead of random UUIDs
+    ///
+    /// Only used when 2FA is not stateless.
+    pub fn with_attempt_id_generator(
+        mut self,
+        generator: impl AttemptIdGenerator + 'static,
+    ) -> Self {
+        let generator: SharedAttemptIdGenerator = Arc::new(generator);
+        // Login is on the password hashing router, resending codes on the main one
+        self.password_hashing_router =
+            std::mem::take(&mut self.password_hashing_router).layer(Extension(generator.clone()));
+        self.router = std::mem::take(&mut self.router).layer(Extension(generator));
+        self
+    }
+
+    /// Mount `/metrics`, which reports operational counters such as the number of
+    /// tokens in `banned_token_store`
+    ///
+    /// Callers authenticate with an API key from `api_key_store` that has the `admin`
+    /// scope. Stores that count by scanning, like Redis, cache the counts briefly.
+    pub fn with_metrics<B, K>(mut self, banned_token_store: B, api_key_store: K) -> Self
+    where
+        B: BannedTokenStore + Clone + 'static,
+        K: ApiKeyStore + Clone + 'static,
+    {
+        self.router = std::mem::take(&mut self.router).route(
+            "/metrics",
+            get(metrics::<B, K>).with_state((banned_token_store, api_key_store)),
+        );
+        self.routes.push(RouteSpec::new(
+            Method::GET,
+            "/metrics",
+            None,
+            Some("MetricsResponse"),
+        ));
+        self
+    }
+
+    /// Accept 2FA backup codes from `backup_code_store` at `/verify-2fa` and mount
+    /// `/2fa/backup-codes`, which replaces a user's codes and returns them once
+    ///
+    /// Regenerating requires an elevated token for the `two_fa_settings` scope.
+    /// `user_store` and `banned_token_store` must be the stores the other routes use.
+    pub fn with_backup_codes<U, C, B>(
+        mut self,
+        user_store: U,
+        backup_code_store: C,
+        banned_token_store: B,
+    ) -> Self
+    where
+        U: UserStore + Clone + 'static,
+        C: BackupCodeStore + Clone + 'static,
+        B: BannedTokenStore + Clone + 'static,
+    {
+        let backup_codes: BackupCodes = Arc::new(backup_code_store.clone());
+        self.router = std::mem::take(&mut self.router)
+            .layer(Extension(backup_codes))
+            .route(
+                "/2fa/backup-codes",
+                post(regenerate_backup_codes::<U, C, B>).with_state((
+                    user_store,
+                    backup_code_store,
+                    banned_token_store,
+                )),
+            );
+        self.routes.push(RouteSpec::new(
+            Method::POST,
+            "/2fa/backup-codes",
+            None,
+            Some("BackupCodesResponse"),
+        ));
+        self
+    }
+
+    /// Reject signup and change password requests whose password `estimator` scores
+    /// below `min_score`, returning its suggestions in the error body
+    ///
+    /// Without an estimator only the minimum password length is enforced. For
+    /// zxcvbn-style scores [`tempered_application::DEFAULT_MIN_PASSWORD_SCORE`] is a
+    /// reasonable minimum.
+    pub fn with_password_strength_estimator(
+        mut self,
+        estimator: impl PasswordStrengthEstimator + 'static,
+        min_score: u8,
+    ) -> Self {
+        let policy = PasswordStrengthPolicy::new(estimator, min_score);
+        self.password_hashing_router =
+            std::mem::take(&mut self.password_hashing_router).layer(Extension(policy));
+        self
+    }
+
+    /// Report `store`'s health and latency under `name` at `/ready`
+    ///
+    /// The route is mounted once a probe is added and answers 503 Service Unavailable
+    /// while any probed store is unhealthy.
+    pub fn with_readiness_probe(
+        mut self,
+        name: impl Into<String>,
+        store: impl ProbeableStore + 'static,
+    ) -> Self {
+        self.readiness_probes.push((name.into(), Arc::new(store)));
+        self
+    }
+
+    /// The routes this service mounts, with the prefix applied
+    ///
+    /// Includes `/openapi.json`, `/capabilities` and `/ready` when they are enabled.
+    /// The static asset fallback is not listed.
+    pub fn route_specs(&self) -> Vec<RouteSpec> {
+        self.mounted_routes()
+            .into_iter()
+            .map(|mut route| {
+                if let Some(prefix) = &self.prefix {
+                    route.path = format!("{prefix}{}", route.path);
+                }
+                route
+            })
+            .collect()
+    }
+
+    /// The features this service offers, derived from the routes it mounts
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::from_routes(&self.mounted_routes())
+    }
+
+    /// The mounted routes relative to the prefix
+    fn mounted_routes(&self) -> Vec<RouteSpec> {
+        let openapi = self
+            .serve_openapi
+            .then(|| RouteSpec::new(Method::GET, OPENAPI_PATH, None, None));
+        let capabilities = self
+            .serve_capabilities
+            .then(|| RouteSpec::new(Method::GET, CAPABILITIES_PATH, None, Some("Capabilities")));
+        let ready = (!self.readiness_probes.is_empty())
+            .then(|| RouteSpec::new(Method::GET, "/ready", None, Some("ReadinessResponse")));
+
+        self.routes
+            .iter()
+            .cloned()
+            .chain(openapi)
+            .chain(capabilities)
+            .chain(ready)
+            .collect()
+    }
+
+    /// Mount all auth routes under a path prefix, e.g. `/auth` serves `/auth/login`
+    ///
+    /// The static asset fallback is still served from the app root.
+    /// An empty prefix or `/` leaves the routes at the root.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        let prefix = prefix.trim_end_matches('/');
+
+        self.prefix = match prefix {
+            "" => None,
+            prefix if prefix.starts_with('/') => Some(prefix.to_owned()),
+            prefix => Some(format!("/{prefix}")),
+        };
+        self
+    }
+
+    fn with_timeout_layer(mut self) -> Self {
+        if let Some(timeout) = self.request_timeout {
+            self.router = self.router.layer(TimeoutLayer::with_status_code(
+                StatusCode::GATEWAY_TIMEOUT,
+                timeout,
+            ));
+        }
+        self
+    }
+
+    // Cookies are migrated before any handler reads them
+    fn with_legacy_cookie_layer(mut self) -> Self {
+        self.router =
+            std::mem::take(&mut self.router).layer(middleware::from_fn(migrate_legacy_cookies));
+        self
+    }
+
+    fn with_security_headers_layer(mut self) -> Self {
+        if let Some(security_headers) = self.security_headers.take() {
+            self.router = std::mem::take(&mut self.router).layer(security_headers);
+        }
+        self
     }
 
     fn with_trace_layer(mut self) -> Self {
@@ -100,6 +622,74 @@ impl AuthService {
         self
     }
 
+    /// Merge the password hashing routes back in, behind the concurrency limit if one is set
+    fn with_password_hashing_routes(mut self) -> Self {
+        let mut password_hashing_router = std::mem::take(&mut self.password_hashing_router);
+        if let Some(limit) = self.password_hashing_limit {
+            password_hashing_router = password_hashing_router.layer(
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(|_: BoxError| async {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    }))
+                    .load_shed()
+                    .layer(GlobalConcurrencyLimitLayer::new(limit)),
+            );
+        }
+        self.router = std::mem::take(&mut self.router).merge(password_hashing_router);
+        self
+    }
+
+    /// Make the token minter available to every route that issues tokens
+    fn with_token_minter_layer(mut self) -> Self {
+        self.router = std::mem::take(&mut self.router).layer(Extension(self.token_minter.clone()));
+        self
+    }
+
+    fn with_readiness_route(mut self) -> Self {
+        if !self.readiness_probes.is_empty() {
+            let probes = Arc::new(std::mem::take(&mut self.readiness_probes));
+            self.router =
+                std::mem::take(&mut self.router).route("/ready", get(ready).with_state(probes));
+        }
+        self
+    }
+
+    fn with_openapi_route(mut self) -> Self {
+        if self.serve_openapi {
+            let document = openapi_document(&self.route_specs());
+            self.router = std::mem::take(&mut self.router).route(
+                OPENAPI_PATH,
+                get(move || {
+                    // Key names are fixed by the OpenAPI spec, so no `CasedJson` here
+                    let document = document.clone();
+                    async move { Json(document) }
+                }),
+            );
+        }
+        self
+    }
+
+    fn with_capabilities_route(mut self) -> Self {
+        if self.serve_capabilities {
+            let capabilities = self.capabilities();
+            self.router = std::mem::take(&mut self.router).route(
+                CAPABILITIES_PATH,
+                get(move || async move { CasedJson(capabilities) }),
+            );
+        }
+        self
+    }
+
+    /// Nest the routes under the configured prefix and attach the asset fallback
+    fn with_assets_fallback(mut self) -> Self {
+        let router = match &self.prefix {
+            Some(prefix) => Router::new().nest(prefix, self.router),
+            None => self.router,
+        };
+        self.router = router.fallback_service(self.assets_service.clone());
+        self
+    }
+
     /// Convert the AuthService into a nested router that can be mounted on another router
     ///
     /// # Arguments
@@ -107,20 +697,49 @@ impl AuthService {
     ///
     /// # Returns
     /// An Axum Router that can be nested into another application
-    pub fn as_nested_router(mut self, allowed_origins: Option<AllowedOrigins>) -> Router {
+    pub fn as_nested_router(self, allowed_origins: Option<AllowedOrigins>) -> Router {
+        // Layers added later wrap earlier ones, so the trace layer sees timed out requests
+        let mut service = self
+            .with_password_hashing_routes()
+            .with_token_minter_layer()
+            .with_openapi_route()
+            .with_capabilities_route()
+            .with_readiness_route()
+            .with_assets_fallback()
+            .with_legacy_cookie_layer()
+            .with_timeout_layer()
+            .with_security_headers_layer();
+
         if let Some(allowed_origins) = allowed_origins {
-            let cors = CorsLayer::new()
-                .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-                .allow_credentials(true)
-                .allow_origin(AllowOrigin::predicate(
-                    move |origin: &HeaderValue, _request_parts: &request::Parts| {
-                        allowed_origins.contains(origin)
-                    },
-                ));
-
-            self.router = self.router.layer(cors);
+            let cors = CorsLayer::new().allow_methods([
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ]);
+
+            // Browsers reject credentialed responses for a wildcard origin, so `*` disables cookies
+            let cors = if allowed_origins.allows_any() {
+                tracing::warn!(
+                    "CORS is configured to allow ANY origin (\"*\"): credentials are disabled, \
+                     auth cookies will not be sent on cross-origin requests. \
+                     Do not use this in production."
+                );
+                cors.allow_credentials(false)
+                    .allow_origin(AllowOrigin::any())
+            } else {
+                cors.allow_credentials(true)
+                    .allow_origin(AllowOrigin::predicate(
+                        move |origin: &HeaderValue, _request_parts: &request::Parts| {
+                            allowed_origins.contains(origin)
+                        },
+                    ))
+            };
+
+            service.router = service.router.layer(cors);
         }
-        self.with_trace_layer().router
+        service.with_trace_layer().router
     }
 
     /// Run the auth service as a standalone server
@@ -141,7 +760,7 @@ impl AuthService {
         tracing::info!("Auth service listening on {}", listener.local_addr()?);
 
         axum_server::Server::<std::net::SocketAddr>::from_listener(listener)
-            .serve(router.into_make_service())
+            .serve(router.into_make_service_with_connect_info::<std::net::SocketAddr>())
             .await
     }
 }
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use crate::openapi::{OPENAPI_PATH, RouteSpec};
+
+/// Path the capabilities are served at when enabled, relative to the route prefix
+pub const CAPABILITIES_PATH: &str = "/capabilities";
+
+/// Features a deployment offers, so a frontend can hide what isn't available
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    pub registration: bool,
+    pub two_fa: bool,
+    pub two_fa_settings: bool,
+    pub backup_codes: bool,
+    pub change_password: bool,
+    /// `/verify-password`, for confirming the user's identity without elevating
+    pub password_confirmation: bool,
+    pub delete_account: bool,
+    pub elevation: bool,
+    pub openapi: bool,
+    pub readiness: bool,
+}
+
+impl Capabilities {
+    /// Derive the capabilities from the mounted routes, relative to the prefix
+    pub fn from_routes(routes: &[RouteSpec]) -> Self {
+        let mounted = |path: &str| routes.iter().any(|route| route.path == path);
+        Self {
+            registration: mounted("/signup"),
+            two_fa: mounted("/verify-2fa"),
+            two_fa_settings: mounted("/2fa/settings"),
+            backup_codes: mounted("/2fa/backup-codes"),
+            change_password: mounted("/change-password"),
+            password_confirmation: mounted("/verify-password"),
+            delete_account: mounted("/delete-account"),
+            elevation: mounted("/elevate"),
+            openapi: mounted(OPENAPI_PATH),
+            readiness: mounted("/ready"),
+        }
+    }
+}
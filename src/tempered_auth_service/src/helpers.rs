@@ -1,14 +1,17 @@
 use redis::{Client, RedisResult};
 use secrecy::ExposeSecret;
-use sqlx::PgPool;
+use sqlx::{
+    PgPool,
+    migrate::{MigrateError, Migrator},
+};
 use tempered_adapters::{
-    config::AuthServiceSetting, persistence::postgres_user_store::get_postgres_pool,
+    config::AuthServiceSetting, persistence::postgres_user_store::get_postgres_pool_with_options,
 };
 
 /// Configure and return a PostgreSQL connection pool
 ///
-/// This function loads the database URL from configuration, creates a connection pool,
-/// and runs all pending migrations.
+/// This function loads the database URL and pool settings from configuration, creates a
+/// connection pool, and runs all pending migrations.
 ///
 /// # Returns
 /// A configured PgPool ready for use
@@ -19,19 +22,34 @@ pub async fn configure_postgresql() -> PgPool {
     let config = AuthServiceSetting::load();
     let db_url = config.postgres.url.expose_secret();
 
-    let pg_pool = get_postgres_pool(db_url, config.postgres.max_connections)
+    let pg_pool = get_postgres_pool_with_options(db_url, config.postgres.pool_options())
         .await
         .expect("Failed to create Postgres connection pool");
 
-    // Run database migrations
-    sqlx::migrate!("./migrations")
-        .run(&pg_pool)
+    run_migrations(&pg_pool)
         .await
         .expect("Failed to run migrations");
 
     pg_pool
 }
 
+/// Migrations for the auth service's tables, embedded at compile time
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Run all pending migrations against `pool`
+///
+/// The migrations are embedded in the library, so callers don't need the
+/// migrations directory at runtime.
+///
+/// # Arguments
+/// * `pool` - Pool for the database to migrate
+///
+/// # Returns
+/// Ok(()) once the database is up to date, or the MigrateError that stopped it
+pub async fn run_migrations(pool: &PgPool) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
 /// Configure and return a Redis connection
 ///
 /// This function loads the Redis hostname from configuration and establishes a connection.
@@ -42,11 +60,11 @@ pub async fn configure_postgresql() -> PgPool {
 /// # Panics
 /// Panics if unable to connect to Redis
 pub fn configure_redis() -> redis::Connection {
-    let redis_host_name = &AuthServiceSetting::load().redis.host_name;
+    let redis_config = &AuthServiceSetting::load().redis;
 
-    get_redis_client(redis_host_name)
-        .expect("Failed to get Redis client")
-        .get_connection()
+    let client = get_redis_client(&redis_config.host_name).expect("Failed to get Redis client");
+    redis_config
+        .connect(&client)
         .expect("Failed to get Redis connection")
 }
 
@@ -1,9 +1,23 @@
 mod auth_service;
+mod capabilities;
 mod helpers;
+mod openapi;
+mod security_headers;
+mod setup;
+#[cfg(feature = "test-utils")]
+mod test_utils;
 mod tracing;
 
-pub use auth_service::AuthService;
-pub use helpers::{configure_postgresql, configure_redis, get_redis_client};
+pub use auth_service::{AuthService, DEFAULT_REQUEST_TIMEOUT};
+pub use capabilities::{CAPABILITIES_PATH, Capabilities};
+pub use helpers::{
+    MIGRATOR, configure_postgresql, configure_redis, get_redis_client, run_migrations,
+};
+pub use openapi::{OPENAPI_PATH, RouteSpec, openapi_document};
+pub use security_headers::{SecurityHeaders, SecurityHeadersLayer};
+pub use setup::SetupError;
+#[cfg(feature = "test-utils")]
+pub use test_utils::TestServer;
 
 // Re-export commonly used types
 pub use tempered_core::{BannedTokenStore, Email, EmailClient, TwoFaCodeStore, UserStore};
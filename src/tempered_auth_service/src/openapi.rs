@@ -0,0 +1,75 @@
+use axum::http::Method;
+use serde_json::{Map, Value, json};
+
+/// Path the OpenAPI document is served at when enabled, relative to the route prefix
+pub const OPENAPI_PATH: &str = "/openapi.json";
+
+/// Description of a mounted auth route, for generating API clients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteSpec {
+    pub path: String,
+    pub method: Method,
+    /// Name of the JSON request body type, if the route takes one
+    pub request_schema: Option<&'static str>,
+    /// Name of the JSON response body type, if the route returns one
+    pub response_schema: Option<&'static str>,
+}
+
+impl RouteSpec {
+    pub(crate) fn new(
+        method: Method,
+        path: &str,
+        request_schema: Option<&'static str>,
+        response_schema: Option<&'static str>,
+    ) -> Self {
+        Self {
+            path: path.to_owned(),
+            method,
+            request_schema,
+            response_schema,
+        }
+    }
+}
+
+/// Build a minimal OpenAPI 3 document for `routes`
+///
+/// Schemas are only referenced by name, their fields are described in `api_schema.yml`.
+pub fn openapi_document(routes: &[RouteSpec]) -> Value {
+    let mut paths = Map::new();
+    for route in routes {
+        let mut operation = Map::new();
+        if let Some(schema) = route.request_schema {
+            operation.insert(
+                "requestBody".to_owned(),
+                json!({ "content": { "application/json": { "schema": { "title": schema } } } }),
+            );
+        }
+        let response = match route.response_schema {
+            Some(schema) => json!({
+                "description": schema,
+                "content": { "application/json": { "schema": { "title": schema } } },
+            }),
+            None => json!({ "description": "Success" }),
+        };
+        operation.insert("responses".to_owned(), json!({ "default": response }));
+
+        let methods = paths
+            .entry(route.path.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(methods) = methods {
+            methods.insert(
+                route.method.as_str().to_lowercase(),
+                Value::Object(operation),
+            );
+        }
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Tempered auth service",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+    })
+}
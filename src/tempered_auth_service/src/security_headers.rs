@@ -0,0 +1,86 @@
+use axum::http::{
+    HeaderName, HeaderValue,
+    header::{
+        CONTENT_SECURITY_POLICY, REFERRER_POLICY, STRICT_TRANSPORT_SECURITY,
+        X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+    },
+};
+use tempered_adapters::config::SecurityHeadersConfig;
+use tower::Layer;
+use tower_http::set_header::SetResponseHeader;
+
+type SetHeader<S> = SetResponseHeader<S, Option<HeaderValue>>;
+
+/// Service produced by [`SecurityHeadersLayer`]
+pub type SecurityHeaders<S> = SetHeader<SetHeader<SetHeader<SetHeader<SetHeader<S>>>>>;
+
+/// Layer that adds `Strict-Transport-Security`, `X-Content-Type-Options`,
+/// `X-Frame-Options`, `Content-Security-Policy` and `Referrer-Policy` to responses
+///
+/// Headers a handler already set are left alone.
+#[derive(Debug, Clone)]
+pub struct SecurityHeadersLayer {
+    strict_transport_security: Option<HeaderValue>,
+    content_type_options: Option<HeaderValue>,
+    frame_options: Option<HeaderValue>,
+    content_security_policy: Option<HeaderValue>,
+    referrer_policy: Option<HeaderValue>,
+}
+
+impl SecurityHeadersLayer {
+    /// Build the layer from config. Invalid values are left out with a warning,
+    /// [`Config::validate`](tempered_adapters::config::Config::validate) reports them.
+    pub fn new(config: &SecurityHeadersConfig) -> Self {
+        let value = |name: &HeaderName, value: &Option<String>| {
+            let value = value.as_deref()?;
+            HeaderValue::from_str(value)
+                .inspect_err(|_| tracing::warn!("Ignoring invalid {name} header value {value:?}"))
+                .ok()
+        };
+
+        Self {
+            strict_transport_security: value(
+                &STRICT_TRANSPORT_SECURITY,
+                &config.strict_transport_security,
+            ),
+            content_type_options: value(&X_CONTENT_TYPE_OPTIONS, &config.content_type_options),
+            frame_options: value(&X_FRAME_OPTIONS, &config.frame_options),
+            content_security_policy: value(
+                &CONTENT_SECURITY_POLICY,
+                &config.content_security_policy,
+            ),
+            referrer_policy: value(&REFERRER_POLICY, &config.referrer_policy),
+        }
+    }
+}
+
+impl Default for SecurityHeadersLayer {
+    fn default() -> Self {
+        Self::new(&SecurityHeadersConfig::default())
+    }
+}
+
+impl<S> Layer<S> for SecurityHeadersLayer {
+    type Service = SecurityHeaders<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        let inner = SetResponseHeader::if_not_present(
+            inner,
+            STRICT_TRANSPORT_SECURITY,
+            self.strict_transport_security.clone(),
+        );
+        let inner = SetResponseHeader::if_not_present(
+            inner,
+            X_CONTENT_TYPE_OPTIONS,
+            self.content_type_options.clone(),
+        );
+        let inner =
+            SetResponseHeader::if_not_present(inner, X_FRAME_OPTIONS, self.frame_options.clone());
+        let inner = SetResponseHeader::if_not_present(
+            inner,
+            CONTENT_SECURITY_POLICY,
+            self.content_security_policy.clone(),
+        );
+        SetResponseHeader::if_not_present(inner, REFERRER_POLICY, self.referrer_policy.clone())
+    }
+}
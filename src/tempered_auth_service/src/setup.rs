@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use secrecy::{ExposeSecret, Secret};
+use tempered_adapters::{
+    auth::{TracingAuditSink, create_two_fa_code_hasher},
+    config::{AuthServiceSetting, Config, SecurityFinding, settings::CONFIG},
+    email::{PostmarkEmailClient, SpawningEmailClient},
+    persistence::{
+        PasswordHashingPool, PostgresApiKeyStore, PostgresUserStore, RedisBannedTokenStore,
+        RedisRateLimitStore, RedisTwoFaCodeStore,
+        postgres_user_store::get_postgres_pool_with_options,
+    },
+};
+use tempered_core::{Email, NoopRegistrationHook, UserError};
+use tokio::sync::RwLock;
+
+use crate::{AuthService, SecurityHeadersLayer, get_redis_client, run_migrations};
+
+/// Error types for building an AuthService from configuration
+#[derive(Debug, thiserror::Error)]
+pub enum SetupError {
+    #[error("Failed to create Postgres connection pool: {0}")]
+    Postgres(#[from] sqlx::Error),
+    #[error("Failed to run migrations: {0}")]
+    Migration(#[from] sqlx::migrate::MigrateError),
+    #[error("Failed to connect to Redis: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("Invalid email sender: {0}")]
+    InvalidSender(#[from] UserError),
+    #[error("Failed to build HTTP client: {0}")]
+    HttpClient(#[from] reqwest::Error),
+    #[error("Insecure configuration with auth.security.strict enabled: {0:?}")]
+    InsecureConfig(Vec<SecurityFinding>),
+}
+
+impl AuthService {
+    /// Create an AuthService backed by Postgres, Redis and Postmark as described by `config`
+    ///
+    /// Connects to Postgres and runs pending migrations, connects to Redis and builds
+    /// the Postmark email client. `config` replaces the loaded configuration the routes
+    /// read. `/admin/force-logout` and `/metrics` are only mounted when enabled under
+    /// `auth.admin`, they authenticate with API keys stored in Postgres. Use
+    /// [`AuthService::new`] to plug in custom stores.
+    ///
+    /// # Arguments
+    /// * `config` - Service configuration, usually `Config::new()?`
+    /// * `assets_dir` - Directory with the static assets to serve
+    ///
+    /// # Returns
+    /// A ready AuthService, or SetupError if any backend can't be set up
+    pub async fn from_config(config: Config, assets_dir: String) -> Result<Self, SetupError> {
+        if config.auth.security.strict {
+            let findings = config.security_lint();
+            if !findings.is_empty() {
+                return Err(SetupError::InsecureConfig(findings));
+            }
+        }
+        CONFIG.store(Arc::new(config));
+        let config = AuthServiceSetting::load();
+
+        let pg_pool = get_postgres_pool_with_options(
+            config.postgres.url.expose_secret(),
+            config.postgres.pool_options(),
+        )
+        .await?;
+        run_migrations(&pg_pool).await?;
+
+        let redis_client = get_redis_client(&config.redis.host_name)?;
+        let redis_connection = Arc::new(RwLock::new(config.redis.connect(&redis_client)?));
+        // Fallback for bans on tokens whose expiry can't be read
+        let ban_ttl = config
+            .auth
+            .jwt
+            .time_to_live
+            .max(config.auth.elevated_jwt.time_to_live)
+            .max(0) as u64;
+
+        let sender = Email::try_from(Secret::new(config.email_client.sender.clone()))?;
+        let http_client = reqwest::Client::builder()
+            .timeout(config.email_client.timeout_in_millis)
+            .build()?;
+        let email_client = PostmarkEmailClient::new(
+            config.email_client.base_url.clone(),
+            sender,
+            config.email_client.auth_token.clone(),
+            http_client,
+        )
+        .with_two_fa_template(config.email_client.templates.two_fa.postmark_template_id);
+        let email_client = SpawningEmailClient::new(
+            email_client,
+            config.email_client.background_queue_size,
+            TracingAuditSink,
+        );
+
+        let api_key_store = PostgresApiKeyStore::new(pg_pool.clone());
+        let mut user_store = PostgresUserStore::new(pg_pool);
+        if config.auth.password_hashing.threads > 0 {
+            user_store = user_store.with_hashing_pool(PasswordHashingPool::new(
+                config.auth.password_hashing.threads,
+            ));
+        }
+        let banned_token_store =
+            RedisBannedTokenStore::with_client(redis_connection.clone(), redis_client, ban_ttl);
+
+        let mut service = Self::new_with_rate_limit_store(
+            user_store.clone(),
+            banned_token_store.clone(),
+            RedisTwoFaCodeStore::with_ttl(
+                redis_connection.clone(),
+                config.auth.two_fa.time_to_live_in_seconds.max(1) as u64,
+            )
+            .with_max_attempts(config.auth.two_fa.max_stored_attempts())
+            .with_code_hasher(create_two_fa_code_hasher(&config))
+            .with_expired_attempt_grace(config.auth.two_fa.expired_attempt_grace_in_seconds),
+            email_client,
+            NoopRegistrationHook,
+            RedisRateLimitStore::new(redis_connection),
+            assets_dir,
+        )
+        .with_password_hashing_limit(
+            Some(config.auth.password_hashing.max_concurrent_requests).filter(|&max| max > 0),
+        )
+        .with_security_headers(Some(SecurityHeadersLayer::new(
+            &config.auth.security_headers,
+        )))
+        .with_readiness_probe("postgres", user_store.clone())
+        .with_readiness_probe("redis", banned_token_store.clone());
+
+        if config.auth.admin.force_logout {
+            service = service.with_admin_routes(
+                user_store,
+                banned_token_store.clone(),
+                api_key_store.clone(),
+            );
+        }
+        if config.auth.admin.metrics {
+            service = service.with_metrics(banned_token_store, api_key_store);
+        }
+        Ok(service)
+    }
+}
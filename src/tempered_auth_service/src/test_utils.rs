@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use reqwest::cookie::Jar;
+use tempered_adapters::{
+    email::MockEmailClient,
+    persistence::{HashMapTwoFaCodeStore, HashMapUserStore, HashSetBannedTokenStore},
+};
+use tokio::net::TcpListener;
+
+use crate::AuthService;
+
+/// An AuthService with in-memory stores running on an ephemeral local port
+///
+/// The stores are shared with the running service, so tests can inspect them,
+/// e.g. to read the 2FA code of a login attempt.
+pub struct TestServer {
+    /// Base URL of the service, e.g. `http://127.0.0.1:54321`
+    pub address: String,
+    /// Client that keeps cookies between requests
+    pub client: reqwest::Client,
+    pub cookie_jar: Arc<Jar>,
+    pub user_store: HashMapUserStore,
+    pub banned_token_store: HashSetBannedTokenStore,
+    pub two_fa_code_store: HashMapTwoFaCodeStore,
+}
+
+impl TestServer {
+    /// Build the service, bind it to `127.0.0.1:0` and start serving in the background
+    ///
+    /// # Panics
+    /// Panics if the listener cannot be bound or the HTTP client cannot be built
+    pub async fn spawn() -> Self {
+        let user_store = HashMapUserStore::default();
+        let banned_token_store = HashSetBannedTokenStore::default();
+        let two_fa_code_store = HashMapTwoFaCodeStore::default();
+
+        let app = AuthService::new(
+            user_store.clone(),
+            banned_token_store.clone(),
+            two_fa_code_store.clone(),
+            MockEmailClient,
+            "./assets".to_string(),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind to address");
+        let address = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async {
+            app.run_standalone(listener, None)
+                .await
+                .expect("Failed to run auth-service")
+        });
+
+        let cookie_jar = Arc::new(Jar::default());
+        let client = reqwest::Client::builder()
+            .cookie_provider(cookie_jar.clone())
+            .build()
+            .expect("Failed to build client");
+
+        Self {
+            address,
+            client,
+            cookie_jar,
+            user_store,
+            banned_token_store,
+            two_fa_code_store,
+        }
+    }
+
+    /// Absolute URL for a path on the service, e.g. `url("/login")`
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.address, path)
+    }
+}
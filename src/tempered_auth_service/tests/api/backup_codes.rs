@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use reqwest::cookie::Jar;
+use tempered_adapters::{
+    email::MockEmailClient,
+    http::routes::{BackupCodesResponse, TwoFactorAuthResponse},
+    persistence::{
+        HashMapBackupCodeStore, HashMapTwoFaCodeStore, HashMapUserStore, HashSetBannedTokenStore,
+    },
+};
+use tempered_auth_service::AuthService;
+
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn backup_code_completes_2fa_login_once() {
+    let banned_token_store = HashSetBannedTokenStore::default();
+    let user_store = HashMapUserStore::default();
+    let app = AuthService::new(
+        user_store.clone(),
+        banned_token_store.clone(),
+        HashMapTwoFaCodeStore::default(),
+        MockEmailClient,
+        "./assets".to_string(),
+    )
+    .with_backup_codes(
+        user_store,
+        HashMapBackupCodeStore::default(),
+        banned_token_store,
+    );
+    let address = spawn_app(app).await;
+    let client = reqwest::Client::builder()
+        .cookie_provider(Arc::new(Jar::default()))
+        .build()
+        .expect("Failed to build client");
+    let post = |path: &str, body: serde_json::Value| {
+        client.post(format!("{address}{path}")).json(&body).send()
+    };
+
+    let credentials = serde_json::json!({
+        "email": "backup@example.com",
+        "password": "passwordpassword",
+        "requires2FA": false,
+    });
+    for path in ["/signup", "/login"] {
+        let response = post(path, credentials.clone()).await.unwrap();
+        assert!(response.status().is_success());
+    }
+    let mut elevate = credentials.clone();
+    elevate["scope"] = "two_fa_settings".into();
+    assert_eq!(
+        post("/elevate", elevate).await.unwrap().status().as_u16(),
+        200
+    );
+
+    let response = post("/2fa/backup-codes", serde_json::json!({}))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let codes = response.json::<BackupCodesResponse>().await.unwrap().codes;
+    assert_eq!(codes.len(), 10);
+
+    let response = post("/2fa/settings", serde_json::json!({ "requires2FA": true }))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = post("/login", credentials.clone()).await.unwrap();
+    assert_eq!(response.status().as_u16(), 206);
+    let attempt = response.json::<TwoFactorAuthResponse>().await.unwrap();
+
+    let verify = serde_json::json!({
+        "email": "backup@example.com",
+        "loginAttemptId": attempt.attempt_id,
+        "backupCode": codes[0].to_uppercase(),
+    });
+    let response = post("/verify-2fa", verify.clone()).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = post("/login", credentials).await.unwrap();
+    let attempt = response.json::<TwoFactorAuthResponse>().await.unwrap();
+    let mut verify = verify;
+    verify["loginAttemptId"] = attempt.attempt_id.into();
+    let response = post("/verify-2fa", verify).await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
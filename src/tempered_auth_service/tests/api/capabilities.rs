@@ -0,0 +1,45 @@
+use tempered_adapters::email::MockEmailClient;
+use tempered_auth_service::Capabilities;
+
+use crate::helpers::{in_memory_app, spawn_app};
+
+#[test]
+fn capabilities_reflect_enabled_routes() {
+    let capabilities = in_memory_app(MockEmailClient).capabilities();
+    assert!(capabilities.registration);
+    assert!(capabilities.two_fa);
+    assert!(!capabilities.openapi);
+    assert!(
+        !in_memory_app(MockEmailClient)
+            .route_specs()
+            .iter()
+            .any(|route| route.path == "/capabilities")
+    );
+
+    let capabilities = in_memory_app(MockEmailClient)
+        .with_openapi(true)
+        .capabilities();
+    assert!(capabilities.openapi);
+}
+
+#[tokio::test]
+async fn capabilities_are_served_when_enabled() {
+    let address = spawn_app(
+        in_memory_app(MockEmailClient)
+            .with_prefix("/auth")
+            .with_capabilities(true),
+    )
+    .await;
+    let response = reqwest::get(format!("{address}/auth/capabilities"))
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["registration"], true);
+    assert_eq!(body["twoFa"], true);
+    assert_eq!(body["readiness"], false);
+
+    let capabilities: Capabilities = serde_json::from_value(body).unwrap();
+    assert!(capabilities.delete_account);
+}
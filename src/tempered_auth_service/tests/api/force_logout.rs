@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use reqwest::cookie::Jar;
+use secrecy::{ExposeSecret, Secret};
+use tempered_adapters::{
+    email::MockEmailClient,
+    persistence::{
+        HashMapApiKeyStore, HashMapTwoFaCodeStore, HashMapUserStore, HashSetBannedTokenStore,
+    },
+};
+use tempered_application::IssueApiKeyUseCase;
+use tempered_auth_service::AuthService;
+use tempered_core::Email;
+
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn force_logout_revokes_the_users_tokens() {
+    let banned_token_store = HashSetBannedTokenStore::default();
+    let api_key_store = HashMapApiKeyStore::default();
+    let admin = Email::try_from(Secret::from("admin@example.com".to_string())).unwrap();
+    let (admin_key, _) = IssueApiKeyUseCase::new(api_key_store.clone())
+        .execute(admin.clone(), vec!["admin".to_owned()])
+        .await
+        .unwrap();
+    let (user_key, _) = IssueApiKeyUseCase::new(api_key_store.clone())
+        .execute(admin, Vec::new())
+        .await
+        .unwrap();
+
+    let user_store = HashMapUserStore::default();
+    let app = AuthService::new(
+        user_store.clone(),
+        banned_token_store.clone(),
+        HashMapTwoFaCodeStore::default(),
+        MockEmailClient,
+        "./assets".to_string(),
+    )
+    .with_admin_routes(user_store, banned_token_store, api_key_store);
+    let address = spawn_app(app).await;
+    let client = reqwest::Client::builder()
+        .cookie_provider(Arc::new(Jar::default()))
+        .build()
+        .expect("Failed to build client");
+
+    let body = serde_json::json!({
+        "email": "forced@example.com",
+        "password": "passwordpassword",
+        "requires2FA": false,
+    });
+    client
+        .post(format!("{address}/signup"))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request");
+    client
+        .post(format!("{address}/login"))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request");
+    let response = client
+        .get(format!("{address}/session"))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let force_logout = |key: Option<&str>| {
+        let request = reqwest::Client::new()
+            .post(format!("{address}/admin/force-logout"))
+            .json(&serde_json::json!({ "email": "forced@example.com" }));
+        match key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+        .send()
+    };
+    assert_eq!(force_logout(None).await.unwrap().status().as_u16(), 400);
+    let response = reqwest::Client::new()
+        .post(format!("{address}/admin/force-logout"))
+        .header("Authorization", "Basic dXNlcjpwYXNz")
+        .json(&serde_json::json!({ "email": "forced@example.com" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    assert!(
+        response
+            .text()
+            .await
+            .unwrap()
+            .contains("Unsupported authorization scheme")
+    );
+    assert_eq!(
+        force_logout(Some(user_key.expose_secret()))
+            .await
+            .unwrap()
+            .status()
+            .as_u16(),
+        403
+    );
+    assert_eq!(
+        force_logout(Some(admin_key.expose_secret()))
+            .await
+            .unwrap()
+            .status()
+            .as_u16(),
+        204
+    );
+
+    let response = client
+        .get(format!("{address}/session"))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 401);
+}
@@ -12,12 +12,12 @@ use tempered_adapters::{
     config::test,
     email::PostmarkEmailClient,
     persistence::{
-        PostgresUserStore, RedisBannedTokenStore, RedisTwoFaCodeStore,
-        postgres_user_store::get_postgres_pool,
+        HashMapTwoFaCodeStore, HashMapUserStore, HashSetBannedTokenStore, PostgresUserStore,
+        RedisBannedTokenStore, RedisTwoFaCodeStore, postgres_user_store::get_postgres_pool,
     },
 };
-use tempered_auth_service::AuthService;
-use tempered_core::{Email, TwoFaAttemptId};
+use tempered_auth_service::{AuthService, run_migrations};
+use tempered_core::{Email, EmailClient, TwoFaAttemptId};
 use testcontainers_modules::{
     postgres,
     redis::Redis,
@@ -70,7 +70,7 @@ impl TestApp {
             "./assets".to_string(),
         );
 
-        let _ = tokio::spawn(async {
+        tokio::spawn(async {
             app.run_standalone(listener, None)
                 .await
                 .expect("Failed to run auth-service")
@@ -141,15 +141,12 @@ impl TestApp {
             .split(';')
             .map(|c| c.trim())
             .find(|c| c.starts_with(cookie_name))
-            .and_then(|c| {
-                c.split_once('=')
-                    .and_then(|(_, token)| Some(token.to_owned()))
-            })
+            .and_then(|c| c.split_once('=').map(|(_, token)| token.to_owned()))
     }
 
     pub async fn get_root(&self) -> reqwest::Response {
         self.http_client
-            .get(&format!("{}/", &self.address))
+            .get(format!("{}/", &self.address))
             .send()
             .await
             .expect("Failed to execute request")
@@ -160,7 +157,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/signup", &self.address))
+            .post(format!("{}/signup", &self.address))
             .json(body)
             .send()
             .await
@@ -169,7 +166,7 @@ impl TestApp {
 
     pub async fn login<Body: Serialize>(&self, body: &Body) -> reqwest::Response {
         self.http_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
             .json(body)
             .send()
             .await
@@ -178,7 +175,7 @@ impl TestApp {
 
     pub async fn logout(&self) -> reqwest::Response {
         self.http_client
-            .post(&format!("{}/logout", &self.address))
+            .post(format!("{}/logout", &self.address))
             .send()
             .await
             .expect("Failed to execute request")
@@ -186,7 +183,7 @@ impl TestApp {
 
     pub async fn verify_2fa<Body: Serialize>(&self, body: &Body) -> reqwest::Response {
         self.http_client
-            .post(&format!("{}/verify-2fa", &self.address))
+            .post(format!("{}/verify-2fa", &self.address))
             .json(body)
             .send()
             .await
@@ -195,7 +192,7 @@ impl TestApp {
 
     pub async fn verify_token<Body: Serialize>(&self, token: &Body) -> reqwest::Response {
         self.http_client
-            .post(&format!("{}/verify-token", &self.address))
+            .post(format!("{}/verify-token", &self.address))
             .json(token)
             .send()
             .await
@@ -204,7 +201,7 @@ impl TestApp {
 
     pub async fn verify_elevated_token<Body: Serialize>(&self, token: &Body) -> reqwest::Response {
         self.http_client
-            .post(&format!("{}/verify-elevated-token", &self.address))
+            .post(format!("{}/verify-elevated-token", &self.address))
             .json(token)
             .send()
             .await
@@ -213,7 +210,7 @@ impl TestApp {
 
     pub async fn delete_account(&self) -> reqwest::Response {
         self.http_client
-            .delete(&format!("{}/delete-account", &self.address))
+            .delete(format!("{}/delete-account", &self.address))
             .send()
             .await
             .expect("Failed to execute request")
@@ -221,7 +218,7 @@ impl TestApp {
 
     pub async fn post_elevate<Body: Serialize>(&self, body: &Body) -> reqwest::Response {
         self.http_client
-            .post(&format!("{}/elevate", &self.address))
+            .post(format!("{}/elevate", &self.address))
             .json(body)
             .send()
             .await
@@ -230,7 +227,7 @@ impl TestApp {
 
     pub async fn post_change_password<Body: Serialize>(&self, body: &Body) -> reqwest::Response {
         self.http_client
-            .post(&format!("{}/change-password", &self.address))
+            .post(format!("{}/change-password", &self.address))
             .json(body)
             .send()
             .await
@@ -247,7 +244,7 @@ impl TestApp {
             .received_requests()
             .await
             .expect("Request recording disabled")
-            .get(0)
+            .first()
             .expect("No email received")
             .body
             .clone();
@@ -264,6 +261,36 @@ impl TestApp {
     }
 }
 
+/// Run an AuthService on a random local port and return its address
+pub async fn spawn_app(app: AuthService) -> String {
+    let listener = TcpListener::bind(test::APP_ADDRESS)
+        .await
+        .expect("Failed to bind to address");
+    let address = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async {
+        app.run_standalone(listener, None)
+            .await
+            .expect("Failed to run auth-service")
+    });
+
+    address
+}
+
+/// An AuthService backed by in-memory stores, for tests that don't need containers
+pub fn in_memory_app<E>(email_client: E) -> AuthService
+where
+    E: EmailClient + Clone + 'static,
+{
+    AuthService::new(
+        HashMapUserStore::default(),
+        HashSetBannedTokenStore::default(),
+        HashMapTwoFaCodeStore::default(),
+        email_client,
+        "./assets".to_string(),
+    )
+}
+
 pub fn get_random_email() -> String {
     format!("{}@example.com", Uuid::new_v4())
 }
@@ -276,6 +303,13 @@ pub fn get_standard_test_user(two_fa: bool) -> Value {
     })
 }
 
+/// `body` with the elevation `scope` added, for `/elevate`
+pub fn with_scope(body: &Value, scope: &str) -> Value {
+    let mut body = body.clone();
+    body["scope"] = scope.into();
+    body
+}
+
 fn configure_postmark_email_client(base_url: String) -> PostmarkEmailClient {
     let postmark_auth_token = Secret::new("auth_token".to_owned());
 
@@ -377,8 +411,7 @@ async fn setup_and_connect_user_store_container() -> (ContainerAsync<postgres::P
         .await
         .expect("Failed to connect to database");
 
-    sqlx::migrate!()
-        .run(&connection)
+    run_migrations(&connection)
         .await
         .expect("Failed to migrate the database");
 
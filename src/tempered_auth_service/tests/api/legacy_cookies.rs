@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use secrecy::{ExposeSecret, Secret};
+use tempered_adapters::{
+    config::{AuthServiceSetting, Config, JWT_COOKIE_NAME, settings::CONFIG},
+    email::MockEmailClient,
+    persistence::{HashMapTwoFaCodeStore, HashMapUserStore, HashSetBannedTokenStore},
+};
+use tempered_application::{LocalJwtIssuer, TokenIssuer, TokenType, token_ban_key};
+use tempered_auth_service::AuthService;
+use tempered_core::{BannedTokenStore, User, UserStore};
+
+use crate::helpers::spawn_app;
+
+const LEGACY_COOKIE_NAME: &str = "jwt_legacy";
+
+/// Accept `LEGACY_COOKIE_NAME` as a former name of the auth cookie. Other tests
+/// never send it, so they are unaffected by the changed config.
+fn enable_legacy_cookie_name() {
+    let mut config = Config::new().expect("Failed to load config");
+    config.auth.jwt.legacy_cookie_names = vec![LEGACY_COOKIE_NAME.to_owned()];
+    CONFIG.store(Arc::new(config));
+}
+
+/// Add a user with `email` to `user_store` and issue an access token for them
+async fn issue_token(user_store: &HashMapUserStore, email: &str, time_to_live: i64) -> String {
+    let config = AuthServiceSetting::load();
+    let user = User::parse(
+        Secret::from(email.to_owned()),
+        Secret::from("passwordpassword".to_owned()),
+        false,
+    )
+    .unwrap();
+    let user_id = *user.id();
+    user_store.add_user(user).await.unwrap();
+
+    LocalJwtIssuer::new(config.auth.jwt.secret.expose_secret().as_bytes())
+        .issue(
+            &user_id,
+            time_to_live,
+            TokenType::Access,
+            None,
+            Default::default(),
+        )
+        .await
+        .expect("Failed to issue token")
+}
+
+async fn get_session(address: &str, token: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{address}/session"))
+        .header("Cookie", format!("{LEGACY_COOKIE_NAME}={token}"))
+        .send()
+        .await
+        .expect("Failed to execute request")
+}
+
+fn set_cookies(response: &reqwest::Response) -> Vec<String> {
+    response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|value| value.to_str().unwrap().to_owned())
+        .collect()
+}
+
+fn app(user_store: HashMapUserStore, banned_token_store: HashSetBannedTokenStore) -> AuthService {
+    AuthService::new(
+        user_store,
+        banned_token_store,
+        HashMapTwoFaCodeStore::default(),
+        MockEmailClient,
+        "./assets".to_string(),
+    )
+}
+
+#[tokio::test]
+async fn legacy_cookie_is_moved_to_the_current_name() {
+    enable_legacy_cookie_name();
+    let user_store = HashMapUserStore::default();
+    let address = spawn_app(app(user_store.clone(), HashSetBannedTokenStore::default())).await;
+    let token = issue_token(&user_store, "legacy@example.com", 600).await;
+
+    let response = get_session(&address, &token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let cookies = set_cookies(&response);
+    let current = format!("{}={token};", *JWT_COOKIE_NAME);
+    assert!(cookies.iter().any(|cookie| cookie.starts_with(&current)));
+    assert!(cookies.iter().any(|cookie| {
+        cookie.starts_with(&format!("{LEGACY_COOKIE_NAME}=;")) && cookie.contains("Max-Age=0")
+    }));
+}
+
+#[tokio::test]
+async fn rejected_legacy_cookie_is_left_alone() {
+    enable_legacy_cookie_name();
+    let user_store = HashMapUserStore::default();
+    let banned_token_store = HashSetBannedTokenStore::default();
+    let address = spawn_app(app(user_store.clone(), banned_token_store.clone())).await;
+    let token = issue_token(&user_store, "banned-legacy@example.com", 600).await;
+    banned_token_store
+        .ban_token(token_ban_key(&token))
+        .await
+        .unwrap();
+
+    let response = get_session(&address, &token).await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert!(
+        set_cookies(&response)
+            .iter()
+            .all(|cookie| !cookie.starts_with(LEGACY_COOKIE_NAME)
+                && !cookie.starts_with(&format!("{}={token}", *JWT_COOKIE_NAME)))
+    );
+}
+
+#[tokio::test]
+async fn cookie_set_by_the_handler_is_kept() {
+    enable_legacy_cookie_name();
+    let user_store = HashMapUserStore::default();
+    let address = spawn_app(app(user_store.clone(), HashSetBannedTokenStore::default())).await;
+    // Inside the renewal window, so /session sets a new cookie itself
+    let token = issue_token(&user_store, "renewed-legacy@example.com", 60).await;
+
+    let response = get_session(&address, &token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let cookies = set_cookies(&response);
+    let current: Vec<_> = cookies
+        .iter()
+        .filter(|cookie| cookie.starts_with(&format!("{}=", *JWT_COOKIE_NAME)))
+        .collect();
+    assert_eq!(current.len(), 1);
+    assert!(!current[0].contains(&token));
+    assert!(
+        cookies
+            .iter()
+            .any(|cookie| cookie.starts_with(&format!("{LEGACY_COOKIE_NAME}=;")))
+    );
+}
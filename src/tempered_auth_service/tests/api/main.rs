@@ -1,2 +1,20 @@
+mod backup_codes;
+mod capabilities;
+mod force_logout;
 mod helpers;
+mod legacy_cookies;
+mod me;
+mod metrics;
+mod openapi;
+mod password_hashing_limit;
+mod password_strength;
+mod prefix;
+mod ready;
+mod resend_2fa_code;
+mod security_headers;
+mod session;
+mod signup;
+mod test_server;
+mod timeout;
 mod user_api;
+mod verify_password;
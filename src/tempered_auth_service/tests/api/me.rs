@@ -0,0 +1,48 @@
+use secrecy::Secret;
+use tempered_adapters::http::routes::UserProfile;
+use tempered_auth_service::TestServer;
+use tempered_core::{Email, UserStore};
+
+#[tokio::test]
+async fn me_returns_profile_and_401_once_user_is_deleted() {
+    let server = TestServer::spawn().await;
+
+    let body = serde_json::json!({
+        "email": "me@example.com",
+        "password": "passwordpassword",
+        "requires2FA": false,
+    });
+    for path in ["/signup", "/login"] {
+        let response = server
+            .client
+            .post(server.url(path))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request");
+        assert!(response.status().is_success());
+    }
+
+    let response = server
+        .client
+        .get(server.url("/me"))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let profile = response.json::<UserProfile>().await.unwrap();
+    assert_eq!(profile.email, "me@example.com");
+    assert!(!profile.requires_2fa);
+
+    let email = Email::try_from(Secret::from("me@example.com".to_owned())).unwrap();
+    server.user_store.delete_user(&email).await.unwrap();
+
+    let response = server
+        .client
+        .get(server.url("/me"))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 401);
+}
@@ -0,0 +1,84 @@
+use secrecy::{ExposeSecret, Secret};
+use tempered_adapters::{
+    email::MockEmailClient,
+    persistence::{
+        HashMapApiKeyStore, HashMapTwoFaCodeStore, HashMapUserStore, HashSetBannedTokenStore,
+    },
+};
+use tempered_application::IssueApiKeyUseCase;
+use tempered_auth_service::AuthService;
+use tempered_core::{BannedTokenStore, Email};
+
+use crate::helpers::spawn_app;
+
+fn app(banned_token_store: HashSetBannedTokenStore) -> AuthService {
+    AuthService::new(
+        HashMapUserStore::default(),
+        banned_token_store,
+        HashMapTwoFaCodeStore::default(),
+        MockEmailClient,
+        "./assets".to_string(),
+    )
+}
+
+#[tokio::test]
+async fn metrics_is_not_mounted_by_default() {
+    let app = app(HashSetBannedTokenStore::default());
+    assert!(
+        !app.route_specs()
+            .iter()
+            .any(|route| route.path == "/metrics")
+    );
+    let address = spawn_app(app).await;
+
+    // Unknown paths fall through to the static assets
+    let response = reqwest::get(format!("{address}/metrics"))
+        .await
+        .expect("Failed to execute request");
+    assert!(!response.text().await.unwrap().contains("bannedTokens"));
+}
+
+#[tokio::test]
+async fn metrics_requires_an_admin_api_key() {
+    let banned_token_store = HashSetBannedTokenStore::default();
+    banned_token_store
+        .ban_token("some-token".to_string())
+        .await
+        .unwrap();
+    let api_key_store = HashMapApiKeyStore::default();
+    let admin = Email::try_from(Secret::from("admin@example.com".to_string())).unwrap();
+    let (admin_key, _) = IssueApiKeyUseCase::new(api_key_store.clone())
+        .execute(admin.clone(), vec!["admin".to_owned()])
+        .await
+        .unwrap();
+    let (user_key, _) = IssueApiKeyUseCase::new(api_key_store.clone())
+        .execute(admin, Vec::new())
+        .await
+        .unwrap();
+
+    let address =
+        spawn_app(app(banned_token_store.clone()).with_metrics(banned_token_store, api_key_store))
+            .await;
+    let metrics = |key: Option<&str>| {
+        let request = reqwest::Client::new().get(format!("{address}/metrics"));
+        match key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+        .send()
+    };
+
+    assert_eq!(metrics(None).await.unwrap().status().as_u16(), 400);
+    assert_eq!(
+        metrics(Some(user_key.expose_secret()))
+            .await
+            .unwrap()
+            .status()
+            .as_u16(),
+        403
+    );
+    let response = metrics(Some(admin_key.expose_secret())).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["bannedTokens"], 1);
+}
@@ -0,0 +1,47 @@
+use axum::http::Method;
+use tempered_adapters::email::MockEmailClient;
+
+use crate::helpers::{in_memory_app, spawn_app};
+
+#[test]
+fn route_specs_reflect_prefix_and_openapi() {
+    let routes = in_memory_app(MockEmailClient)
+        .with_prefix("/auth")
+        .route_specs();
+    let signup = routes
+        .iter()
+        .find(|route| route.path == "/auth/signup")
+        .expect("signup route is listed");
+    assert_eq!(signup.method, Method::POST);
+    assert_eq!(signup.request_schema, Some("SignupRequest"));
+    assert!(
+        !routes
+            .iter()
+            .any(|route| route.path == "/auth/openapi.json")
+    );
+
+    let routes = in_memory_app(MockEmailClient)
+        .with_prefix("/auth")
+        .with_openapi(true)
+        .route_specs();
+    assert!(
+        routes
+            .iter()
+            .any(|route| route.path == "/auth/openapi.json")
+    );
+}
+
+#[tokio::test]
+async fn openapi_document_is_served_when_enabled() {
+    let address = spawn_app(in_memory_app(MockEmailClient).with_openapi(true)).await;
+
+    let response = reqwest::get(format!("{address}/openapi.json"))
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let document: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(document["openapi"], "3.0.3");
+    assert!(document["paths"]["/login"]["post"].is_object());
+    assert!(document["paths"]["/delete-account"]["delete"].is_object());
+}
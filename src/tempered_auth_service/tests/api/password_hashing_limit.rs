@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use tempered_core::{Email, EmailClient};
+
+use crate::helpers::{in_memory_app, spawn_app};
+
+#[derive(Clone)]
+struct SlowEmailClient;
+
+#[async_trait::async_trait]
+impl EmailClient for SlowEmailClient {
+    async fn send_email(
+        &self,
+        _recipient: &Email,
+        _subject: &str,
+        _content: &str,
+    ) -> Result<(), String> {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn should_return_503_when_password_hashing_limit_is_reached() {
+    let app = in_memory_app(SlowEmailClient).with_password_hashing_limit(Some(1));
+    let address = spawn_app(app).await;
+
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("{address}/signup"))
+        .json(&serde_json::json!({
+            "email": "limit@example.com",
+            "password": "passwordpassword",
+            "requires2FA": true,
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 201);
+
+    // Sending the 2FA email stalls, so this login holds the only slot
+    let login = tokio::spawn({
+        let client = client.clone();
+        let address = address.clone();
+        async move {
+            client
+                .post(format!("{address}/login"))
+                .json(&serde_json::json!({
+                    "email": "limit@example.com",
+                    "password": "passwordpassword",
+                }))
+                .send()
+                .await
+                .expect("Failed to execute request")
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let response = client
+        .post(format!("{address}/signup"))
+        .json(&serde_json::json!({
+            "email": "other@example.com",
+            "password": "passwordpassword",
+            "requires2FA": false,
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 503);
+
+    // Routes without password hashing are not limited
+    let response = client
+        .get(format!("{address}/session"))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_ne!(response.status().as_u16(), 503);
+
+    assert_eq!(login.await.unwrap().status().as_u16(), 206);
+}
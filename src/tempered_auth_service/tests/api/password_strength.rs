@@ -0,0 +1,54 @@
+use secrecy::ExposeSecret;
+use tempered_adapters::{
+    email::MockEmailClient,
+    http::error::ErrorResponse,
+    persistence::{HashMapTwoFaCodeStore, HashMapUserStore, HashSetBannedTokenStore},
+};
+use tempered_auth_service::AuthService;
+use tempered_core::{Password, PasswordStrength, PasswordStrengthEstimator};
+
+use crate::helpers::spawn_app;
+
+// Scores one point per four characters, like a very rough zxcvbn
+struct LengthEstimator;
+
+impl PasswordStrengthEstimator for LengthEstimator {
+    fn estimate(&self, password: &Password, _user_inputs: &[&str]) -> PasswordStrength {
+        PasswordStrength {
+            score: (password.as_ref().expose_secret().len() / 4).min(4) as u8,
+            suggestions: vec!["Add another word or two".to_owned()],
+        }
+    }
+}
+
+#[tokio::test]
+async fn signup_rejects_weak_password_with_suggestions() {
+    let app = AuthService::new(
+        HashMapUserStore::default(),
+        HashSetBannedTokenStore::default(),
+        HashMapTwoFaCodeStore::default(),
+        MockEmailClient,
+        "./assets".to_string(),
+    )
+    .with_password_strength_estimator(LengthEstimator, 3);
+    let address = spawn_app(app).await;
+    let client = reqwest::Client::new();
+    let signup = |password: &str| {
+        client
+            .post(format!("{address}/signup"))
+            .json(&serde_json::json!({
+                "email": "strength@example.com",
+                "password": password,
+                "requires2FA": false,
+            }))
+            .send()
+    };
+
+    let response = signup("Password1!").await.unwrap();
+    assert_eq!(response.status().as_u16(), 400);
+    let body = response.json::<ErrorResponse>().await.unwrap();
+    assert_eq!(body.suggestions, ["Add another word or two"]);
+
+    let response = signup("correct horse battery staple").await.unwrap();
+    assert_eq!(response.status().as_u16(), 201);
+}
@@ -0,0 +1,43 @@
+use tempered_adapters::email::MockEmailClient;
+
+use crate::helpers::{in_memory_app, spawn_app};
+
+#[tokio::test]
+async fn routes_are_served_under_prefix() {
+    let address = spawn_app(in_memory_app(MockEmailClient).with_prefix("/auth")).await;
+    let client = reqwest::Client::new();
+
+    let body = serde_json::json!({
+        "email": "prefix@example.com",
+        "password": "passwordpassword",
+        "requires2FA": false,
+    });
+
+    let response = client
+        .post(format!("{address}/auth/signup"))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = client
+        .post(format!("{address}/signup"))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_ne!(response.status().as_u16(), 201);
+}
+
+#[tokio::test]
+async fn assets_are_served_from_root_with_prefix() {
+    let address = spawn_app(in_memory_app(MockEmailClient).with_prefix("auth/")).await;
+
+    let response = reqwest::get(format!("{address}/"))
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers().get("content-type").unwrap(), "text/html");
+}
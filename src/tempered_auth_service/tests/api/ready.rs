@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use tempered_adapters::email::MockEmailClient;
+use tempered_core::{ProbeableStore, StoreProbe};
+
+use crate::helpers::{in_memory_app, spawn_app};
+
+struct StaticProbe(bool);
+
+#[async_trait::async_trait]
+impl ProbeableStore for StaticProbe {
+    async fn probe(&self) -> StoreProbe {
+        StoreProbe {
+            healthy: self.0,
+            latency: Duration::from_millis(5),
+        }
+    }
+}
+
+#[tokio::test]
+async fn ready_reports_each_store() {
+    let app = in_memory_app(MockEmailClient)
+        .with_readiness_probe("postgres", StaticProbe(true))
+        .with_readiness_probe("redis", StaticProbe(true));
+    let address = spawn_app(app).await;
+
+    let response = reqwest::get(format!("{address}/ready"))
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["ready"], true);
+    assert_eq!(body["stores"][0]["name"], "postgres");
+    assert_eq!(body["stores"][1]["name"], "redis");
+    assert_eq!(body["stores"][1]["latencyMs"], 5.0);
+}
+
+#[tokio::test]
+async fn ready_returns_503_when_a_store_is_unhealthy() {
+    let app = in_memory_app(MockEmailClient)
+        .with_readiness_probe("postgres", StaticProbe(true))
+        .with_readiness_probe("redis", StaticProbe(false));
+    let address = spawn_app(app).await;
+
+    let response = reqwest::get(format!("{address}/ready"))
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 503);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["ready"], false);
+    assert_eq!(body["stores"][1]["healthy"], false);
+}
@@ -0,0 +1,136 @@
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU64, Ordering},
+};
+
+use tempered_adapters::email::MockEmailClient;
+use tempered_core::{AttemptIdGenerator, Email, EmailClient, TwoFaAttemptId};
+
+use crate::helpers::{in_memory_app, spawn_app};
+
+/// Fails to send the first email, then records the content of every later one
+#[derive(Clone, Default)]
+struct FlakyEmailClient {
+    sent: Arc<Mutex<Option<Vec<String>>>>,
+}
+
+#[async_trait::async_trait]
+impl EmailClient for FlakyEmailClient {
+    async fn send_email(
+        &self,
+        _recipient: &Email,
+        _subject: &str,
+        content: &str,
+    ) -> Result<(), String> {
+        let mut sent = self.sent.lock().unwrap();
+        match sent.as_mut() {
+            Some(sent) => {
+                sent.push(content.to_owned());
+                Ok(())
+            }
+            None => {
+                *sent = Some(Vec::new());
+                Err("email service unavailable".to_string())
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn resend_replaces_attempt_whose_code_was_not_delivered() {
+    let email_client = FlakyEmailClient::default();
+    let address = spawn_app(in_memory_app(email_client.clone())).await;
+    let client = reqwest::Client::new();
+    let post = |path: &str, body: serde_json::Value| {
+        client.post(format!("{address}{path}")).json(&body).send()
+    };
+
+    let credentials = serde_json::json!({
+        "email": "resend@example.com",
+        "password": "passwordpassword",
+        "requires2FA": true,
+    });
+    let response = post("/signup", credentials.clone()).await.unwrap();
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = post("/login", credentials).await.unwrap();
+    assert_eq!(response.status().as_u16(), 503);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let first_attempt_id = body["loginAttemptId"].as_str().unwrap().to_owned();
+
+    let resend = serde_json::json!({
+        "email": "resend@example.com",
+        "loginAttemptId": first_attempt_id,
+    });
+    let response = post("/resend-2fa-code", resend.clone()).await.unwrap();
+    assert_eq!(response.status().as_u16(), 206);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let attempt_id = body["loginAttemptId"].as_str().unwrap().to_owned();
+    assert_ne!(attempt_id, first_attempt_id);
+
+    // The replaced attempt can't be resent again, stores report it as used or unknown
+    let response = post("/resend-2fa-code", resend).await.unwrap();
+    assert!(response.status().is_client_error());
+
+    let code = email_client.sent.lock().unwrap().as_ref().unwrap()[0].clone();
+    let response = post(
+        "/verify-2fa",
+        serde_json::json!({
+            "email": "resend@example.com",
+            "loginAttemptId": attempt_id,
+            "2FACode": code,
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+/// Numbers attempts from 1, to tell its IDs apart from random ones
+#[derive(Default)]
+struct CountingAttemptIdGenerator {
+    generated: AtomicU64,
+}
+
+impl AttemptIdGenerator for CountingAttemptIdGenerator {
+    fn generate(&self, _email: &Email) -> TwoFaAttemptId {
+        let n = self.generated.fetch_add(1, Ordering::SeqCst) + 1;
+        TwoFaAttemptId::from(uuid::Uuid::from_u128(n.into()))
+    }
+}
+
+#[tokio::test]
+async fn login_and_resend_use_the_configured_attempt_id_generator() {
+    let app = in_memory_app(MockEmailClient)
+        .with_attempt_id_generator(CountingAttemptIdGenerator::default());
+    let address = spawn_app(app).await;
+    let client = reqwest::Client::new();
+    let post = |path: &str, body: serde_json::Value| {
+        client.post(format!("{address}{path}")).json(&body).send()
+    };
+
+    let credentials = serde_json::json!({
+        "email": "counted@example.com",
+        "password": "passwordpassword",
+        "requires2FA": true,
+    });
+    post("/signup", credentials.clone()).await.unwrap();
+
+    let response = post("/login", credentials).await.unwrap();
+    assert_eq!(response.status().as_u16(), 206);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["loginAttemptId"], uuid::Uuid::from_u128(1).to_string());
+
+    let response = post(
+        "/resend-2fa-code",
+        serde_json::json!({
+            "email": "counted@example.com",
+            "loginAttemptId": body["loginAttemptId"],
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status().as_u16(), 206);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["loginAttemptId"], uuid::Uuid::from_u128(2).to_string());
+}
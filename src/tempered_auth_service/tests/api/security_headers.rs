@@ -0,0 +1,48 @@
+use tempered_adapters::{config::SecurityHeadersConfig, email::MockEmailClient};
+use tempered_auth_service::SecurityHeadersLayer;
+
+use crate::helpers::{in_memory_app, spawn_app};
+
+#[tokio::test]
+async fn security_headers_are_added_by_default() {
+    let address = spawn_app(in_memory_app(MockEmailClient)).await;
+
+    let response = reqwest::get(format!("{address}/session"))
+        .await
+        .expect("Failed to execute request");
+    let headers = response.headers();
+    assert_eq!(
+        headers["strict-transport-security"],
+        "max-age=63072000; includeSubDomains"
+    );
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+    assert_eq!(headers["x-frame-options"], "DENY");
+    assert_eq!(headers["content-security-policy"], "frame-ancestors 'none'");
+    assert_eq!(headers["referrer-policy"], "no-referrer");
+}
+
+#[tokio::test]
+async fn security_headers_are_configurable() {
+    let config = SecurityHeadersConfig {
+        strict_transport_security: None,
+        frame_options: Some("SAMEORIGIN".to_owned()),
+        ..SecurityHeadersConfig::default()
+    };
+    let app = in_memory_app(MockEmailClient)
+        .with_security_headers(Some(SecurityHeadersLayer::new(&config)));
+    let address = spawn_app(app).await;
+
+    let response = reqwest::get(format!("{address}/session"))
+        .await
+        .expect("Failed to execute request");
+    let headers = response.headers();
+    assert!(headers.get("strict-transport-security").is_none());
+    assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+
+    let address = spawn_app(in_memory_app(MockEmailClient).with_security_headers(None)).await;
+    let response = reqwest::get(format!("{address}/session"))
+        .await
+        .expect("Failed to execute request");
+    assert!(response.headers().get("x-content-type-options").is_none());
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use reqwest::cookie::Jar;
+use secrecy::{ExposeSecret, Secret};
+use tempered_adapters::{
+    config::{AuthServiceSetting, JWT_COOKIE_NAME},
+    email::MockEmailClient,
+    http::SessionResponse,
+    persistence::{HashMapTwoFaCodeStore, HashMapUserStore, HashSetBannedTokenStore},
+};
+use tempered_application::{LocalJwtIssuer, TokenIssuer, TokenType};
+use tempered_auth_service::AuthService;
+use tempered_core::{User, UserStore};
+
+use crate::helpers::{in_memory_app, spawn_app};
+
+#[tokio::test]
+async fn session_returns_identity_for_valid_token() {
+    let address = spawn_app(in_memory_app(MockEmailClient)).await;
+    let client = reqwest::Client::builder()
+        .cookie_provider(Arc::new(Jar::default()))
+        .build()
+        .expect("Failed to build client");
+
+    let body = serde_json::json!({
+        "email": "session@example.com",
+        "password": "passwordpassword",
+        "requires2FA": false,
+    });
+    client
+        .post(format!("{address}/signup"))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request");
+    let response = client
+        .post(format!("{address}/login"))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = client
+        .get(format!("{address}/session"))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let session = response
+        .json::<SessionResponse>()
+        .await
+        .expect("Unable to parse session response");
+    assert_eq!(session.email, "session@example.com");
+    assert!(session.expires_in_seconds > 0);
+    assert!(!session.renewed);
+}
+
+#[tokio::test]
+async fn session_renews_token_inside_renewal_window() {
+    let user_store = HashMapUserStore::default();
+    let user = User::parse(
+        Secret::from("renewal@example.com".to_string()),
+        Secret::from("passwordpassword".to_string()),
+        false,
+    )
+    .unwrap();
+    user_store.add_user(user.clone()).await.unwrap();
+    let address = spawn_app(AuthService::new(
+        user_store,
+        HashSetBannedTokenStore::default(),
+        HashMapTwoFaCodeStore::default(),
+        MockEmailClient,
+        "./assets".to_string(),
+    ))
+    .await;
+    let config = AuthServiceSetting::load();
+    assert!(config.auth.jwt.renewal_window > 60);
+
+    // A token that expires within the renewal window
+    let issuer = LocalJwtIssuer::new(config.auth.jwt.secret.expose_secret().as_bytes());
+    let token = issuer
+        .issue(user.id(), 60, TokenType::Access, None, Default::default())
+        .await
+        .expect("Failed to issue token");
+
+    let response = reqwest::Client::new()
+        .get(format!("{address}/session"))
+        .header("Cookie", format!("{}={token}", *JWT_COOKIE_NAME))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let renewed_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == *JWT_COOKIE_NAME)
+        .expect("No renewed auth cookie");
+    assert_ne!(renewed_cookie.value(), token);
+    let claims = issuer.verify(renewed_cookie.value()).await.unwrap();
+    assert_eq!(claims.user_id().as_ref(), Some(user.id()));
+
+    let session = response
+        .json::<SessionResponse>()
+        .await
+        .expect("Unable to parse session response");
+    assert!(session.renewed);
+    assert_eq!(session.expires_in_seconds, config.auth.jwt.time_to_live);
+}
+
+#[tokio::test]
+async fn session_returns_400_without_token() {
+    let address = spawn_app(in_memory_app(MockEmailClient)).await;
+
+    let response = reqwest::get(format!("{address}/session"))
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status().as_u16(), 400);
+}
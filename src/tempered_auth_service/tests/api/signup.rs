@@ -0,0 +1,68 @@
+use tempered_adapters::{
+    config::JWT_COOKIE_NAME,
+    email::MockEmailClient,
+    http::routes::{SignupResponse, TwoFactorAuthResponse},
+};
+use tempered_auth_service::AuthService;
+
+use crate::helpers::{in_memory_app, spawn_app};
+
+async fn post_signup(app: AuthService, email: &str, requires_2fa: bool) -> reqwest::Response {
+    let address = spawn_app(app).await;
+    reqwest::Client::new()
+        .post(format!("{address}/signup"))
+        .json(&serde_json::json!({
+            "email": email,
+            "password": "passwordpassword",
+            "requires2FA": requires_2fa,
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request")
+}
+
+fn has_auth_cookie(response: &reqwest::Response) -> bool {
+    response
+        .cookies()
+        .any(|cookie| cookie.name() == *JWT_COOKIE_NAME)
+}
+
+#[tokio::test]
+async fn auto_login_sets_auth_cookie_on_signup() {
+    let app = in_memory_app(MockEmailClient).with_signup_auto_login(true);
+
+    let response = post_signup(app, "auto-login@example.com", false).await;
+    assert_eq!(response.status().as_u16(), 201);
+    assert!(has_auth_cookie(&response));
+
+    let body = response.json::<SignupResponse>().await.unwrap();
+    assert_eq!(body.email, "auto-login@example.com");
+}
+
+#[tokio::test]
+async fn auto_login_requires_2fa_instead_of_setting_cookie() {
+    let app = in_memory_app(MockEmailClient).with_signup_auto_login(true);
+
+    let response = post_signup(app, "auto-login-2fa@example.com", true).await;
+    assert_eq!(response.status().as_u16(), 206);
+    assert!(!has_auth_cookie(&response));
+
+    let body = response.json::<TwoFactorAuthResponse>().await.unwrap();
+    assert_eq!(body.message, "2FA required");
+    assert!(!body.attempt_id.is_empty());
+}
+
+#[tokio::test]
+async fn signup_without_auto_login_sets_no_cookie() {
+    for requires_2fa in [false, true] {
+        let app = in_memory_app(MockEmailClient).with_signup_auto_login(false);
+
+        let response = post_signup(app, "no-auto-login@example.com", requires_2fa).await;
+        assert_eq!(response.status().as_u16(), 201);
+        assert!(response.headers().get("set-cookie").is_none());
+
+        let body = response.json::<SignupResponse>().await.unwrap();
+        assert_eq!(body.requires_2fa, requires_2fa);
+        assert!(body.csrf_token.is_none());
+    }
+}
@@ -0,0 +1,62 @@
+use secrecy::Secret;
+use tempered_adapters::http::routes::TwoFactorAuthResponse;
+use tempered_auth_service::TestServer;
+use tempered_core::{Email, TwoFaCodeStore};
+
+#[tokio::test]
+async fn test_server_runs_signup_login_2fa_flow() {
+    let server = TestServer::spawn().await;
+
+    let body = serde_json::json!({
+        "email": "e2e@example.com",
+        "password": "passwordpassword",
+        "requires2FA": true,
+    });
+    let response = server
+        .client
+        .post(server.url("/signup"))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = server
+        .client
+        .post(server.url("/login"))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 206);
+    let two_fa_response = response.json::<TwoFactorAuthResponse>().await.unwrap();
+    assert_eq!(two_fa_response.expires_in_seconds, 600);
+
+    let email = Email::try_from(Secret::from("e2e@example.com".to_owned())).unwrap();
+    let (attempt_id, code) = server
+        .two_fa_code_store
+        .get_login_attempt_id_and_two_fa_code(&email)
+        .await
+        .expect("2FA code should be stored");
+
+    let response = server
+        .client
+        .post(server.url("/verify-2fa"))
+        .json(&serde_json::json!({
+            "email": "e2e@example.com",
+            "loginAttemptId": attempt_id.to_string(),
+            "2FACode": code.to_string(),
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = server
+        .client
+        .get(server.url("/session"))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 200);
+}
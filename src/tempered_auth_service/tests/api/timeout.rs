@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use tempered_core::{Email, EmailClient};
+
+use crate::helpers::{in_memory_app, spawn_app};
+
+#[derive(Clone)]
+struct SlowEmailClient;
+
+#[async_trait::async_trait]
+impl EmailClient for SlowEmailClient {
+    async fn send_email(
+        &self,
+        _recipient: &Email,
+        _subject: &str,
+        _content: &str,
+    ) -> Result<(), String> {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn should_return_504_when_request_times_out() {
+    let app = in_memory_app(SlowEmailClient).with_request_timeout(Some(Duration::from_millis(100)));
+    let address = spawn_app(app).await;
+
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("{address}/signup"))
+        .json(&serde_json::json!({
+            "email": "timeout@example.com",
+            "password": "passwordpassword",
+            "requires2FA": true,
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 201);
+
+    // Sending the 2FA email stalls, so login must be cut off by the timeout
+    let response = client
+        .post(format!("{address}/login"))
+        .json(&serde_json::json!({
+            "email": "timeout@example.com",
+            "password": "passwordpassword",
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(response.status().as_u16(), 504);
+}
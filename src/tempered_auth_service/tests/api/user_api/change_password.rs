@@ -1,6 +1,6 @@
 use tempered_adapters::auth::jwt::JWT_ELEVATED_COOKIE_NAME;
 
-use crate::helpers::{TestApp, get_standard_test_user};
+use crate::helpers::{TestApp, get_standard_test_user, with_scope};
 
 #[tokio::test]
 async fn should_return_200_with_valid_request_and_elevated_auth() {
@@ -17,7 +17,13 @@ async fn should_return_200_with_valid_request_and_elevated_auth() {
         200,
         "Failed to login"
     );
-    assert_eq!(app.post_elevate(&body).await.status().as_u16(), 200);
+    assert_eq!(
+        app.post_elevate(&with_scope(&body, "change_password"))
+            .await
+            .status()
+            .as_u16(),
+        200
+    );
 
     let new_password = serde_json::json!({
         "new_password": "newpassword123"
@@ -80,7 +86,13 @@ async fn should_return_400_with_invalid_password() {
         200,
         "Failed to login"
     );
-    assert_eq!(app.post_elevate(&user_body).await.status().as_u16(), 200);
+    assert_eq!(
+        app.post_elevate(&with_scope(&user_body, "change_password"))
+            .await
+            .status()
+            .as_u16(),
+        200
+    );
 
     let body = serde_json::json!({
         "new_password": "123"
@@ -105,7 +117,13 @@ async fn should_return_422_with_missing_password() {
         200,
         "Failed to login"
     );
-    assert_eq!(app.post_elevate(&user_body).await.status().as_u16(), 200);
+    assert_eq!(
+        app.post_elevate(&with_scope(&user_body, "change_password"))
+            .await
+            .status()
+            .as_u16(),
+        200
+    );
 
     // Test with empty body
     let body = serde_json::json!({});
@@ -129,7 +147,13 @@ async fn should_return_422_with_invalid_json() {
         200,
         "Failed to login"
     );
-    assert_eq!(app.post_elevate(&user_body).await.status().as_u16(), 200);
+    assert_eq!(
+        app.post_elevate(&with_scope(&user_body, "change_password"))
+            .await
+            .status()
+            .as_u16(),
+        200
+    );
 
     // Test with password as wrong type
     let body = serde_json::json!({
@@ -1,6 +1,6 @@
 use tempered_adapters::http::error::{AuthApiError, ErrorResponse};
 
-use crate::helpers::{TestApp, get_standard_test_user};
+use crate::helpers::{TestApp, get_standard_test_user, with_scope};
 
 #[tokio::test]
 pub async fn should_return_204_with_valid_elevated_auth_token() {
@@ -13,7 +13,14 @@ pub async fn should_return_204_with_valid_elevated_auth_token() {
 
     assert_eq!(client.login(&body).await.status().as_u16(), 200);
 
-    assert_eq!(client.post_elevate(&body).await.status().as_u16(), 200);
+    assert_eq!(
+        client
+            .post_elevate(&with_scope(&body, "delete_account"))
+            .await
+            .status()
+            .as_u16(),
+        200
+    );
 
     let user_deleted = client.delete_account().await;
     let status_code = user_deleted.status().as_u16();
@@ -22,6 +29,8 @@ pub async fn should_return_204_with_valid_elevated_auth_token() {
         .await
         .unwrap_or(ErrorResponse {
             error: "".to_owned(),
+            suggestions: Vec::new(),
+            login_attempt_id: None,
         })
         .error;
     println!("{error_message}");
@@ -56,7 +65,9 @@ pub async fn should_return_400_after_logout() {
 
     client.post_signup(&body).await;
     client.login(&body).await;
-    client.post_elevate(&body).await;
+    client
+        .post_elevate(&with_scope(&body, "delete_account"))
+        .await;
     client.logout().await;
 
     let response = client.delete_account().await;
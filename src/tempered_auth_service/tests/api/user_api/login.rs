@@ -97,7 +97,7 @@ async fn should_return_200_if_valid_credentials_and_2fa_enabled() {
         .received_requests()
         .await
         .expect("Request recording disabled")
-        .get(0)
+        .first()
         .expect("No email received")
         .body
         .clone();
@@ -234,7 +234,7 @@ async fn should_return_401_with_unregistered_email() {
 }
 
 #[tokio::test]
-async fn should_return_422_with_malformed_input() {
+async fn should_return_400_with_malformed_input() {
     let app = TestApp::new().await;
 
     assert!(
@@ -251,5 +251,13 @@ async fn should_return_422_with_malformed_input() {
 
     let response = app.login(&body).await;
 
-    assert_eq!(response.status().as_u16(), 422);
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Unable to parse error response")
+            .error,
+        "Invalid input: field 'email' is required"
+    );
 }
@@ -1,4 +1,5 @@
 use tempered_adapters::http::error::{AuthApiError, ErrorResponse};
+use tempered_adapters::http::routes::SignupResponse;
 use tempered_core::UserError;
 
 use crate::helpers::{TestApp, get_random_email};
@@ -16,6 +17,11 @@ async fn signup_should_return_201_with_valid_input() {
     let response = app.post_signup(&body).await;
 
     assert_eq!(response.status().as_u16(), 201);
+
+    let body = response.json::<SignupResponse>().await.unwrap();
+    assert_eq!(body.email, "test@example.com");
+    assert!(!body.requires_2fa);
+    assert!(!body.email_verification_sent);
 }
 
 #[tokio::test]
@@ -115,7 +121,7 @@ async fn signup_should_return_409_if_email_already_exists() {
 }
 
 #[tokio::test]
-async fn signup_returns_422_if_malformed_input() {
+async fn signup_returns_400_if_malformed_input() {
     let app = TestApp::new().await;
 
     let random_email = get_random_email();
@@ -153,6 +159,6 @@ async fn signup_returns_422_if_malformed_input() {
 
     for body in test_cases {
         let response = app.post_signup(&body).await;
-        assert_eq!(response.status().as_u16(), 422);
+        assert_eq!(response.status().as_u16(), 400);
     }
 }
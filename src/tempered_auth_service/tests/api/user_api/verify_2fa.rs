@@ -100,7 +100,7 @@ async fn should_return_400_with_invalid_input() {
 }
 
 #[tokio::test]
-async fn should_return_401_with_outdated_login_attempt_id() {
+async fn should_return_200_for_earlier_of_two_parallel_attempts() {
     let app = TestApp::new().await;
 
     let body = get_standard_test_user(true);
@@ -127,12 +127,41 @@ async fn should_return_401_with_outdated_login_attempt_id() {
     let two_fa_attempt_id =
         TwoFaAttemptId::parse(&two_fa_response.attempt_id).expect("Invalid attempt Id");
 
+    // A second login, e.g. on another device, leaves the first attempt valid
     assert!(app.login(&body).await.status().as_u16() == 206);
 
     let body = app
         .get_verify_two_fa_request(email, two_fa_attempt_id)
         .await;
 
+    let response = app.verify_2fa(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_401_with_unknown_login_attempt_id() {
+    let app = TestApp::new().await;
+
+    let body = get_standard_test_user(true);
+    assert!(app.post_signup(&body).await.status().is_success());
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.login(&body).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let email = body["email"]
+        .as_str()
+        .expect("Email was not of type String");
+    let body = app
+        .get_verify_two_fa_request(email, TwoFaAttemptId::new())
+        .await;
+
     let response = app.verify_2fa(&body).await;
 
     assert_eq!(response.status().as_u16(), 401);
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use reqwest::cookie::Jar;
+use tempered_adapters::email::MockEmailClient;
+
+use crate::helpers::{in_memory_app, spawn_app};
+
+#[tokio::test]
+async fn verify_password_checks_password_without_issuing_tokens() {
+    let address = spawn_app(in_memory_app(MockEmailClient)).await;
+    let jar = Arc::new(Jar::default());
+    let client = reqwest::Client::builder()
+        .cookie_provider(jar.clone())
+        .build()
+        .expect("Failed to build client");
+    let post = |path: &str, body: serde_json::Value| {
+        client.post(format!("{address}{path}")).json(&body).send()
+    };
+
+    let response = post(
+        "/verify-password",
+        serde_json::json!({ "password": "passwordpassword" }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status().as_u16(), 400);
+
+    let credentials = serde_json::json!({
+        "email": "confirm@example.com",
+        "password": "passwordpassword",
+        "requires2FA": false,
+    });
+    for path in ["/signup", "/login"] {
+        let response = post(path, credentials.clone()).await.unwrap();
+        assert!(response.status().is_success());
+    }
+
+    let response = post(
+        "/verify-password",
+        serde_json::json!({ "password": "wrongpassword" }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = post(
+        "/verify-password",
+        serde_json::json!({ "password": "passwordpassword" }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.headers().get("set-cookie").is_none());
+}
@@ -0,0 +1,102 @@
+use std::fmt::Display;
+
+use rand::{Rng, distr::Alphanumeric};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+use super::email::Email;
+
+const API_KEY_PREFIX: &str = "tmp_";
+const API_KEY_RANDOM_LENGTH: usize = 40;
+
+/// A plaintext API key. Only ever shown to the client once, when it is issued.
+#[derive(Debug, Clone)]
+pub struct ApiKey(Secret<String>);
+
+impl ApiKey {
+    pub fn new() -> Self {
+        let random: String = rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(API_KEY_RANDOM_LENGTH)
+            .map(char::from)
+            .collect();
+
+        ApiKey(Secret::new(format!("{API_KEY_PREFIX}{random}")))
+    }
+}
+
+impl Default for ApiKey {
+    fn default() -> Self {
+        ApiKey::new()
+    }
+}
+
+impl From<String> for ApiKey {
+    fn from(key: String) -> Self {
+        ApiKey(Secret::new(key))
+    }
+}
+
+impl AsRef<Secret<String>> for ApiKey {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
+
+impl ExposeSecret<String> for ApiKey {
+    fn expose_secret(&self) -> &String {
+        self.0.expose_secret()
+    }
+}
+
+/// Hash of an API key, the form in which keys are stored
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ApiKeyHash(String);
+
+impl ApiKeyHash {
+    pub fn new(hash: String) -> Self {
+        ApiKeyHash(hash)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for ApiKeyHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A stored API key: the key's hash, its owner and the scopes it grants
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKeyRecord {
+    pub key_hash: ApiKeyHash,
+    pub email: Email,
+    pub scopes: Vec<String>,
+}
+
+impl ApiKeyRecord {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_api_keys_are_unique_and_prefixed() {
+        let first = ApiKey::new();
+        let second = ApiKey::new();
+
+        assert!(first.expose_secret().starts_with(API_KEY_PREFIX));
+        assert_eq!(
+            first.expose_secret().len(),
+            API_KEY_PREFIX.len() + API_KEY_RANDOM_LENGTH
+        );
+        assert_ne!(first.expose_secret(), second.expose_secret());
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt::Display;
+
+use rand::seq::IndexedRandom;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+use super::two_fa_error::TwoFaError;
+
+// Lowercase letters and digits without the easily confused 0, 1, l and o
+const BACKUP_CODE_ALPHABET: &[u8] = b"23456789abcdefghijkmnpqrstuvwxyz";
+const BACKUP_CODE_GROUP_LENGTH: usize = 5;
+
+/// A plaintext 2FA backup code like `k7d2m-x9qpe`. Only ever shown to the user once,
+/// when the codes are generated.
+#[derive(Debug, Clone)]
+pub struct BackupCode(Secret<String>);
+
+impl BackupCode {
+    pub fn new() -> Self {
+        let mut rng = rand::rng();
+        let mut group = || -> String {
+            (0..BACKUP_CODE_GROUP_LENGTH)
+                .filter_map(|_| BACKUP_CODE_ALPHABET.choose(&mut rng))
+                .map(|&c| char::from(c))
+                .collect()
+        };
+        let first = group();
+        let second = group();
+
+        BackupCode(Secret::new(format!("{first}-{second}")))
+    }
+
+    /// Parse a user-entered code. Case, whitespace and the position of `-` don't matter.
+    pub fn parse(code: &str) -> Result<Self, TwoFaError> {
+        let code: String = code
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+
+        let valid = code.len() == 2 * BACKUP_CODE_GROUP_LENGTH
+            && code.bytes().all(|c| BACKUP_CODE_ALPHABET.contains(&c));
+        if !valid {
+            return Err(TwoFaError::InvalidTwoFaCode);
+        }
+
+        let (first, second) = code.split_at(BACKUP_CODE_GROUP_LENGTH);
+        Ok(BackupCode(Secret::new(format!("{first}-{second}"))))
+    }
+}
+
+impl Default for BackupCode {
+    fn default() -> Self {
+        BackupCode::new()
+    }
+}
+
+impl ExposeSecret<String> for BackupCode {
+    fn expose_secret(&self) -> &String {
+        self.0.expose_secret()
+    }
+}
+
+/// Hash of a backup code, the form in which codes are stored
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BackupCodeHash(String);
+
+impl BackupCodeHash {
+    pub fn new(hash: String) -> Self {
+        BackupCodeHash(hash)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for BackupCodeHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_backup_codes_parse() {
+        for _ in 0..100 {
+            let code = BackupCode::new();
+            let parsed = BackupCode::parse(code.expose_secret()).unwrap();
+            assert_eq!(parsed.expose_secret(), code.expose_secret());
+        }
+    }
+
+    #[test]
+    fn test_parse_normalizes() {
+        let code = BackupCode::parse(" K7D2M X9QPE\n").unwrap();
+        assert_eq!(code.expose_secret(), "k7d2m-x9qpe");
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_codes() {
+        for input in ["", "k7d2m", "k7d2m-x9qp", "k7d2m-x9qpe1", "k7d2m-x9q0e"] {
+            assert!(BackupCode::parse(input).is_err());
+        }
+    }
+}
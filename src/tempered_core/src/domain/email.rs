@@ -15,7 +15,7 @@ impl TryFrom<Secret<String>> for Email {
     type Error = UserError;
 
     fn try_from(email: Secret<String>) -> Result<Self, Self::Error> {
-        if !EMAIL_REGEX.is_match(&email.expose_secret()) {
+        if !EMAIL_REGEX.is_match(email.expose_secret()) {
             return Err(UserError::InvalidEmail);
         }
         Ok(Email(email))
@@ -34,11 +34,7 @@ impl AsRef<Secret<String>> for Email {
     }
 }
 
-impl Eq for Email {
-    fn assert_receiver_is_total_eq(&self) {
-        self.0.expose_secret().assert_receiver_is_total_eq();
-    }
-}
+impl Eq for Email {}
 
 impl Hash for Email {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
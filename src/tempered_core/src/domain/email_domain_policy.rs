@@ -0,0 +1,117 @@
+use std::{collections::HashSet, sync::Arc};
+
+use secrecy::ExposeSecret;
+
+use super::email::Email;
+
+/// Allowlist/denylist of email domains consulted at signup, e.g. to block
+/// disposable email providers
+///
+/// A domain also matches its subdomains. The default policy has empty lists
+/// and allows every domain.
+#[derive(Debug, Clone, Default)]
+pub struct EmailDomainPolicy {
+    allowed: Arc<HashSet<String>>,
+    denied: Arc<HashSet<String>>,
+}
+
+impl EmailDomainPolicy {
+    /// Create a policy from domain lists. A non-empty `allowed` list rejects
+    /// every domain not on it, `denied` domains are rejected either way.
+    pub fn new<A, D>(allowed: A, denied: D) -> Self
+    where
+        A: IntoIterator<Item = String>,
+        D: IntoIterator<Item = String>,
+    {
+        let normalize = |domain: String| domain.trim().trim_matches('.').to_lowercase();
+        Self {
+            allowed: Arc::new(allowed.into_iter().map(normalize).collect()),
+            denied: Arc::new(denied.into_iter().map(normalize).collect()),
+        }
+    }
+
+    /// Parse a domain list with one domain per line. Blank lines and lines
+    /// starting with `#` are skipped.
+    pub fn parse_list(contents: &str) -> Vec<String> {
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_owned)
+            .collect()
+    }
+
+    /// Whether users may sign up with `email`
+    pub fn allows(&self, email: &Email) -> bool {
+        let Some((_, domain)) = email.as_ref().expose_secret().rsplit_once('@') else {
+            return false;
+        };
+        let domain = domain.to_lowercase();
+
+        if !self.allowed.is_empty() && !Self::matches(&self.allowed, &domain) {
+            return false;
+        }
+        !Self::matches(&self.denied, &domain)
+    }
+
+    /// Whether `domain` or one of its parent domains is in `domains`
+    fn matches(domains: &HashSet<String>, domain: &str) -> bool {
+        let mut candidate = domain;
+        loop {
+            if domains.contains(candidate) {
+                return true;
+            }
+            match candidate.split_once('.') {
+                Some((_, parent)) => candidate = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::Secret;
+
+    use super::*;
+
+    fn email(address: &str) -> Email {
+        Email::try_from(Secret::from(address.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_default_allows_everything() {
+        let policy = EmailDomainPolicy::default();
+        assert!(policy.allows(&email("test@mailinator.com")));
+    }
+
+    #[test]
+    fn test_denylist() {
+        let policy = EmailDomainPolicy::new([], ["Mailinator.com".to_string()]);
+        assert!(!policy.allows(&email("test@mailinator.com")));
+        assert!(!policy.allows(&email("test@eu.MAILINATOR.com")));
+        assert!(policy.allows(&email("test@example.com")));
+        assert!(policy.allows(&email("test@notmailinator.com")));
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let policy = EmailDomainPolicy::new(
+            ["example.com".to_string()],
+            ["test.example.com".to_string()],
+        );
+        assert!(policy.allows(&email("test@example.com")));
+        assert!(policy.allows(&email("test@mail.example.com")));
+        assert!(!policy.allows(&email("test@test.example.com")));
+        assert!(!policy.allows(&email("test@example.org")));
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let list = "# disposable providers\nmailinator.com\n\n  guerrillamail.com  \n";
+        assert_eq!(
+            EmailDomainPolicy::parse_list(list),
+            ["mailinator.com", "guerrillamail.com"]
+        );
+    }
+}
@@ -1,6 +1,11 @@
+pub mod api_key;
+pub mod backup_code;
 pub mod email;
+pub mod email_domain_policy;
 pub mod password;
 pub mod two_fa_attempt_id;
 pub mod two_fa_code;
 pub mod two_fa_error;
 pub mod user;
+pub mod user_id;
+pub mod username;
@@ -27,6 +27,12 @@ impl Default for TwoFaAttemptId {
     }
 }
 
+impl From<Uuid> for TwoFaAttemptId {
+    fn from(id: Uuid) -> Self {
+        TwoFaAttemptId(id)
+    }
+}
+
 impl Display for TwoFaAttemptId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -19,11 +19,17 @@ impl TwoFaCode {
         TwoFaCode(code)
     }
 
+    /// Parse a user-entered code. Whitespace and `-` separators are dropped first,
+    /// so pasted codes like "123 456" or "123-456\n" are accepted.
     pub fn parse(code: String) -> Result<Self, TwoFaError> {
-        if code.len() != 6 || !code.chars().all(|c| c.is_numeric()) {
+        let code: String = code
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .collect();
+        if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
             Err(TwoFaError::InvalidTwoFaCode)
         } else {
-            Ok(TwoFaCode(code.to_string()))
+            Ok(TwoFaCode(code))
         }
     }
 }
@@ -55,4 +61,21 @@ mod tests {
             assert!(code.0.chars().all(|c| c.is_numeric()))
         }
     }
+
+    #[test]
+    fn test_parse_strips_separators() {
+        for input in ["123456", " 123456\n", "123 456", "123-456", "12 34 56"] {
+            assert_eq!(*TwoFaCode::parse(input.to_string()).unwrap(), "123456");
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_codes() {
+        for input in ["", "12345", "1234567", "12a456", "123_456", "１２３４５６"] {
+            assert!(matches!(
+                TwoFaCode::parse(input.to_string()),
+                Err(TwoFaError::InvalidTwoFaCode)
+            ));
+        }
+    }
 }
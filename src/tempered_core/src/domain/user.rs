@@ -1,7 +1,7 @@
 use secrecy::Secret;
 use thiserror::Error;
 
-use super::{email::Email, password::Password};
+use super::{email::Email, password::Password, user_id::UserId};
 
 #[derive(Debug, Error, PartialEq)]
 pub enum UserError {
@@ -9,10 +9,15 @@ pub enum UserError {
     InvalidEmail,
     #[error("Invalid Password: Must be at least 8 characters")]
     InvalidPassword,
+    #[error("Invalid Username: Must be 3 to 32 letters, digits, '.', '_' or '-'")]
+    InvalidUsername,
+    #[error("Invalid user id")]
+    InvalidUserId,
 }
 
 #[derive(Debug, Clone)]
 pub struct User {
+    pub id: UserId,
     pub email: Email,
     pub password: Password,
     pub requires_2fa: bool,
@@ -21,6 +26,7 @@ pub struct User {
 impl User {
     pub fn new(email: Email, password: Password, requires_2fa: bool) -> Self {
         User {
+            id: UserId::new(),
             email,
             password,
             requires_2fa,
@@ -33,12 +39,23 @@ impl User {
         requires_2fa: bool,
     ) -> Result<Self, UserError> {
         Ok(User {
+            id: UserId::new(),
             email: Email::try_from(email)?,
             password: Password::try_from(password)?,
             requires_2fa,
         })
     }
 
+    /// Replace the freshly generated id, e.g. with the one a store loaded the user with
+    pub fn with_id(mut self, id: UserId) -> Self {
+        self.id = id;
+        self
+    }
+
+    pub fn id(&self) -> &UserId {
+        &self.id
+    }
+
     pub fn email(&self) -> &Email {
         &self.email
     }
@@ -0,0 +1,108 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use secrecy::{ExposeSecret, Secret};
+
+use super::{email::Email, user::UserError};
+
+// 3 to 32 characters, starting with a letter or digit
+const USERNAME_REGEX_PATTERN: &str = r"^[a-zA-Z0-9][a-zA-Z0-9._-]{2,31}$";
+static USERNAME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(USERNAME_REGEX_PATTERN).unwrap());
+
+/// A username, case-insensitive and therefore kept lowercased
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Username(String);
+
+impl TryFrom<String> for Username {
+    type Error = UserError;
+
+    fn try_from(username: String) -> Result<Self, Self::Error> {
+        if !USERNAME_REGEX.is_match(&username) {
+            return Err(UserError::InvalidUsername);
+        }
+        Ok(Username(username.to_lowercase()))
+    }
+}
+
+impl AsRef<str> for Username {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// What a user logs in with, either their email or their username
+#[derive(Debug, Clone, PartialEq)]
+pub enum Identifier {
+    Email(Email),
+    Username(Username),
+}
+
+impl Identifier {
+    /// Parse a login identifier, anything containing an `@` is treated as an email
+    pub fn parse(identifier: Secret<String>) -> Result<Self, UserError> {
+        if identifier.expose_secret().contains('@') {
+            Email::try_from(identifier).map(Identifier::Email)
+        } else {
+            Username::try_from(identifier.expose_secret().clone()).map(Identifier::Username)
+        }
+    }
+}
+
+impl From<Email> for Identifier {
+    fn from(email: Email) -> Self {
+        Identifier::Email(email)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_usernames() {
+        for username in ["bob", "alice_99", "j.doe-2", &"a".repeat(32)] {
+            assert!(
+                Username::try_from(username.to_string()).is_ok(),
+                "{username}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_usernames() {
+        for username in ["", "ab", "_bob", "bob smith", "bob@home", &"a".repeat(33)] {
+            assert_eq!(
+                Username::try_from(username.to_string()),
+                Err(UserError::InvalidUsername),
+                "{username}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_usernames_are_case_insensitive() {
+        let username = Username::try_from("Test_User".to_string()).unwrap();
+
+        assert_eq!(username.as_ref(), "test_user");
+        assert_eq!(
+            username,
+            Username::try_from("TEST_USER".to_string()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_identifier() {
+        let email = Identifier::parse(Secret::from("test@example.com".to_string())).unwrap();
+        assert!(matches!(email, Identifier::Email(_)));
+
+        let username = Identifier::parse(Secret::from("test_user".to_string())).unwrap();
+        assert_eq!(
+            username,
+            Identifier::Username(Username("test_user".to_string()))
+        );
+
+        let invalid = Identifier::parse(Secret::from("not@an-email".to_string()));
+        assert_eq!(invalid, Err(UserError::InvalidEmail));
+    }
+}
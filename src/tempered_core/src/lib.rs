@@ -3,18 +3,28 @@ pub mod ports;
 
 // Re-export commonly used types for convenience
 pub use domain::{
+    api_key::{ApiKey, ApiKeyHash, ApiKeyRecord},
+    backup_code::{BackupCode, BackupCodeHash},
     email::Email,
+    email_domain_policy::EmailDomainPolicy,
     password::Password,
     two_fa_attempt_id::TwoFaAttemptId,
     two_fa_code::TwoFaCode,
     two_fa_error::TwoFaError,
     user::{User, UserError, ValidatedUser},
+    user_id::UserId,
+    username::{Identifier, Username},
 };
 
 pub use ports::{
     repositories::{
-        BannedTokenStore, BannedTokenStoreError, TwoFaCodeStore, TwoFaCodeStoreError, UserStore,
-        UserStoreError,
+        ApiKeyStore, ApiKeyStoreError, BackupCodeStore, BackupCodeStoreError, BannedTokenStore,
+        BannedTokenStoreError, MAX_EMAIL_SEARCH_LIMIT, PASSWORD_HISTORY_LENGTH, ProbeableStore,
+        RateLimitStore, RateLimitStoreError, StoreProbe, TwoFaCodeStore, TwoFaCodeStoreError,
+        UserStore, UserStoreError,
+    },
+    services::{
+        AttemptIdGenerator, AuditEvent, AuditSink, EmailClient, EmailKind, NoopRegistrationHook,
+        PasswordStrength, PasswordStrengthEstimator, RegistrationHook, UuidAttemptIdGenerator,
     },
-    services::EmailClient,
 };
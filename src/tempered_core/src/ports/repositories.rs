@@ -1,12 +1,19 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
+use secrecy::Secret;
 use thiserror::Error;
 
 use crate::domain::{
+    api_key::{ApiKeyHash, ApiKeyRecord},
+    backup_code::BackupCodeHash,
     email::Email,
     password::Password,
     two_fa_attempt_id::TwoFaAttemptId,
     two_fa_code::TwoFaCode,
     user::{User, ValidatedUser},
+    user_id::UserId,
+    username::{Identifier, Username},
 };
 
 // UserStore port trait and errors
@@ -18,25 +25,53 @@ pub enum UserStoreError {
     UserNotFound,
     #[error("Incorrect password")]
     IncorrectPassword,
+    #[error("Invalid password hash: {0}")]
+    InvalidPasswordHash(String),
+    #[error("Invalid search: {0}")]
+    InvalidSearch(String),
     #[error("Unexpected error {0}")]
     UnexpectedError(String),
 }
 
 impl PartialEq for UserStoreError {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::UserAlreadyExists, Self::UserAlreadyExists) => true,
-            (Self::UserNotFound, Self::UserNotFound) => true,
-            (Self::IncorrectPassword, Self::IncorrectPassword) => true,
-            (Self::UnexpectedError(_), Self::UnexpectedError(_)) => true,
-            _ => false,
-        }
+        matches!(
+            (self, other),
+            (Self::UserAlreadyExists, Self::UserAlreadyExists)
+                | (Self::UserNotFound, Self::UserNotFound)
+                | (Self::IncorrectPassword, Self::IncorrectPassword)
+                | (Self::InvalidPasswordHash(_), Self::InvalidPasswordHash(_))
+                | (Self::InvalidSearch(_), Self::InvalidSearch(_))
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
     }
 }
 
+/// Upper bound on the number of results of [`UserStore::search_by_email_prefix`]
+pub const MAX_EMAIL_SEARCH_LIMIT: usize = 100;
+
+/// Number of previous passwords kept per user by stores with a password history,
+/// see [`UserStore::is_password_reused`]
+pub const PASSWORD_HISTORY_LENGTH: usize = 5;
+
 #[async_trait]
 pub trait UserStore: Send + Sync {
     async fn add_user(&self, user: User) -> Result<(), UserStoreError>;
+
+    /// Add a user whose password has already been hashed elsewhere, e.g. when
+    /// importing users from another system. The hash is stored verbatim.
+    /// Stores that cannot verify externally produced hashes keep this default.
+    async fn add_user_with_hash(
+        &self,
+        email: Email,
+        password_hash: Secret<String>,
+        requires_2fa: bool,
+    ) -> Result<(), UserStoreError> {
+        let _ = (email, password_hash, requires_2fa);
+        Err(UserStoreError::UnexpectedError(
+            "Importing pre-hashed passwords is not supported by this store".to_string(),
+        ))
+    }
     async fn set_new_password(
         &self,
         email: &Email,
@@ -48,7 +83,78 @@ pub trait UserStore: Send + Sync {
         password: &Password,
     ) -> Result<ValidatedUser, UserStoreError>;
     async fn get_user(&self, email: &Email) -> Result<User, UserStoreError>;
+
+    /// Look a user up by their [`UserId`], which stays the same when their email changes.
+    /// Stores that don't keep user ids keep this default.
+    async fn get_user_by_id(&self, id: &UserId) -> Result<User, UserStoreError> {
+        let _ = id;
+        Err(UserStoreError::UnexpectedError(
+            "Looking users up by id is not supported by this store".to_string(),
+        ))
+    }
     async fn delete_user(&self, user: &Email) -> Result<(), UserStoreError>;
+
+    /// Enable or disable 2FA for an existing user.
+    /// Stores that cannot change the setting after signup keep this default.
+    async fn set_requires_2fa(
+        &self,
+        email: &Email,
+        requires_2fa: bool,
+    ) -> Result<(), UserStoreError> {
+        let _ = (email, requires_2fa);
+        Err(UserStoreError::UnexpectedError(
+            "Changing the 2FA setting is not supported by this store".to_string(),
+        ))
+    }
+
+    /// Find users whose email starts with `prefix`, sorted by email, e.g. for admin autocomplete.
+    /// Empty prefixes are rejected and `limit` is capped at [`MAX_EMAIL_SEARCH_LIMIT`].
+    async fn search_by_email_prefix(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<Email>, UserStoreError> {
+        let _ = (prefix, limit);
+        Err(UserStoreError::UnexpectedError(
+            "Searching users is not supported by this store".to_string(),
+        ))
+    }
+
+    /// Authenticate a user by email or username.
+    /// Stores without usernames keep this default, which only accepts emails.
+    async fn authenticate_user_by_identifier(
+        &self,
+        identifier: &Identifier,
+        password: &Password,
+    ) -> Result<ValidatedUser, UserStoreError> {
+        match identifier {
+            Identifier::Email(email) => self.authenticate_user(email, password).await,
+            Identifier::Username(_) => Err(UserStoreError::UnexpectedError(
+                "Logging in by username is not supported by this store".to_string(),
+            )),
+        }
+    }
+
+    /// Whether `candidate` is the user's current password or one of their last
+    /// [`PASSWORD_HISTORY_LENGTH`] passwords. Stores with a history record the replaced
+    /// password in `set_new_password`. Stores without one keep this default, which
+    /// never reports reuse.
+    async fn is_password_reused(
+        &self,
+        email: &Email,
+        candidate: &Password,
+    ) -> Result<bool, UserStoreError> {
+        let _ = (email, candidate);
+        Ok(false)
+    }
+
+    /// Assign a username to an existing user, failing with `UserAlreadyExists` if it is taken
+    async fn set_username(&self, email: &Email, username: &Username) -> Result<(), UserStoreError> {
+        let _ = (email, username);
+        Err(UserStoreError::UnexpectedError(
+            "Usernames are not supported by this store".to_string(),
+        ))
+    }
 }
 
 // BannedTokenStore port trait and errors
@@ -62,6 +168,66 @@ pub enum BannedTokenStoreError {
 pub trait BannedTokenStore: Send + Sync {
     async fn ban_token(&self, token: String) -> Result<(), BannedTokenStoreError>;
     async fn contains_token(&self, token: &str) -> Result<bool, BannedTokenStoreError>;
+
+    /// Ban a token until `expires_at` (unix timestamp in seconds), after which
+    /// the token is invalid anyway and the ban entry may be dropped.
+    /// Stores without expiring entries fall back to `ban_token`.
+    async fn ban_token_until(
+        &self,
+        token: String,
+        expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        let _ = expires_at;
+        self.ban_token(token).await
+    }
+
+    /// Ban a token until `expires_at` unless it is banned already, e.g. to redeem a
+    /// single-use token. Returns whether this call banned it.
+    ///
+    /// The default implementation checks and bans in two steps, so two concurrent
+    /// calls can both succeed. Stores should override it with an atomic insert.
+    async fn ban_token_if_absent(
+        &self,
+        token: String,
+        expires_at: u64,
+    ) -> Result<bool, BannedTokenStoreError> {
+        if self.contains_token(&token).await? {
+            return Ok(false);
+        }
+        self.ban_token_until(token, expires_at).await?;
+        Ok(true)
+    }
+
+    /// Number of tokens currently banned, for monitoring the size of the ban list
+    async fn banned_count(&self) -> Result<usize, BannedTokenStoreError> {
+        Err(BannedTokenStoreError::DatabaseError(
+            "Counting banned tokens is not supported by this store".to_owned(),
+        ))
+    }
+
+    /// Reject every token of `user` issued at or before `revoked_at` (unix timestamp
+    /// in seconds). The cutoff may be dropped after `expires_at`, once none of those
+    /// tokens can still be valid.
+    async fn revoke_user_tokens(
+        &self,
+        user: &UserId,
+        revoked_at: u64,
+        expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        let _ = (user, revoked_at, expires_at);
+        Err(BannedTokenStoreError::DatabaseError(
+            "Revoking all tokens of a user is not supported by this store".to_owned(),
+        ))
+    }
+
+    /// When all of `user`'s tokens were last revoked, if ever
+    async fn user_tokens_revoked_at(
+        &self,
+        user: &UserId,
+    ) -> Result<Option<u64>, BannedTokenStoreError> {
+        let _ = user;
+        Ok(None)
+    }
 }
 
 // TwoFaCodeStore port trait and errors
@@ -73,6 +239,11 @@ pub enum TwoFaCodeStoreError {
     InvalidAttemptId,
     #[error("Invalid 2FA code")]
     Invalid2FACode,
+    #[error("2FA code has already been used")]
+    CodeAlreadyUsed,
+    /// The attempt existed but its code is no longer valid, the user has to log in again
+    #[error("2FA attempt has expired")]
+    AttemptExpired,
     #[error("Unexpected error")]
     UnexpectedError(String),
 }
@@ -80,24 +251,34 @@ pub enum TwoFaCodeStoreError {
 #[cfg(debug_assertions)]
 impl PartialEq for TwoFaCodeStoreError {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::UserNotFound, Self::UserNotFound) => true,
-            (Self::InvalidAttemptId, Self::InvalidAttemptId) => true,
-            (Self::Invalid2FACode, Self::Invalid2FACode) => true,
-            (Self::UnexpectedError(_), Self::UnexpectedError(_)) => true,
-            _ => false,
-        }
+        matches!(
+            (self, other),
+            (Self::UserNotFound, Self::UserNotFound)
+                | (Self::InvalidAttemptId, Self::InvalidAttemptId)
+                | (Self::Invalid2FACode, Self::Invalid2FACode)
+                | (Self::CodeAlreadyUsed, Self::CodeAlreadyUsed)
+                | (Self::AttemptExpired, Self::AttemptExpired)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
     }
 }
 
 #[async_trait]
 pub trait TwoFaCodeStore: Send + Sync {
+    /// Store the code for a new login attempt.
+    ///
+    /// Attempts are keyed by user and attempt id, so a user logging in on several
+    /// devices at once has one valid code per attempt. Storing a new attempt leaves
+    /// the user's other pending attempts untouched.
     async fn store_code(
         &self,
         user_id: Email,
         login_attempt_id: TwoFaAttemptId,
         two_fa_code: TwoFaCode,
     ) -> Result<(), TwoFaCodeStoreError>;
+    /// Check the code of one attempt. Returns `UserNotFound` if the user has no pending
+    /// attempts, `InvalidAttemptId` if none of them has `login_attempt_id` and
+    /// `AttemptExpired` if the store still knows the attempt but its code has expired.
     async fn validate(
         &self,
         user_id: &Email,
@@ -105,10 +286,140 @@ pub trait TwoFaCodeStore: Send + Sync {
         two_fa_code: &TwoFaCode,
     ) -> Result<(), TwoFaCodeStoreError>;
 
+    /// The user's most recent pending attempt. Stores that only keep hashed codes
+    /// can't return the code and fail with `UnexpectedError`.
     async fn get_login_attempt_id_and_two_fa_code(
         &self,
         user_id: &Email,
     ) -> Result<(TwoFaAttemptId, TwoFaCode), TwoFaCodeStoreError>;
 
+    /// Discard all of the user's pending attempts
     async fn delete(&self, user_id: &Email) -> Result<(), TwoFaCodeStoreError>;
+
+    /// Validate the code of one attempt and delete it, so each code can be used exactly
+    /// once. If a concurrent call already consumed the code, this returns `CodeAlreadyUsed`.
+    ///
+    /// The default implementation only sees the most recent attempt and is not atomic,
+    /// a concurrent `store_code` between the read and the delete is lost. Stores should
+    /// override it with an atomic compare-and-delete of the given attempt.
+    async fn consume(
+        &self,
+        user_id: &Email,
+        login_attempt_id: &TwoFaAttemptId,
+        two_fa_code: &TwoFaCode,
+    ) -> Result<(), TwoFaCodeStoreError> {
+        let (stored_attempt_id, stored_code) =
+            self.get_login_attempt_id_and_two_fa_code(user_id).await?;
+
+        if stored_attempt_id != *login_attempt_id {
+            return Err(TwoFaCodeStoreError::InvalidAttemptId);
+        }
+        if stored_code != *two_fa_code {
+            return Err(TwoFaCodeStoreError::Invalid2FACode);
+        }
+
+        // The code was there a moment ago, so a missing entry means someone else consumed it
+        self.delete(user_id).await.map_err(|e| match e {
+            TwoFaCodeStoreError::UserNotFound => TwoFaCodeStoreError::CodeAlreadyUsed,
+            e => e,
+        })
+    }
+
+    /// Delete one attempt without checking its code, for a login completed another
+    /// way such as with a backup code. Errors like `consume` for unknown or used attempts.
+    ///
+    /// The default implementation reads the code back, stores that hash codes must
+    /// override it.
+    async fn discard_attempt(
+        &self,
+        user_id: &Email,
+        login_attempt_id: &TwoFaAttemptId,
+    ) -> Result<(), TwoFaCodeStoreError> {
+        let (stored_attempt_id, stored_code) =
+            self.get_login_attempt_id_and_two_fa_code(user_id).await?;
+
+        if stored_attempt_id != *login_attempt_id {
+            return Err(TwoFaCodeStoreError::InvalidAttemptId);
+        }
+        self.consume(user_id, login_attempt_id, &stored_code).await
+    }
+}
+
+// ApiKeyStore port trait and errors
+#[derive(Debug, Error, PartialEq)]
+pub enum ApiKeyStoreError {
+    #[error("API key not found")]
+    KeyNotFound,
+    #[error("API key already exists")]
+    KeyAlreadyExists,
+    #[error("Unexpected error {0}")]
+    UnexpectedError(String),
+}
+
+/// Storage for API keys. Keys are only ever stored and looked up by their hash.
+#[async_trait]
+pub trait ApiKeyStore: Send + Sync {
+    async fn add_key(&self, record: ApiKeyRecord) -> Result<(), ApiKeyStoreError>;
+    async fn get_key(&self, key_hash: &ApiKeyHash) -> Result<ApiKeyRecord, ApiKeyStoreError>;
+    async fn delete_key(&self, key_hash: &ApiKeyHash) -> Result<(), ApiKeyStoreError>;
+}
+
+// BackupCodeStore port trait and errors
+#[derive(Debug, Error, PartialEq)]
+pub enum BackupCodeStoreError {
+    #[error("Backup code not found")]
+    CodeNotFound,
+    #[error("Unexpected error {0}")]
+    UnexpectedError(String),
+}
+
+/// Storage for one-time 2FA backup codes per user, only ever stored by their hash
+#[async_trait]
+pub trait BackupCodeStore: Send + Sync {
+    /// Replace all of the user's backup codes with `code_hashes`
+    async fn replace_codes(
+        &self,
+        user: &Email,
+        code_hashes: Vec<BackupCodeHash>,
+    ) -> Result<(), BackupCodeStoreError>;
+
+    /// Remove a code so it can't be used again, `CodeNotFound` if the user doesn't have it
+    async fn consume_code(
+        &self,
+        user: &Email,
+        code_hash: &BackupCodeHash,
+    ) -> Result<(), BackupCodeStoreError>;
+
+    /// Number of unused codes the user has left
+    async fn remaining_codes(&self, user: &Email) -> Result<usize, BackupCodeStoreError>;
+}
+
+// RateLimitStore port trait and errors
+#[derive(Debug, Error, PartialEq)]
+pub enum RateLimitStoreError {
+    #[error("Unexpected error {0}")]
+    UnexpectedError(String),
+}
+
+/// Fixed-window hit counters for rate limiting, keyed by e.g. an action and an email
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Record a hit for `key` and return the number of hits in the current window,
+    /// including this one. The first hit for a key starts a window of length `window`.
+    async fn hit(&self, key: &str, window: Duration) -> Result<u64, RateLimitStoreError>;
+}
+
+/// Result of a [`ProbeableStore::probe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoreProbe {
+    pub healthy: bool,
+    /// Round-trip time of the probe, also set when it failed
+    pub latency: Duration,
+}
+
+/// Store backed by an external dependency that can be checked for readiness
+#[async_trait]
+pub trait ProbeableStore: Send + Sync {
+    /// Run a trivial command against the backend, e.g. `SELECT 1` or `PING`, and time it
+    async fn probe(&self) -> StoreProbe;
 }
@@ -1,6 +1,18 @@
 use async_trait::async_trait;
 
-use crate::domain::email::Email;
+use crate::domain::{email::Email, password::Password, two_fa_attempt_id::TwoFaAttemptId};
+
+/// What an email sent by the auth service is for, so clients that render
+/// templates can pick the right one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailKind {
+    /// `content` is the bare 2FA code
+    TwoFaCode,
+    /// `content` is the password reset link
+    PasswordReset,
+    /// `content` is the email verification link
+    EmailVerify,
+}
 
 /// Port trait for email sending service
 #[async_trait]
@@ -11,4 +23,95 @@ pub trait EmailClient: Send + Sync {
         subject: &str,
         content: &str,
     ) -> Result<(), String>;
+
+    /// Send an email of `kind`. The default implementation ignores the kind and
+    /// sends `content` as is with [`EmailClient::send_email`].
+    async fn send_email_of_kind(
+        &self,
+        kind: EmailKind,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), String> {
+        let _ = kind;
+        self.send_email(recipient, subject, content).await
+    }
+}
+
+/// Port trait for custom logic that runs after a new user has been persisted,
+/// e.g. creating a profile row or sending a welcome email
+#[async_trait]
+pub trait RegistrationHook: Send + Sync {
+    async fn after_register(&self, email: &Email) -> Result<(), String>;
+}
+
+/// Registration hook that does nothing, used when no hook is configured
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopRegistrationHook;
+
+#[async_trait]
+impl RegistrationHook for NoopRegistrationHook {
+    async fn after_register(&self, _email: &Email) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Port trait for generating 2FA login attempt IDs, e.g. to correlate attempts
+/// with an external system
+pub trait AttemptIdGenerator: Send + Sync {
+    fn generate(&self, email: &Email) -> TwoFaAttemptId;
+}
+
+impl<G: AttemptIdGenerator + ?Sized> AttemptIdGenerator for std::sync::Arc<G> {
+    fn generate(&self, email: &Email) -> TwoFaAttemptId {
+        (**self).generate(email)
+    }
+}
+
+/// Attempt ID generator that uses random v4 UUIDs, used when no generator is configured
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidAttemptIdGenerator;
+
+impl AttemptIdGenerator for UuidAttemptIdGenerator {
+    fn generate(&self, _email: &Email) -> TwoFaAttemptId {
+        TwoFaAttemptId::new()
+    }
+}
+
+/// Estimated strength of a password, on zxcvbn's scale from 0 (too guessable)
+/// to 4 (very unguessable)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PasswordStrength {
+    pub score: u8,
+    /// Advice for choosing a stronger password, shown to the user on rejection
+    pub suggestions: Vec<String>,
+}
+
+/// Port trait for estimating password strength, e.g. with zxcvbn
+pub trait PasswordStrengthEstimator: Send + Sync {
+    /// Estimate `password`'s strength. `user_inputs` are values an attacker would
+    /// try first, like the user's email, and should lower the score when reused.
+    fn estimate(&self, password: &Password, user_inputs: &[&str]) -> PasswordStrength;
+}
+
+/// Security relevant event recorded through an [`AuditSink`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditEvent {
+    /// A token was issued for `subject` without their credentials, on behalf of `actor`,
+    /// e.g. a support agent or an SSO bridge
+    Impersonation { subject: Email, actor: String },
+    /// Every token of `subject` was revoked by `actor`, e.g. a support agent
+    ForcedLogout { subject: Email, actor: String },
+    /// An email to `recipient` that was sent in the background couldn't be delivered
+    EmailDeliveryFailed {
+        recipient: Email,
+        subject: String,
+        error: String,
+    },
+}
+
+/// Port trait for recording audit events, e.g. to an audit log table or a SIEM
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    async fn record(&self, event: AuditEvent);
 }